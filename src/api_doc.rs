@@ -1,8 +1,22 @@
+use anyhow::{Context, Result};
+use axum::{routing::get, Json, Router};
+use utoipa::openapi::server::Server;
 use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 use crate::error::{ErrorResponse, HealthResponse, UnhealthyResponse};
 use crate::handlers;
-use crate::models::{GetResponse, KvEntryResponse, ListResponse, PutResponse};
+use crate::routes;
+use crate::spanner::{MatchMode, SortOrder};
+use crate::jobs::{JobCounts, JobInfo, JobStatus};
+use crate::models::{
+    BatchDeleteRequest, BatchDeleteResponse, BatchEntryStatus, BatchGetRequest, BatchGetResponse,
+    BatchPutEntry, BatchPutResponse, BatchPutResult, CopyRequest, CopyResponse, CountResponse,
+    DdlResponse, DeletePrefixResponse, DeleteResponse, DryRunResponse, ExportRecord, GetResponse,
+    HistoryEntryResponse, HistoryResponse, ImportLineError, ImportResponse, JobListResponse,
+    KvEntryResponse, KvMetaResponse, ListResponse, MoveRequest, PutResponse, RenameRequest,
+    RenameResponse, UndeleteResponse,
+};
 
 /// OpenAPI documentation
 #[derive(OpenApi)]
@@ -12,26 +26,200 @@ use crate::models::{GetResponse, KvEntryResponse, ListResponse, PutResponse};
         version = "1.0.0",
         description = "A simple JSON key-value store backed by Google Cloud Spanner"
     ),
+    servers(
+        (url = "/", description = "The server serving this document")
+    ),
     paths(
-        handlers::health::health_handler,
+        handlers::health::liveness_handler,
+        handlers::health::readiness_handler,
+        handlers::metrics::metrics_handler,
         handlers::put::put_handler,
+        handlers::create::create_handler,
         handlers::get::get_handler,
-        handlers::list::list_handler
+        handlers::head::head_handler,
+        handlers::batch::batch_put_handler,
+        handlers::batch_get::batch_get_handler,
+        handlers::batch_delete::batch_delete_handler,
+        handlers::delete::delete_handler,
+        handlers::undelete::undelete_handler,
+        handlers::delete_prefix::delete_prefix_handler,
+        handlers::patch::patch_handler,
+        handlers::list::list_handler,
+        handlers::count::count_handler,
+        handlers::secondary::secondary_key_handler,
+        handlers::export::export_handler,
+        handlers::import::import_handler,
+        handlers::stream::stream_handler,
+        handlers::rename::rename_handler,
+        handlers::rename::move_handler,
+        handlers::copy::copy_handler,
+        handlers::history::history_handler,
+        handlers::meta::meta_handler,
+        handlers::path::path_handler,
+        handlers::path::put_path_handler,
+        handlers::ddl::ddl_handler,
+        handlers::jobs::list_jobs_handler,
+        handlers::jobs::get_job_handler,
+        handlers::jobs::cancel_job_handler
     ),
     components(
         schemas(
             PutResponse,
+            DryRunResponse,
+            RenameRequest,
+            RenameResponse,
+            MoveRequest,
+            CopyRequest,
+            CopyResponse,
+            HistoryResponse,
+            HistoryEntryResponse,
+            KvMetaResponse,
             GetResponse,
             ListResponse,
             KvEntryResponse,
+            ExportRecord,
+            ImportResponse,
+            ImportLineError,
+            CountResponse,
             ErrorResponse,
             HealthResponse,
-            UnhealthyResponse
+            UnhealthyResponse,
+            BatchDeleteRequest,
+            BatchDeleteResponse,
+            BatchEntryStatus,
+            BatchGetRequest,
+            BatchGetResponse,
+            BatchPutEntry,
+            BatchPutResponse,
+            BatchPutResult,
+            DdlResponse,
+            DeletePrefixResponse,
+            DeleteResponse,
+            UndeleteResponse,
+            JobListResponse,
+            JobInfo,
+            JobCounts,
+            JobStatus,
+            SortOrder,
+            MatchMode
         )
     ),
     tags(
         (name = "health", description = "Health check operations"),
-        (name = "kv", description = "Key-value store operations")
+        (name = "kv", description = "Key-value store operations"),
+        (name = "admin", description = "Operator endpoints, authenticated with the ADMIN_TOKEN bearer token")
     )
 )]
 pub struct ApiDoc;
+
+/// The OpenAPI document, advertising `public_base_url` as its server when set
+///
+/// Without a public base URL the document keeps its relative `/` server, which
+/// tools resolve against wherever they fetched it from.
+pub fn openapi(public_base_url: Option<&str>) -> utoipa::openapi::OpenApi {
+    let mut doc = ApiDoc::openapi();
+    if let Some(url) = public_base_url {
+        doc.servers = Some(vec![Server::new(url)]);
+    }
+    doc
+}
+
+/// Routes serving the OpenAPI document at `/openapi.json` and through Swagger UI
+pub fn router<S>(public_base_url: Option<&str>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let doc = openapi(public_base_url);
+    let served = doc.clone();
+    Router::new()
+        .route(routes::OPENAPI, get(move || async move { Json(served) }))
+        .merge(SwaggerUi::new("/swagger-ui").url("/api-doc/openapi.json", doc))
+}
+
+/// Write the OpenAPI document as pretty-printed JSON to the given path
+pub fn write_openapi(path: &str, public_base_url: Option<&str>) -> Result<()> {
+    let json = openapi(public_base_url)
+        .to_pretty_json()
+        .context("Failed to serialize OpenAPI document")?;
+
+    std::fs::write(path, json)
+        .with_context(|| format!("Failed to write OpenAPI document to {}", path))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_openapi() {
+        let path = std::env::temp_dir().join(format!("openapi-{}.json", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+
+        write_openapi(path, None).unwrap();
+
+        let contents = std::fs::read_to_string(path).unwrap();
+        let doc: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(doc["info"]["title"], "rust-spanner-kv API");
+        assert!(doc["paths"]["/kv/{id}"].is_object());
+        assert!(doc["paths"]["/health/live"].is_object());
+        assert!(doc["paths"]["/health/ready"].is_object());
+        assert_eq!(doc["servers"], serde_json::json!([{"url": "/", "description": "The server serving this document"}]));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_openapi_enumerates_sort_orders_and_match_modes() {
+        let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let names: Vec<&str> = SortOrder::ALL.iter().map(|sort| sort.name()).collect();
+        assert_eq!(doc["components"]["schemas"]["SortOrder"]["enum"], serde_json::json!(names));
+
+        let names: Vec<&str> = MatchMode::ALL.iter().map(|mode| mode.name()).collect();
+        assert_eq!(doc["components"]["schemas"]["MatchMode"]["enum"], serde_json::json!(names));
+    }
+
+    #[test]
+    fn test_write_openapi_invalid_path() {
+        let result = write_openapi("/nonexistent-dir/openapi.json", None);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("/nonexistent-dir/openapi.json"));
+    }
+
+    #[tokio::test]
+    async fn test_openapi_route_lists_public_base_url() {
+        use axum::{body::Body, http::Request, http::StatusCode};
+        use tower::ServiceExt;
+
+        let app: Router = router(Some("https://kv.example.com"));
+
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/openapi.json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let doc: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let servers: Vec<&str> = doc["servers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|server| server["url"].as_str().unwrap())
+            .collect();
+        assert_eq!(servers, vec!["https://kv.example.com"]);
+        assert!(doc["paths"]["/kv/{id}"].is_object());
+
+        // The Swagger UI copy is the same document
+        let response = app
+            .oneshot(Request::builder().uri("/api-doc/openapi.json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let swagger_doc: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(swagger_doc, doc);
+    }
+}
@@ -0,0 +1,220 @@
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+
+/// Serialize a JSON document in canonical form, following RFC 8785 (JCS)
+///
+/// Object members are sorted by the UTF-16 code units of their keys, strings use
+/// the minimal JSON escaping, and floating point numbers use the ECMAScript
+/// shortest round-trip format. Integers that fit in `i64`/`u64` are written
+/// exactly instead of being converted to doubles, matching how the store keeps
+/// them, so two documents that read back differently never share a form.
+pub fn canonicalize(value: &JsonValue) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+/// Fingerprint of a JSON document: lowercase hex SHA-256 of its canonical form
+///
+/// Use this wherever documents are compared by content (ETags, skipping
+/// unchanged writes, sync) so every feature agrees on what "the same" means.
+pub fn content_hash(value: &JsonValue) -> String {
+    Sha256::digest(canonicalize(value).as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn write_value(out: &mut String, value: &JsonValue) {
+    match value {
+        JsonValue::Null => out.push_str("null"),
+        JsonValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        JsonValue::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                out.push_str(&i.to_string());
+            } else if let Some(u) = n.as_u64() {
+                out.push_str(&u.to_string());
+            } else if let Some(f) = n.as_f64() {
+                write_f64(out, f);
+            }
+        }
+        JsonValue::String(s) => write_string(out, s),
+        JsonValue::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        JsonValue::Object(map) => {
+            let mut members: Vec<_> = map.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            out.push('{');
+            for (i, (key, item)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, item);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// Write a string with the minimal escaping RFC 8785 requires
+fn write_string(out: &mut String, s: &str) {
+    // serde_json escapes exactly the characters JCS requires, in the same way
+    out.push_str(&serde_json::to_string(s).unwrap_or_default());
+}
+
+/// Write a double in the ECMAScript `Number.prototype.toString` format
+fn write_f64(out: &mut String, f: f64) {
+    if f == 0.0 {
+        // Covers -0, which ECMAScript also prints as 0
+        out.push('0');
+        return;
+    }
+    if f < 0.0 {
+        out.push('-');
+    }
+
+    // Rust's exponent format yields the shortest round-trip digits, e.g. "1.2345e-7"
+    let scientific = format!("{:e}", f.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+
+    // The value is 0.DIGITS x 10^point
+    let k = digits.len() as i32;
+    let point = exponent + 1;
+
+    if k <= point && point <= 21 {
+        out.push_str(&digits);
+        out.extend(std::iter::repeat_n('0', (point - k) as usize));
+    } else if 0 < point && point <= 21 {
+        out.push_str(&digits[..point as usize]);
+        out.push('.');
+        out.push_str(&digits[point as usize..]);
+    } else if -6 < point && point <= 0 {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', (-point) as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push('e');
+        out.push(if point > 0 { '+' } else { '-' });
+        out.push_str(&(point - 1).abs().to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn canonical_number(f: f64) -> String {
+        canonicalize(&json!(f))
+    }
+
+    #[test]
+    fn test_rfc8785_example() {
+        // Example from RFC 8785 section 3.2.2
+        let input: JsonValue = serde_json::from_str(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            canonicalize(&input),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn test_keys_sorted_by_utf16_code_units() {
+        // Sorting fixture from RFC 8785 section 3.2.3
+        let input: JsonValue = serde_json::from_str(
+            r#"{
+                "\u20ac": "Euro Sign",
+                "\r": "Carriage Return",
+                "\ufb33": "Hebrew Letter Dalet With Dagesh",
+                "1": "One",
+                "\ud83d\ude00": "Emoji: Grinning Face",
+                "\u0080": "Control",
+                "\u00f6": "Latin Small Letter O With Diaeresis"
+            }"#,
+        )
+        .unwrap();
+
+        let expected = concat!(
+            r#"{"\r":"Carriage Return","1":"One","#,
+            "\"\u{80}\":\"Control\",",
+            "\"\u{f6}\":\"Latin Small Letter O With Diaeresis\",",
+            "\"\u{20ac}\":\"Euro Sign\",",
+            "\"\u{1f600}\":\"Emoji: Grinning Face\",",
+            "\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}",
+        );
+        assert_eq!(canonicalize(&input), expected);
+    }
+
+    #[test]
+    fn test_number_formatting() {
+        // Vectors from the RFC 8785 number serialization appendix
+        assert_eq!(canonical_number(-0.0), "0");
+        assert_eq!(canonical_number(1e21), "1e+21");
+        assert_eq!(canonical_number(1e20), "100000000000000000000");
+        assert_eq!(canonical_number(9007199254740992.0), "9007199254740992");
+        assert_eq!(canonical_number(295147905179352830000.0), "295147905179352830000");
+        assert_eq!(canonical_number(0.000001), "0.000001");
+        assert_eq!(canonical_number(1e-7), "1e-7");
+        assert_eq!(canonical_number(-1.5e-7), "-1.5e-7");
+        assert_eq!(canonical_number(5e-324), "5e-324");
+        assert_eq!(canonical_number(1.7976931348623157e308), "1.7976931348623157e+308");
+        assert_eq!(canonical_number(123.456), "123.456");
+        assert_eq!(canonical_number(1.0), "1");
+    }
+
+    #[test]
+    fn test_big_integers_are_exact() {
+        let input: JsonValue =
+            serde_json::from_str("[18446744073709551615, -9223372036854775808, 9007199254740993]").unwrap();
+        assert_eq!(
+            canonicalize(&input),
+            "[18446744073709551615,-9223372036854775808,9007199254740993]"
+        );
+    }
+
+    #[test]
+    fn test_nested_arrays_keep_order() {
+        let input = json!({"b": [[3, 2], [1, {"z": 0, "a": []}]], "a": {}});
+        assert_eq!(canonicalize(&input), r#"{"a":{},"b":[[3,2],[1,{"a":[],"z":0}]]}"#);
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let a: JsonValue = serde_json::from_str(r#"{"x": 1.50, "y": "é"}"#).unwrap();
+        let b: JsonValue = serde_json::from_str(r#"{ "y": "é", "x": 1.5 }"#).unwrap();
+        assert_eq!(content_hash(&a), content_hash(&b));
+        assert_ne!(content_hash(&a), content_hash(&json!({"x": 1.5})));
+
+        // SHA-256 of the canonical empty object "{}"
+        assert_eq!(
+            content_hash(&json!({})),
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+    }
+}
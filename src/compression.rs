@@ -0,0 +1,241 @@
+use crate::config::ResponseCompression;
+use tower_http::compression::{
+    predicate::{NotForContentType, Predicate, SizeAbove},
+    CompressionLayer,
+};
+use tower_http::decompression::RequestDecompressionLayer;
+
+/// Response compression for every route, negotiated from `Accept-Encoding`
+///
+/// Only the `encodings` configured are offered. Bodies smaller than
+/// `min_bytes` are sent as is, as are images, gRPC and event streams, and ZIP
+/// exports: they are already deflated, so compressing them again only costs
+/// CPU. Streamed bodies have no known size, so they are always compressed,
+/// chunk by chunk.
+pub fn layer(encodings: ResponseCompression, min_bytes: u16) -> CompressionLayer<impl Predicate> {
+    let predicate = SizeAbove::new(min_bytes)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE)
+        .and(NotForContentType::const_new("application/zip"));
+    CompressionLayer::new()
+        .gzip(encodings.gzip)
+        .br(encodings.br)
+        .compress_when(predicate)
+}
+
+/// Decompression of request bodies sent with `Content-Encoding: gzip`
+///
+/// Body limits apply to the decompressed bytes, so a small compressed body
+/// can't expand past `MAX_BODY_BYTES`. Other encodings are rejected with 415.
+pub fn request_layer() -> RequestDecompressionLayer {
+    RequestDecompressionLayer::new().gzip(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::handlers::{export_handler, get_handler, list_handler, metrics_handler, put_handler};
+    use crate::jobs::JobRegistry;
+    use crate::metrics::{track_requests, Metrics};
+    use crate::models::{GetResponse, ListResponse};
+    use crate::spanner::SpannerClient;
+    use crate::state::AppState;
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        middleware,
+        routing::get,
+        Router,
+    };
+    use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+    use serde_json::json;
+    use std::io::{Read, Write};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> Router {
+        setup_test_app_with(ResponseCompression::ALL, 0).await
+    }
+
+    async fn setup_test_app_with(encodings: ResponseCompression, min_bytes: u16) -> Router {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("compression-test", "compression-test-db");
+        let metrics = Metrics::new();
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client")
+            .with_metrics(metrics.clone());
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics,
+        };
+
+        Router::new()
+            .route(crate::routes::KV_LIST, get(list_handler))
+            .route(crate::routes::KV_EXPORT, get(export_handler))
+            .route(crate::routes::METRICS, get(metrics_handler))
+            .route(crate::routes::KV_ITEM, get(get_handler).put(put_handler))
+            .layer(layer(encodings, min_bytes))
+            .layer(request_layer())
+            .layer(middleware::from_fn_with_state(state.clone(), track_requests))
+            .with_state(state)
+    }
+
+    async fn request(app: &Router, uri: &str, accept_encoding: Option<&str>) -> axum::response::Response {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(encoding) = accept_encoding {
+            builder = builder.header(header::ACCEPT_ENCODING, encoding);
+        }
+        let response = app.clone().oneshot(builder.body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "GET {}", uri);
+        response
+    }
+
+    fn gunzip(body: &[u8]) -> Vec<u8> {
+        let mut decoded = Vec::new();
+        GzDecoder::new(body).read_to_end(&mut decoded).unwrap();
+        decoded
+    }
+
+    #[tokio::test]
+    async fn test_list_is_compressed_on_request() {
+        let app = setup_test_app().await;
+
+        let response = request(&app, "/kv?limit=5", Some("gzip")).await;
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let list: ListResponse = serde_json::from_slice(&gunzip(&body)).unwrap();
+        assert_eq!(list.limit, 5);
+
+        let response = request(&app, "/kv?limit=5", Some("br")).await;
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "br");
+
+        // Without Accept-Encoding the body is plain JSON
+        let response = request(&app, "/kv?limit=5", None).await;
+        assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<ListResponse>(&body).unwrap();
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics_and_exports_under_compression() {
+        let app = setup_test_app().await;
+
+        // Scrapers that ask for gzip get valid exposition text back
+        request(&app, "/kv?limit=1", None).await;
+        let response = request(&app, "/metrics", Some("gzip")).await;
+        assert!(response.headers()[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/plain"));
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(gunzip(&body)).unwrap();
+        assert!(text.contains("kv_requests_total"), "{}", text);
+
+        // ZIP exports are already compressed and stream through untouched
+        let response = request(&app, "/kv/export?format=zip&prefix=none-such-", Some("gzip, br")).await;
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/zip");
+        assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(body.starts_with(b"PK"), "a ZIP archive");
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_configured_encodings_are_offered() {
+        let app = setup_test_app_with(ResponseCompression { gzip: true, br: false }, 0).await;
+
+        let response = request(&app, "/kv?limit=5", Some("br")).await;
+        assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+
+        let response = request(&app, "/kv?limit=5", Some("br, gzip")).await;
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_small_responses_are_not_compressed() {
+        let app = setup_test_app_with(ResponseCompression::ALL, 1024).await;
+
+        // An empty page is far below the threshold
+        let response = request(&app, "/kv?limit=1&prefix=none-such-", Some("gzip")).await;
+        assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(body.len() < 1024);
+        serde_json::from_slice::<ListResponse>(&body).unwrap();
+
+        // The metrics exposition is well above it
+        let response = request(&app, "/metrics", Some("gzip")).await;
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gzip_request_body_round_trips() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+        let document = json!({"name": "compressed", "items": vec!["repetitive"; 200]});
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(document.to_string().as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", id))
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::CONTENT_ENCODING, "gzip")
+                    .body(Body::from(compressed))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = request(&app, &format!("/kv/{}", id), None).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let stored: GetResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(stored.data, document);
+
+        // Encodings the server can't decode are refused rather than stored as is
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", id))
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::CONTENT_ENCODING, "br")
+                    .body(Body::from(document.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -1,14 +1,122 @@
 use std::env;
 use anyhow::{Context, Result};
 
+/// Spanner rejects commits containing more mutations than this
+const SPANNER_MAX_MUTATIONS_PER_COMMIT: usize = 80_000;
+
+/// Spanner rejects a single column value, such as one document, larger than this
+const SPANNER_MAX_VALUE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Columns written by each upsert, each counting as one mutation
+pub const UPSERT_COLUMN_COUNT: usize = 8;
+
+/// Mutations each upsert adds for its history: the history row's columns and one prune
+pub const HISTORY_MUTATION_COUNT: usize = 5;
+
+/// Mutations one upsert contributes to its commit
+pub const MUTATIONS_PER_UPSERT: usize = UPSERT_COLUMN_COUNT + HISTORY_MUTATION_COUNT;
+
+/// Table holding the documents unless `SPANNER_TABLE` names another
+pub const DEFAULT_SPANNER_TABLE: &str = "kv_store";
+
+/// Keys under this prefix are reserved for internal use unless overridden
+const DEFAULT_RESERVED_KEY_PREFIX: &str = "__internal/";
+
+/// What document keys look like, from `KEY_MODE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMode {
+    /// Keys are UUIDs; any spelling of one addresses the same document
+    Uuid,
+    /// Keys are short strings such as `user:1234`, stored as given
+    String,
+}
+
+/// Priority of Spanner requests, from `SPANNER_REQUEST_PRIORITY`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestPriority {
+    Low,
+    Medium,
+    High,
+}
+
+/// Encodings offered for responses, from `RESPONSE_COMPRESSION`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseCompression {
+    pub gzip: bool,
+    pub br: bool,
+}
+
+impl ResponseCompression {
+    pub const OFF: Self = ResponseCompression { gzip: false, br: false };
+    pub const ALL: Self = ResponseCompression { gzip: true, br: true };
+
+    /// Parse `off` or a comma-separated list of `gzip` and `br`
+    fn parse(raw: &str) -> Result<Self> {
+        if raw.trim() == "off" {
+            return Ok(Self::OFF);
+        }
+        let mut encodings = Self::OFF;
+        for name in raw.split(',').map(str::trim) {
+            match name {
+                "gzip" => encodings.gzip = true,
+                "br" => encodings.br = true,
+                _ => anyhow::bail!(
+                    "RESPONSE_COMPRESSION must be 'off' or a comma-separated list of gzip and br, got '{}'",
+                    raw
+                ),
+            }
+        }
+        Ok(encodings)
+    }
+
+    pub fn is_off(&self) -> bool {
+        *self == Self::OFF
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub spanner_emulator_host: Option<String>,
     pub spanner_project: String,
     pub spanner_instance: String,
     pub spanner_database: String,
+    pub spanner_table: String,
     pub service_port: u16,
     pub service_host: String,
+    pub secondary_key_path: Option<String>,
+    pub write_batch_window_ms: Option<u64>,
+    pub write_batch_max_size: usize,
+    pub spanner_transaction_tag: Option<String>,
+    pub spanner_request_priority: RequestPriority,
+    pub reserved_key_prefix: Option<String>,
+    pub ramp_duration_secs: Option<u64>,
+    pub ramp_initial_concurrency: usize,
+    pub debug_read_info: bool,
+    pub max_get_wait_secs: u64,
+    pub request_timeout_secs: u64,
+    pub admin_token: Option<String>,
+    pub job_retention_secs: u64,
+    pub max_documents: Option<u64>,
+    pub list_cache_max_age: Option<u64>,
+    pub max_batch_get_ids: usize,
+    pub spanner_max_retries: u32,
+    pub spanner_retry_initial_backoff_ms: u64,
+    pub spanner_retry_max_backoff_ms: u64,
+    pub history_max_versions: u32,
+    pub max_limit: u32,
+    pub default_limit: u32,
+    pub max_search_rows: u32,
+    pub max_document_bytes: usize,
+    pub max_body_bytes: usize,
+    pub key_mode: KeyMode,
+    pub spanner_min_sessions: Option<usize>,
+    pub spanner_max_sessions: Option<usize>,
+    pub response_compression: ResponseCompression,
+    pub compression_min_bytes: u16,
+    pub spanner_node_count: Option<i32>,
+    pub spanner_processing_units: Option<i32>,
+    pub public_base_url: Option<String>,
+    pub auto_provision: bool,
 }
 
 impl Config {
@@ -24,6 +132,15 @@ impl Config {
         let spanner_database = env::var("SPANNER_DATABASE")
             .context("SPANNER_DATABASE environment variable is required")?;
 
+        // The table name is embedded into SQL and DDL, so only plain identifiers are allowed
+        let spanner_table = env::var("SPANNER_TABLE").unwrap_or_else(|_| DEFAULT_SPANNER_TABLE.to_string());
+        if !is_identifier(&spanner_table) {
+            anyhow::bail!(
+                "SPANNER_TABLE must be a plain identifier (letters, digits and underscores, not starting with a digit), got '{}'",
+                spanner_table
+            );
+        }
+
         let service_port = env::var("SERVICE_PORT")
             .unwrap_or_else(|_| "3000".to_string())
             .parse::<u16>()
@@ -32,16 +149,402 @@ impl Config {
         let service_host = env::var("SERVICE_HOST")
             .unwrap_or_else(|_| "0.0.0.0".to_string());
 
+        let secondary_key_path = env::var("SECONDARY_KEY_PATH").ok();
+        if let Some(path) = &secondary_key_path {
+            validate_json_path(path).context("SECONDARY_KEY_PATH is invalid")?;
+        }
+
+        let write_batch_window_ms = env::var("WRITE_BATCH_WINDOW_MS")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()
+            .context("WRITE_BATCH_WINDOW_MS must be a non-negative integer")?;
+
+        let write_batch_max_size = env::var("WRITE_BATCH_MAX_SIZE")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<usize>()
+            .context("WRITE_BATCH_MAX_SIZE must be a positive integer")?;
+        if write_batch_max_size == 0 {
+            anyhow::bail!("WRITE_BATCH_MAX_SIZE must be a positive integer");
+        }
+
+        let spanner_transaction_tag = env::var("SPANNER_TRANSACTION_TAG").ok();
+
+        // An empty value disables the reserved namespace entirely
+        let reserved_key_prefix = match env::var("RESERVED_KEY_PREFIX") {
+            Ok(prefix) if prefix.is_empty() => None,
+            Ok(prefix) => Some(prefix.to_lowercase()),
+            Err(_) => Some(DEFAULT_RESERVED_KEY_PREFIX.to_string()),
+        };
+
+        let ramp_duration_secs = env::var("RAMP_DURATION_SECS")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()
+            .context("RAMP_DURATION_SECS must be a non-negative integer")?;
+
+        let ramp_initial_concurrency = env::var("RAMP_INITIAL_CONCURRENCY")
+            .unwrap_or_else(|_| "4".to_string())
+            .parse::<usize>()
+            .context("RAMP_INITIAL_CONCURRENCY must be a positive integer")?;
+        if ramp_initial_concurrency == 0 {
+            anyhow::bail!("RAMP_INITIAL_CONCURRENCY must be a positive integer");
+        }
+
+        let debug_read_info = env::var("DEBUG_READ_INFO")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .context("DEBUG_READ_INFO must be true or false")?;
+
+        let max_get_wait_secs = env::var("MAX_GET_WAIT_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<u64>()
+            .context("MAX_GET_WAIT_SECS must be a non-negative integer")?;
+
+        let request_timeout_secs = env::var("REQUEST_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<u64>()
+            .context("REQUEST_TIMEOUT_SECS must be a positive integer")?;
+        if request_timeout_secs == 0 {
+            anyhow::bail!("REQUEST_TIMEOUT_SECS must be a positive integer");
+        }
+
+        // Admin endpoints stay disabled unless a token is configured
+        let admin_token = env::var("ADMIN_TOKEN").ok().filter(|token| !token.is_empty());
+
+        let job_retention_secs = env::var("JOB_RETENTION_SECS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse::<u64>()
+            .context("JOB_RETENTION_SECS must be a non-negative integer")?;
+
+        let max_documents = env::var("MAX_DOCUMENTS")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()
+            .context("MAX_DOCUMENTS must be a positive integer")?;
+        if max_documents == Some(0) {
+            anyhow::bail!("MAX_DOCUMENTS must be a positive integer");
+        }
+
+        let list_cache_max_age = env::var("LIST_CACHE_MAX_AGE")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()
+            .context("LIST_CACHE_MAX_AGE must be a non-negative integer")?;
+
+        let max_batch_get_ids = env::var("MAX_BATCH_GET_IDS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse::<usize>()
+            .context("MAX_BATCH_GET_IDS must be a positive integer")?;
+        if max_batch_get_ids == 0 {
+            anyhow::bail!("MAX_BATCH_GET_IDS must be a positive integer");
+        }
+
+        let spanner_max_retries = env::var("SPANNER_MAX_RETRIES")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u32>()
+            .context("SPANNER_MAX_RETRIES must be a non-negative integer")?;
+
+        let spanner_retry_initial_backoff_ms = env::var("SPANNER_RETRY_INITIAL_BACKOFF_MS")
+            .unwrap_or_else(|_| "50".to_string())
+            .parse::<u64>()
+            .context("SPANNER_RETRY_INITIAL_BACKOFF_MS must be a positive integer")?;
+        if spanner_retry_initial_backoff_ms == 0 {
+            anyhow::bail!("SPANNER_RETRY_INITIAL_BACKOFF_MS must be a positive integer");
+        }
+
+        let spanner_retry_max_backoff_ms = env::var("SPANNER_RETRY_MAX_BACKOFF_MS")
+            .unwrap_or_else(|_| "2000".to_string())
+            .parse::<u64>()
+            .context("SPANNER_RETRY_MAX_BACKOFF_MS must be a positive integer")?;
+
+        let history_max_versions = env::var("HISTORY_MAX_VERSIONS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u32>()
+            .context("HISTORY_MAX_VERSIONS must be a non-negative integer")?;
+
+        let max_limit = env::var("MAX_LIMIT")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse::<u32>()
+            .context("MAX_LIMIT must be a positive integer")?;
+        if max_limit == 0 {
+            anyhow::bail!("MAX_LIMIT must be a positive integer");
+        }
+
+        let default_limit = env::var("DEFAULT_LIMIT")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<u32>()
+            .context("DEFAULT_LIMIT must be a positive integer")?;
+        if default_limit == 0 {
+            anyhow::bail!("DEFAULT_LIMIT must be a positive integer");
+        }
+
+        let max_search_rows = env::var("MAX_SEARCH_ROWS")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<u32>()
+            .context("MAX_SEARCH_ROWS must be a positive integer")?;
+        if max_search_rows == 0 {
+            anyhow::bail!("MAX_SEARCH_ROWS must be a positive integer");
+        }
+
+        let max_document_bytes = env::var("MAX_DOCUMENT_BYTES")
+            .unwrap_or_else(|_| "1048576".to_string())
+            .parse::<usize>()
+            .context("MAX_DOCUMENT_BYTES must be a positive integer")?;
+        if max_document_bytes == 0 {
+            anyhow::bail!("MAX_DOCUMENT_BYTES must be a positive integer");
+        }
+
+        let max_body_bytes = env::var("MAX_BODY_BYTES")
+            .unwrap_or_else(|_| "2097152".to_string())
+            .parse::<usize>()
+            .context("MAX_BODY_BYTES must be a positive integer")?;
+        if max_body_bytes == 0 {
+            anyhow::bail!("MAX_BODY_BYTES must be a positive integer");
+        }
+
+        let spanner_request_priority = match env::var("SPANNER_REQUEST_PRIORITY").map(|raw| raw.to_ascii_lowercase()).as_deref() {
+            Err(_) | Ok("medium") => RequestPriority::Medium,
+            Ok("low") => RequestPriority::Low,
+            Ok("high") => RequestPriority::High,
+            Ok(other) => anyhow::bail!("SPANNER_REQUEST_PRIORITY must be 'low', 'medium' or 'high', got '{}'", other),
+        };
+
+        let key_mode = match env::var("KEY_MODE").as_deref() {
+            Err(_) | Ok("uuid") => KeyMode::Uuid,
+            Ok("string") => KeyMode::String,
+            Ok(other) => anyhow::bail!("KEY_MODE must be 'uuid' or 'string', got '{}'", other),
+        };
+
+        // Unset keeps the Spanner client's own session pool sizes
+        let spanner_min_sessions = env::var("SPANNER_MIN_SESSIONS")
+            .ok()
+            .map(|v| v.parse::<usize>())
+            .transpose()
+            .context("SPANNER_MIN_SESSIONS must be a non-negative integer")?;
+
+        let spanner_max_sessions = env::var("SPANNER_MAX_SESSIONS")
+            .ok()
+            .map(|v| v.parse::<usize>())
+            .transpose()
+            .context("SPANNER_MAX_SESSIONS must be a positive integer")?;
+        if spanner_max_sessions == Some(0) {
+            anyhow::bail!("SPANNER_MAX_SESSIONS must be a positive integer");
+        }
+
+        // ENABLE_COMPRESSION=false, the older switch, still turns compression off
+        let enable_compression = env::var("ENABLE_COMPRESSION")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse::<bool>()
+            .context("ENABLE_COMPRESSION must be true or false")?;
+        let response_compression = match env::var("RESPONSE_COMPRESSION") {
+            Ok(raw) if !enable_compression => {
+                anyhow::bail!("ENABLE_COMPRESSION=false conflicts with RESPONSE_COMPRESSION={}; set only RESPONSE_COMPRESSION", raw)
+            }
+            Ok(raw) => ResponseCompression::parse(&raw)?,
+            Err(_) if !enable_compression => ResponseCompression::OFF,
+            Err(_) => ResponseCompression::ALL,
+        };
+
+        let compression_min_bytes = env::var("COMPRESSION_MIN_BYTES")
+            .unwrap_or_else(|_| "1024".to_string())
+            .parse::<u16>()
+            .context("COMPRESSION_MIN_BYTES must be an integer from 0 to 65535")?;
+
+        // Capacity of an instance created at startup; one node when neither is set
+        let spanner_node_count = env::var("SPANNER_NODE_COUNT")
+            .ok()
+            .map(|v| v.parse::<i32>())
+            .transpose()
+            .context("SPANNER_NODE_COUNT must be a positive integer")?;
+        if spanner_node_count.is_some_and(|n| n <= 0) {
+            anyhow::bail!("SPANNER_NODE_COUNT must be a positive integer");
+        }
+
+        let spanner_processing_units = env::var("SPANNER_PROCESSING_UNITS")
+            .ok()
+            .map(|v| v.parse::<i32>())
+            .transpose()
+            .context("SPANNER_PROCESSING_UNITS must be a positive integer")?;
+        if spanner_processing_units.is_some_and(|n| n <= 0) {
+            anyhow::bail!("SPANNER_PROCESSING_UNITS must be a positive integer");
+        }
+        if spanner_node_count.is_some() && spanner_processing_units.is_some() {
+            anyhow::bail!("SPANNER_NODE_COUNT and SPANNER_PROCESSING_UNITS are mutually exclusive; set only one");
+        }
+
+        let public_base_url = public_base_url_from_env()?;
+
+        // Creating the instance, database and schema is only assumed against the emulator
+        let auto_provision = env::var("AUTO_PROVISION")
+            .ok()
+            .map(|v| v.parse::<bool>())
+            .transpose()
+            .context("AUTO_PROVISION must be true or false")?
+            .unwrap_or(spanner_emulator_host.is_some());
+
         Ok(Config {
             spanner_emulator_host,
             spanner_project,
             spanner_instance,
             spanner_database,
+            spanner_table,
             service_port,
             service_host,
+            secondary_key_path,
+            write_batch_window_ms,
+            write_batch_max_size,
+            spanner_transaction_tag,
+            spanner_request_priority,
+            reserved_key_prefix,
+            ramp_duration_secs,
+            ramp_initial_concurrency,
+            debug_read_info,
+            max_get_wait_secs,
+            request_timeout_secs,
+            admin_token,
+            job_retention_secs,
+            max_documents,
+            list_cache_max_age,
+            max_batch_get_ids,
+            spanner_max_retries,
+            spanner_retry_initial_backoff_ms,
+            spanner_retry_max_backoff_ms,
+            history_max_versions,
+            max_limit,
+            default_limit,
+            max_search_rows,
+            max_document_bytes,
+            max_body_bytes,
+            key_mode,
+            spanner_min_sessions,
+            spanner_max_sessions,
+            response_compression,
+            compression_min_bytes,
+            spanner_node_count,
+            spanner_processing_units,
+            public_base_url,
+            auto_provision,
         })
     }
 
+    /// Whether a key falls in the reserved namespace for internal use
+    pub fn is_reserved_key(&self, key: &str) -> bool {
+        self.reserved_key_prefix
+            .as_deref()
+            .is_some_and(|prefix| key.starts_with(prefix))
+    }
+
+    /// Check for settings that are individually valid but contradict each other
+    ///
+    /// Every conflict found is listed in the error, so operators can fix them all
+    /// in one pass instead of discovering them one failure at a time.
+    pub fn validate(&self) -> Result<()> {
+        let mut conflicts = Vec::new();
+
+        if self.write_batch_window_ms == Some(0) {
+            conflicts.push(
+                "WRITE_BATCH_WINDOW_MS=0 enables batching with no window; unset it to disable batching"
+                    .to_string(),
+            );
+        }
+
+        // Each batched upsert writes every column and its history, and all of them count toward the commit limit
+        let batch_mutations = self.write_batch_max_size.saturating_mul(MUTATIONS_PER_UPSERT);
+        if self.write_batch_window_ms.is_some() && batch_mutations > SPANNER_MAX_MUTATIONS_PER_COMMIT {
+            conflicts.push(format!(
+                "WRITE_BATCH_MAX_SIZE={} exceeds Spanner's limit of {} mutations per commit (at most {} upserts)",
+                self.write_batch_max_size,
+                SPANNER_MAX_MUTATIONS_PER_COMMIT,
+                SPANNER_MAX_MUTATIONS_PER_COMMIT / MUTATIONS_PER_UPSERT
+            ));
+        }
+
+        if self.ramp_duration_secs == Some(0) {
+            conflicts.push(
+                "RAMP_DURATION_SECS=0 enables the connection ramp with no duration; unset it to disable the ramp"
+                    .to_string(),
+            );
+        }
+
+        if self.spanner_retry_max_backoff_ms < self.spanner_retry_initial_backoff_ms {
+            conflicts.push(format!(
+                "SPANNER_RETRY_MAX_BACKOFF_MS={} is below SPANNER_RETRY_INITIAL_BACKOFF_MS={}",
+                self.spanner_retry_max_backoff_ms, self.spanner_retry_initial_backoff_ms
+            ));
+        }
+
+        if self.default_limit > self.max_limit {
+            conflicts.push(format!(
+                "DEFAULT_LIMIT={} is above MAX_LIMIT={}",
+                self.default_limit, self.max_limit
+            ));
+        }
+
+        // Documents are measured compacted, so a body limit below the document limit rejects valid documents
+        if self.max_body_bytes < self.max_document_bytes {
+            conflicts.push(format!(
+                "MAX_BODY_BYTES={} is below MAX_DOCUMENT_BYTES={}",
+                self.max_body_bytes, self.max_document_bytes
+            ));
+        }
+
+        if let (Some(min), Some(max)) = (self.spanner_min_sessions, self.spanner_max_sessions)
+            && min > max
+        {
+            conflicts.push(format!(
+                "SPANNER_MIN_SESSIONS={} is above SPANNER_MAX_SESSIONS={}",
+                min, max
+            ));
+        }
+
+        if !self.auto_provision && (self.spanner_node_count.is_some() || self.spanner_processing_units.is_some()) {
+            conflicts.push(
+                "SPANNER_NODE_COUNT and SPANNER_PROCESSING_UNITS only size an instance created by AUTO_PROVISION, which is off"
+                    .to_string(),
+            );
+        }
+
+        if let Some(tag) = &self.spanner_transaction_tag
+            && tag.split(',').any(|part| part.starts_with("op="))
+        {
+            conflicts.push(format!(
+                "SPANNER_TRANSACTION_TAG '{}' sets op=, which is added per operation",
+                tag
+            ));
+        }
+
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+
+        anyhow::bail!(
+            "Contradictory configuration:\n  - {}",
+            conflicts.join("\n  - ")
+        )
+    }
+
+    /// Settings that are allowed but likely to fail at runtime, logged at startup
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if self.max_body_bytes > SPANNER_MAX_VALUE_BYTES {
+            warnings.push(format!(
+                "MAX_BODY_BYTES={} is above Spanner's {} byte limit on one value; a single document that large fails at commit",
+                self.max_body_bytes, SPANNER_MAX_VALUE_BYTES
+            ));
+        }
+
+        if self.max_document_bytes > SPANNER_MAX_VALUE_BYTES {
+            warnings.push(format!(
+                "MAX_DOCUMENT_BYTES={} is above Spanner's {} byte limit on one value; documents that large are accepted but fail at commit",
+                self.max_document_bytes, SPANNER_MAX_VALUE_BYTES
+            ));
+        }
+
+        warnings
+    }
+
     pub fn log_startup(&self) {
         tracing::info!("Configuration loaded:");
         tracing::info!("  Spanner emulator: {}",
@@ -49,7 +552,169 @@ impl Config {
         tracing::info!("  Spanner project: {}", self.spanner_project);
         tracing::info!("  Spanner instance: {}", self.spanner_instance);
         tracing::info!("  Spanner database: {}", self.spanner_database);
+        tracing::info!("  Spanner table: {}", self.spanner_table);
         tracing::info!("  Service listening on: {}:{}", self.service_host, self.service_port);
+        tracing::info!("  Secondary key path: {}",
+            self.secondary_key_path.as_deref().unwrap_or("disabled"));
+        match self.write_batch_window_ms {
+            Some(window) => tracing::info!("  Write batching: {}ms window, max {} writes",
+                window, self.write_batch_max_size),
+            None => tracing::info!("  Write batching: disabled"),
+        }
+        tracing::info!("  Spanner transaction tag: {}",
+            self.spanner_transaction_tag.as_deref().unwrap_or("none"));
+        tracing::info!("  Reserved key prefix: {}",
+            self.reserved_key_prefix.as_deref().unwrap_or("disabled"));
+        match self.ramp_duration_secs {
+            Some(secs) => tracing::info!("  Connection ramp: {}s, starting at {} concurrent operations",
+                secs, self.ramp_initial_concurrency),
+            None => tracing::info!("  Connection ramp: disabled"),
+        }
+        tracing::info!("  Read info headers: {}",
+            if self.debug_read_info { "always" } else { "on request" });
+        tracing::info!("  Max GET wait: {}s", self.max_get_wait_secs);
+        tracing::info!("  Request timeout: {}s", self.request_timeout_secs);
+        tracing::info!("  Admin endpoints: {}",
+            if self.admin_token.is_some() { "enabled" } else { "disabled" });
+        tracing::info!("  Finished job retention: {}s", self.job_retention_secs);
+        match self.max_documents {
+            Some(max) => tracing::info!("  Document limit: {}", max),
+            None => tracing::info!("  Document limit: none"),
+        }
+        match self.list_cache_max_age {
+            Some(secs) => tracing::info!("  Read cache max-age: {}s", secs),
+            None => tracing::info!("  Read cache headers: disabled"),
+        }
+        tracing::info!("  Max ids per batch GET: {}", self.max_batch_get_ids);
+        tracing::info!("  Spanner retries: up to {}, backoff {}ms to {}ms",
+            self.spanner_max_retries, self.spanner_retry_initial_backoff_ms, self.spanner_retry_max_backoff_ms);
+        match self.history_max_versions {
+            0 => tracing::info!("  Version history: disabled"),
+            max => tracing::info!("  Version history: last {} versions per key", max),
+        }
+        tracing::info!("  List limit: {} by default, at most {}", self.default_limit, self.max_limit);
+        tracing::info!("  Search limit: at most {} rows per q= page", self.max_search_rows);
+        tracing::info!("  Max document size: {} bytes", self.max_document_bytes);
+        tracing::info!("  Max request body: {} bytes", self.max_body_bytes);
+        tracing::info!("  Key mode: {:?}", self.key_mode);
+        tracing::info!("  Spanner request priority: {:?}", self.spanner_request_priority);
+        let sessions = |n: Option<usize>| n.map_or("client default".to_string(), |n| n.to_string());
+        tracing::info!("  Spanner sessions: min {}, max {}",
+            sessions(self.spanner_min_sessions), sessions(self.spanner_max_sessions));
+        if self.response_compression.is_off() {
+            tracing::info!("  Response compression: disabled");
+        } else {
+            let encodings: Vec<&str> = [("gzip", self.response_compression.gzip), ("br", self.response_compression.br)]
+                .into_iter()
+                .filter_map(|(name, offered)| offered.then_some(name))
+                .collect();
+            tracing::info!("  Response compression: {} for bodies of at least {} bytes",
+                encodings.join(", "), self.compression_min_bytes);
+        }
+        tracing::info!("  Auto-provisioning: {}", if self.auto_provision { "enabled" } else { "disabled" });
+        match (self.spanner_node_count, self.spanner_processing_units) {
+            (_, Some(units)) => tracing::info!("  New instance capacity: {} processing units", units),
+            (Some(nodes), None) => tracing::info!("  New instance capacity: {} nodes", nodes),
+            (None, None) => tracing::info!("  New instance capacity: 1 node"),
+        }
+        tracing::info!("  Public base URL: {}", self.public_base_url.as_deref().unwrap_or("unset (relative)"));
+        for warning in self.warnings() {
+            tracing::warn!("{}", warning);
+        }
+    }
+}
+
+/// Read `PUBLIC_BASE_URL`, the server advertised in the OpenAPI document
+///
+/// Separate from [`Config::from_env`] so `--dump-openapi`, which runs without
+/// Spanner settings, can honor it too. A trailing `/` is dropped.
+pub fn public_base_url_from_env() -> Result<Option<String>> {
+    match env::var("PUBLIC_BASE_URL") {
+        Ok(url) if url.is_empty() => Ok(None),
+        Ok(url) if url.starts_with("http://") || url.starts_with("https://") => {
+            Ok(Some(url.trim_end_matches('/').to_string()))
+        }
+        Ok(url) => anyhow::bail!("PUBLIC_BASE_URL must start with http:// or https://, got '{}'", url),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Validate a simple JSONPath of the form `$.field` or `$.parent.child`
+///
+/// The path is embedded into DDL, so only plain identifier segments are allowed.
+fn validate_json_path(path: &str) -> Result<()> {
+    let segments = path
+        .strip_prefix("$.")
+        .ok_or_else(|| anyhow::anyhow!("path must start with '$.', got '{}'", path))?;
+
+    for segment in segments.split('.') {
+        if !is_identifier(segment) {
+            anyhow::bail!("invalid path segment '{}' in '{}'", segment, path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `name` matches `^[A-Za-z_][A-Za-z0-9_]*$`
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => {
+            (first.is_ascii_alphabetic() || first == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+impl Config {
+    /// Config pointing at the local emulator, used by integration tests
+    pub fn for_emulator(instance: &str, database: &str) -> Self {
+        Config {
+            spanner_emulator_host: Some("localhost:9010".to_string()),
+            spanner_project: "test-project".to_string(),
+            spanner_instance: instance.to_string(),
+            spanner_database: database.to_string(),
+            spanner_table: DEFAULT_SPANNER_TABLE.to_string(),
+            service_port: 3000,
+            service_host: "0.0.0.0".to_string(),
+            secondary_key_path: None,
+            write_batch_window_ms: None,
+            write_batch_max_size: 100,
+            spanner_transaction_tag: None,
+            spanner_request_priority: RequestPriority::Medium,
+            reserved_key_prefix: Some(DEFAULT_RESERVED_KEY_PREFIX.to_string()),
+            ramp_duration_secs: None,
+            ramp_initial_concurrency: 4,
+            debug_read_info: false,
+            max_get_wait_secs: 30,
+            request_timeout_secs: 30,
+            admin_token: None,
+            job_retention_secs: 3600,
+            max_documents: None,
+            list_cache_max_age: None,
+            max_batch_get_ids: 1000,
+            spanner_max_retries: 5,
+            spanner_retry_initial_backoff_ms: 50,
+            spanner_retry_max_backoff_ms: 2000,
+            history_max_versions: 10,
+            max_limit: 1000,
+            default_limit: 100,
+            max_search_rows: 100,
+            max_document_bytes: 1024 * 1024,
+            max_body_bytes: 2 * 1024 * 1024,
+            key_mode: KeyMode::Uuid,
+            spanner_min_sessions: None,
+            spanner_max_sessions: None,
+            response_compression: ResponseCompression::ALL,
+            compression_min_bytes: 1024,
+            spanner_node_count: None,
+            spanner_processing_units: None,
+            public_base_url: None,
+            auto_provision: true,
+        }
     }
 }
 
@@ -64,8 +729,44 @@ mod tests {
             env::remove_var("SPANNER_PROJECT");
             env::remove_var("SPANNER_INSTANCE");
             env::remove_var("SPANNER_DATABASE");
+            env::remove_var("SPANNER_TABLE");
             env::remove_var("SERVICE_PORT");
             env::remove_var("SERVICE_HOST");
+            env::remove_var("SECONDARY_KEY_PATH");
+            env::remove_var("WRITE_BATCH_WINDOW_MS");
+            env::remove_var("WRITE_BATCH_MAX_SIZE");
+            env::remove_var("SPANNER_TRANSACTION_TAG");
+            env::remove_var("SPANNER_REQUEST_PRIORITY");
+            env::remove_var("RESERVED_KEY_PREFIX");
+            env::remove_var("RAMP_DURATION_SECS");
+            env::remove_var("RAMP_INITIAL_CONCURRENCY");
+            env::remove_var("DEBUG_READ_INFO");
+            env::remove_var("MAX_GET_WAIT_SECS");
+            env::remove_var("REQUEST_TIMEOUT_SECS");
+            env::remove_var("ADMIN_TOKEN");
+            env::remove_var("JOB_RETENTION_SECS");
+            env::remove_var("MAX_DOCUMENTS");
+            env::remove_var("LIST_CACHE_MAX_AGE");
+            env::remove_var("MAX_BATCH_GET_IDS");
+            env::remove_var("SPANNER_MAX_RETRIES");
+            env::remove_var("SPANNER_RETRY_INITIAL_BACKOFF_MS");
+            env::remove_var("SPANNER_RETRY_MAX_BACKOFF_MS");
+            env::remove_var("HISTORY_MAX_VERSIONS");
+            env::remove_var("MAX_LIMIT");
+            env::remove_var("DEFAULT_LIMIT");
+            env::remove_var("MAX_SEARCH_ROWS");
+            env::remove_var("MAX_DOCUMENT_BYTES");
+            env::remove_var("MAX_BODY_BYTES");
+            env::remove_var("KEY_MODE");
+            env::remove_var("SPANNER_MIN_SESSIONS");
+            env::remove_var("SPANNER_MAX_SESSIONS");
+            env::remove_var("ENABLE_COMPRESSION");
+            env::remove_var("RESPONSE_COMPRESSION");
+            env::remove_var("COMPRESSION_MIN_BYTES");
+            env::remove_var("SPANNER_NODE_COUNT");
+            env::remove_var("SPANNER_PROCESSING_UNITS");
+            env::remove_var("PUBLIC_BASE_URL");
+            env::remove_var("AUTO_PROVISION");
         }
     }
 
@@ -85,6 +786,7 @@ mod tests {
             env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
             env::set_var("SERVICE_PORT", "8080");
             env::set_var("SERVICE_HOST", "127.0.0.1");
+            env::set_var("SPANNER_TRANSACTION_TAG", "team=kv");
         }
 
         let config = Config::from_env().unwrap();
@@ -95,6 +797,7 @@ mod tests {
         assert_eq!(config.spanner_database, "test-database");
         assert_eq!(config.service_port, 8080);
         assert_eq!(config.service_host, "127.0.0.1");
+        assert_eq!(config.spanner_transaction_tag, Some("team=kv".to_string()));
     }
 
     #[test]
@@ -107,6 +810,41 @@ mod tests {
         assert_eq!(config.spanner_emulator_host, None);
         assert_eq!(config.service_port, 3000);
         assert_eq!(config.service_host, "0.0.0.0");
+        assert_eq!(config.secondary_key_path, None);
+        assert_eq!(config.write_batch_window_ms, None);
+        assert_eq!(config.write_batch_max_size, 100);
+        assert_eq!(config.spanner_transaction_tag, None);
+        assert_eq!(config.reserved_key_prefix, Some("__internal/".to_string()));
+        assert_eq!(config.ramp_duration_secs, None);
+        assert_eq!(config.ramp_initial_concurrency, 4);
+        assert!(!config.debug_read_info);
+        assert_eq!(config.max_get_wait_secs, 30);
+        assert_eq!(config.request_timeout_secs, 30);
+        assert_eq!(config.admin_token, None);
+        assert_eq!(config.job_retention_secs, 3600);
+        assert_eq!(config.max_documents, None);
+        assert_eq!(config.list_cache_max_age, None);
+        assert_eq!(config.max_batch_get_ids, 1000);
+        assert_eq!(config.spanner_table, "kv_store");
+        assert_eq!(config.spanner_max_retries, 5);
+        assert_eq!(config.spanner_retry_initial_backoff_ms, 50);
+        assert_eq!(config.spanner_retry_max_backoff_ms, 2000);
+        assert_eq!(config.history_max_versions, 10);
+        assert_eq!(config.max_limit, 1000);
+        assert_eq!(config.default_limit, 100);
+        assert_eq!(config.max_search_rows, 100);
+        assert_eq!(config.max_document_bytes, 1024 * 1024);
+        assert_eq!(config.max_body_bytes, 2 * 1024 * 1024);
+        assert_eq!(config.key_mode, KeyMode::Uuid);
+        assert_eq!(config.spanner_request_priority, RequestPriority::Medium);
+        assert_eq!(config.spanner_min_sessions, None);
+        assert_eq!(config.spanner_max_sessions, None);
+        assert_eq!(config.response_compression, ResponseCompression::ALL);
+        assert_eq!(config.compression_min_bytes, 1024);
+        assert_eq!(config.spanner_node_count, None);
+        assert_eq!(config.spanner_processing_units, None);
+        assert_eq!(config.public_base_url, None);
+        assert!(!config.auto_provision);
     }
 
     #[test]
@@ -149,4 +887,706 @@ mod tests {
         let result = Config::from_env();
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_secondary_key_path() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("SECONDARY_KEY_PATH", "$.user.email");
+        }
+
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.secondary_key_path, Some("$.user.email".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_secondary_key_path() {
+        clear_env_vars();
+        set_required_vars();
+
+        for path in ["email", "$.", "$.email')", "$.a..b", "$.1abc"] {
+            unsafe {
+                env::set_var("SECONDARY_KEY_PATH", path);
+            }
+            let result = Config::from_env();
+            assert!(result.is_err(), "path '{}' should be rejected", path);
+            assert!(result.unwrap_err().to_string().contains("SECONDARY_KEY_PATH"));
+        }
+    }
+
+    #[test]
+    fn test_spanner_table() {
+        clear_env_vars();
+        set_required_vars();
+        assert_eq!(Config::from_env().unwrap().spanner_table, "kv_store");
+
+        for name in ["documents", "_kv", "KvStore2"] {
+            unsafe {
+                env::set_var("SPANNER_TABLE", name);
+            }
+            assert_eq!(Config::from_env().unwrap().spanner_table, name);
+        }
+    }
+
+    #[test]
+    fn test_invalid_spanner_table() {
+        clear_env_vars();
+        set_required_vars();
+
+        for name in ["", "2kv", "kv-store", "kv store", "kv_store; DROP TABLE kv_store", "`kv`", "kv.store"] {
+            unsafe {
+                env::set_var("SPANNER_TABLE", name);
+            }
+            let result = Config::from_env();
+            assert!(result.is_err(), "table name '{}' should be rejected", name);
+            assert!(result.unwrap_err().to_string().contains("SPANNER_TABLE"));
+        }
+    }
+
+    #[test]
+    fn test_write_batch_settings() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("WRITE_BATCH_WINDOW_MS", "5");
+            env::set_var("WRITE_BATCH_MAX_SIZE", "250");
+        }
+
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.write_batch_window_ms, Some(5));
+        assert_eq!(config.write_batch_max_size, 250);
+    }
+
+    #[test]
+    fn test_invalid_write_batch_max_size() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("WRITE_BATCH_MAX_SIZE", "0");
+        }
+
+        let result = Config::from_env();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("WRITE_BATCH_MAX_SIZE"));
+    }
+
+    #[test]
+    fn test_reserved_key_prefix() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("RESERVED_KEY_PREFIX", "00000000-0000-");
+        }
+
+        let config = Config::from_env().unwrap();
+        assert!(config.is_reserved_key("00000000-0000-0000-0000-000000000001"));
+        assert!(!config.is_reserved_key("550e8400-e29b-41d4-a716-446655440000"));
+
+        unsafe {
+            env::set_var("RESERVED_KEY_PREFIX", "");
+        }
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.reserved_key_prefix, None);
+        assert!(!config.is_reserved_key("__internal/canary"));
+    }
+
+    #[test]
+    fn test_connection_ramp() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("RAMP_DURATION_SECS", "120");
+            env::set_var("RAMP_INITIAL_CONCURRENCY", "2");
+        }
+
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.ramp_duration_secs, Some(120));
+        assert_eq!(config.ramp_initial_concurrency, 2);
+
+        unsafe {
+            env::set_var("RAMP_INITIAL_CONCURRENCY", "0");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("RAMP_INITIAL_CONCURRENCY"));
+    }
+
+    #[test]
+    fn test_debug_read_info() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("DEBUG_READ_INFO", "true");
+        }
+        assert!(Config::from_env().unwrap().debug_read_info);
+
+        unsafe {
+            env::set_var("DEBUG_READ_INFO", "sometimes");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("DEBUG_READ_INFO"));
+    }
+
+    #[test]
+    fn test_enable_compression() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("ENABLE_COMPRESSION", "false");
+        }
+        assert!(Config::from_env().unwrap().response_compression.is_off());
+
+        unsafe {
+            env::set_var("RESPONSE_COMPRESSION", "gzip");
+        }
+        let err = Config::from_env().unwrap_err().to_string();
+        assert!(err.contains("set only RESPONSE_COMPRESSION"), "{}", err);
+
+        unsafe {
+            env::remove_var("RESPONSE_COMPRESSION");
+            env::set_var("ENABLE_COMPRESSION", "yes");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("ENABLE_COMPRESSION"));
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_response_compression() {
+        clear_env_vars();
+        set_required_vars();
+        for (raw, expected) in [
+            ("off", ResponseCompression::OFF),
+            ("gzip", ResponseCompression { gzip: true, br: false }),
+            ("br", ResponseCompression { gzip: false, br: true }),
+            ("gzip, br", ResponseCompression::ALL),
+        ] {
+            unsafe {
+                env::set_var("RESPONSE_COMPRESSION", raw);
+            }
+            assert_eq!(Config::from_env().unwrap().response_compression, expected, "{}", raw);
+        }
+
+        for raw in ["", "deflate", "gzip,off"] {
+            unsafe {
+                env::set_var("RESPONSE_COMPRESSION", raw);
+            }
+            let err = Config::from_env().unwrap_err().to_string();
+            assert!(err.contains("RESPONSE_COMPRESSION"), "{}", err);
+        }
+
+        unsafe {
+            env::remove_var("RESPONSE_COMPRESSION");
+            env::set_var("COMPRESSION_MIN_BYTES", "0");
+        }
+        assert_eq!(Config::from_env().unwrap().compression_min_bytes, 0);
+
+        unsafe {
+            env::set_var("COMPRESSION_MIN_BYTES", "70000");
+        }
+        let err = Config::from_env().unwrap_err().to_string();
+        assert!(err.contains("COMPRESSION_MIN_BYTES"), "{}", err);
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_max_get_wait_secs() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("MAX_GET_WAIT_SECS", "5");
+        }
+        assert_eq!(Config::from_env().unwrap().max_get_wait_secs, 5);
+
+        unsafe {
+            env::set_var("MAX_GET_WAIT_SECS", "-1");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("MAX_GET_WAIT_SECS"));
+    }
+
+    #[test]
+    fn test_request_timeout_secs() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("REQUEST_TIMEOUT_SECS", "5");
+        }
+        assert_eq!(Config::from_env().unwrap().request_timeout_secs, 5);
+
+        unsafe {
+            env::set_var("REQUEST_TIMEOUT_SECS", "0");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("REQUEST_TIMEOUT_SECS"));
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_max_batch_get_ids() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("MAX_BATCH_GET_IDS", "50");
+        }
+        assert_eq!(Config::from_env().unwrap().max_batch_get_ids, 50);
+
+        for value in ["0", "-1", "many"] {
+            unsafe {
+                env::set_var("MAX_BATCH_GET_IDS", value);
+            }
+            let result = Config::from_env();
+            assert!(result.unwrap_err().to_string().contains("MAX_BATCH_GET_IDS"));
+        }
+    }
+
+    #[test]
+    fn test_spanner_retry_settings() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("SPANNER_MAX_RETRIES", "0");
+            env::set_var("SPANNER_RETRY_INITIAL_BACKOFF_MS", "10");
+            env::set_var("SPANNER_RETRY_MAX_BACKOFF_MS", "100");
+        }
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.spanner_max_retries, 0);
+        assert_eq!(config.spanner_retry_initial_backoff_ms, 10);
+        assert_eq!(config.spanner_retry_max_backoff_ms, 100);
+
+        for (var, value) in [
+            ("SPANNER_MAX_RETRIES", "-1"),
+            ("SPANNER_RETRY_INITIAL_BACKOFF_MS", "0"),
+            ("SPANNER_RETRY_MAX_BACKOFF_MS", "soon"),
+        ] {
+            clear_env_vars();
+            set_required_vars();
+            unsafe {
+                env::set_var(var, value);
+            }
+            let result = Config::from_env();
+            assert!(result.unwrap_err().to_string().contains(var));
+        }
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_history_max_versions() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("HISTORY_MAX_VERSIONS", "0");
+        }
+        assert_eq!(Config::from_env().unwrap().history_max_versions, 0);
+
+        unsafe {
+            env::set_var("HISTORY_MAX_VERSIONS", "-3");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("HISTORY_MAX_VERSIONS"));
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_list_limits() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("MAX_LIMIT", "500");
+            env::set_var("DEFAULT_LIMIT", "20");
+        }
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.max_limit, 500);
+        assert_eq!(config.default_limit, 20);
+
+        unsafe {
+            env::set_var("MAX_LIMIT", "0");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("MAX_LIMIT"));
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_max_search_rows() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("MAX_SEARCH_ROWS", "25");
+        }
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.max_search_rows, 25);
+
+        unsafe {
+            env::set_var("MAX_SEARCH_ROWS", "0");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("MAX_SEARCH_ROWS"));
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_max_document_bytes() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("MAX_DOCUMENT_BYTES", "4096");
+        }
+        assert_eq!(Config::from_env().unwrap().max_document_bytes, 4096);
+
+        unsafe {
+            env::set_var("MAX_DOCUMENT_BYTES", "0");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("MAX_DOCUMENT_BYTES"));
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_max_body_bytes() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("MAX_BODY_BYTES", "8192");
+        }
+        assert_eq!(Config::from_env().unwrap().max_body_bytes, 8192);
+
+        unsafe {
+            env::set_var("MAX_BODY_BYTES", "0");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("MAX_BODY_BYTES"));
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_key_mode() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("KEY_MODE", "string");
+        }
+        assert_eq!(Config::from_env().unwrap().key_mode, KeyMode::String);
+
+        unsafe {
+            env::set_var("KEY_MODE", "UUID");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("KEY_MODE"));
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_spanner_request_priority() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("SPANNER_REQUEST_PRIORITY", "LOW");
+        }
+        assert_eq!(Config::from_env().unwrap().spanner_request_priority, RequestPriority::Low);
+
+        unsafe {
+            env::set_var("SPANNER_REQUEST_PRIORITY", "high");
+        }
+        assert_eq!(Config::from_env().unwrap().spanner_request_priority, RequestPriority::High);
+
+        unsafe {
+            env::set_var("SPANNER_REQUEST_PRIORITY", "urgent");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("SPANNER_REQUEST_PRIORITY"));
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_spanner_sessions() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("SPANNER_MIN_SESSIONS", "0");
+            env::set_var("SPANNER_MAX_SESSIONS", "50");
+        }
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.spanner_min_sessions, Some(0));
+        assert_eq!(config.spanner_max_sessions, Some(50));
+
+        unsafe {
+            env::set_var("SPANNER_MAX_SESSIONS", "0");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("SPANNER_MAX_SESSIONS"));
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_instance_capacity() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("SPANNER_NODE_COUNT", "3");
+        }
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.spanner_node_count, Some(3));
+        assert_eq!(config.spanner_processing_units, None);
+
+        unsafe {
+            env::remove_var("SPANNER_NODE_COUNT");
+            env::set_var("SPANNER_PROCESSING_UNITS", "500");
+        }
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.spanner_node_count, None);
+        assert_eq!(config.spanner_processing_units, Some(500));
+
+        for (name, value) in [("SPANNER_PROCESSING_UNITS", "0"), ("SPANNER_NODE_COUNT", "-1")] {
+            clear_env_vars();
+            set_required_vars();
+            unsafe {
+                env::set_var(name, value);
+            }
+            let result = Config::from_env();
+            assert!(result.unwrap_err().to_string().contains(name));
+        }
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_node_count_and_processing_units_are_exclusive() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("SPANNER_NODE_COUNT", "2");
+            env::set_var("SPANNER_PROCESSING_UNITS", "2000");
+        }
+        let err = Config::from_env().unwrap_err().to_string();
+        assert!(err.contains("mutually exclusive"), "{}", err);
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_auto_provision() {
+        clear_env_vars();
+        set_required_vars();
+        assert!(!Config::from_env().unwrap().auto_provision, "Off by default against production");
+
+        unsafe {
+            env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+        assert!(Config::from_env().unwrap().auto_provision, "On by default against the emulator");
+
+        unsafe {
+            env::set_var("AUTO_PROVISION", "false");
+        }
+        assert!(!Config::from_env().unwrap().auto_provision);
+
+        unsafe {
+            env::remove_var("SPANNER_EMULATOR_HOST");
+            env::set_var("AUTO_PROVISION", "true");
+        }
+        assert!(Config::from_env().unwrap().auto_provision);
+
+        unsafe {
+            env::set_var("AUTO_PROVISION", "sometimes");
+        }
+        let err = Config::from_env().unwrap_err().to_string();
+        assert!(err.contains("AUTO_PROVISION"), "{}", err);
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_public_base_url() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("PUBLIC_BASE_URL", "https://kv.example.com/");
+        }
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.public_base_url, Some("https://kv.example.com".to_string()));
+
+        unsafe {
+            env::set_var("PUBLIC_BASE_URL", "kv.example.com");
+        }
+        let err = Config::from_env().unwrap_err().to_string();
+        assert!(err.contains("PUBLIC_BASE_URL must start with http:// or https://"), "{}", err);
+        clear_env_vars();
+    }
+
+    #[test]
+    fn test_validate_min_sessions_above_max() {
+        let config = Config {
+            spanner_min_sessions: Some(20),
+            spanner_max_sessions: Some(10),
+            ..Config::for_emulator("test-instance", "test-database")
+        };
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("SPANNER_MIN_SESSIONS=20 is above SPANNER_MAX_SESSIONS=10"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_default_limit_above_max() {
+        let config = Config {
+            max_limit: 10,
+            default_limit: 50,
+            ..Config::for_emulator("test-instance", "test-database")
+        };
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("DEFAULT_LIMIT=50 is above MAX_LIMIT=10"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_body_limit_below_document_limit() {
+        let config = Config {
+            max_body_bytes: 1000,
+            max_document_bytes: 4000,
+            ..Config::for_emulator("test-instance", "test-database")
+        };
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("MAX_BODY_BYTES=1000 is below MAX_DOCUMENT_BYTES=4000"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_instance_capacity_without_auto_provision() {
+        let config = Config {
+            auto_provision: false,
+            spanner_processing_units: Some(500),
+            ..Config::for_emulator("test-instance", "test-database")
+        };
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("AUTO_PROVISION"), "{}", err);
+    }
+
+    #[test]
+    fn test_warnings_for_limits_above_spanner_value_cap() {
+        assert!(Config::for_emulator("test-instance", "test-database").warnings().is_empty());
+
+        let config = Config {
+            max_body_bytes: SPANNER_MAX_VALUE_BYTES + 1,
+            ..Config::for_emulator("test-instance", "test-database")
+        };
+        let warnings = config.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("MAX_BODY_BYTES=10485761"), "{}", warnings[0]);
+        assert!(config.validate().is_ok(), "A warning, not a conflict");
+
+        let config = Config {
+            max_body_bytes: SPANNER_MAX_VALUE_BYTES * 2,
+            max_document_bytes: SPANNER_MAX_VALUE_BYTES * 2,
+            ..Config::for_emulator("test-instance", "test-database")
+        };
+        assert_eq!(config.warnings().len(), 2);
+    }
+
+    #[test]
+    fn test_validate_retry_backoff_bounds() {
+        let config = Config {
+            spanner_retry_initial_backoff_ms: 500,
+            spanner_retry_max_backoff_ms: 100,
+            ..Config::for_emulator("test-instance", "test-database")
+        };
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("SPANNER_RETRY_MAX_BACKOFF_MS=100"), "{}", message);
+    }
+
+    #[test]
+    fn test_admin_settings() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("ADMIN_TOKEN", "s3cret");
+            env::set_var("JOB_RETENTION_SECS", "600");
+        }
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.admin_token, Some("s3cret".to_string()));
+        assert_eq!(config.job_retention_secs, 600);
+
+        unsafe {
+            env::set_var("ADMIN_TOKEN", "");
+        }
+        assert_eq!(Config::from_env().unwrap().admin_token, None);
+    }
+
+    #[test]
+    fn test_max_documents() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("MAX_DOCUMENTS", "1000");
+        }
+        assert_eq!(Config::from_env().unwrap().max_documents, Some(1000));
+
+        for value in ["0", "lots"] {
+            unsafe {
+                env::set_var("MAX_DOCUMENTS", value);
+            }
+            let result = Config::from_env();
+            assert!(result.unwrap_err().to_string().contains("MAX_DOCUMENTS"));
+        }
+    }
+
+    #[test]
+    fn test_list_cache_max_age() {
+        clear_env_vars();
+        set_required_vars();
+        unsafe {
+            env::set_var("LIST_CACHE_MAX_AGE", "60");
+        }
+        assert_eq!(Config::from_env().unwrap().list_cache_max_age, Some(60));
+
+        unsafe {
+            env::set_var("LIST_CACHE_MAX_AGE", "-1");
+        }
+        let result = Config::from_env();
+        assert!(result.unwrap_err().to_string().contains("LIST_CACHE_MAX_AGE"));
+    }
+
+    #[test]
+    fn test_validate_accepts_defaults() {
+        assert!(Config::for_emulator("test-instance", "test-database").validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_zero_windows() {
+        let config = Config {
+            write_batch_window_ms: Some(0),
+            ramp_duration_secs: Some(0),
+            ..Config::for_emulator("test-instance", "test-database")
+        };
+
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("WRITE_BATCH_WINDOW_MS=0"), "{}", message);
+        assert!(message.contains("RAMP_DURATION_SECS=0"), "{}", message);
+    }
+
+    #[test]
+    fn test_validate_batch_exceeds_commit_limit() {
+        let config = Config {
+            write_batch_window_ms: Some(5),
+            write_batch_max_size: 50_000,
+            ..Config::for_emulator("test-instance", "test-database")
+        };
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("WRITE_BATCH_MAX_SIZE=50000"), "{}", message);
+
+        // The batch size is irrelevant while batching is disabled
+        let config = Config {
+            write_batch_window_ms: None,
+            ..config
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_transaction_tag_with_op() {
+        let config = Config {
+            spanner_transaction_tag: Some("team=kv,op=custom".to_string()),
+            ..Config::for_emulator("test-instance", "test-database")
+        };
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("SPANNER_TRANSACTION_TAG"), "{}", message);
+
+        let config = Config {
+            spanner_transaction_tag: Some("team=kv,scope=ops".to_string()),
+            ..config
+        };
+        assert!(config.validate().is_ok());
+    }
 }
@@ -0,0 +1,85 @@
+use std::borrow::Cow;
+
+/// Quote one field per RFC 4180, leaving it as is when nothing needs escaping
+///
+/// Fields holding a comma, a double quote, or a line break are wrapped in
+/// double quotes, with each embedded double quote doubled.
+pub fn field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// One CSV record, terminated by CRLF as RFC 4180 specifies
+pub fn record(fields: &[&str]) -> String {
+    let mut line = fields.iter().map(|value| field(value)).collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Split one record back into fields, per RFC 4180
+    fn parse(record: &str) -> Vec<String> {
+        let record = record.strip_suffix("\r\n").expect("records end with CRLF");
+        let mut fields = vec![String::new()];
+        let mut chars = record.chars().peekable();
+        let mut quoted = false;
+        while let Some(c) = chars.next() {
+            match (c, quoted) {
+                ('"', false) => quoted = true,
+                ('"', true) if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    fields.last_mut().unwrap().push('"');
+                }
+                ('"', true) => quoted = false,
+                (',', false) => fields.push(String::new()),
+                (c, _) => fields.last_mut().unwrap().push(c),
+            }
+        }
+        fields
+    }
+
+    #[test]
+    fn test_plain_fields_are_unquoted() {
+        assert_eq!(field("abc"), "abc");
+        assert_eq!(field(""), "");
+        assert_eq!(field("2024-01-01T00:00:00+00:00"), "2024-01-01T00:00:00+00:00");
+        assert!(matches!(field("plain"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_special_characters_are_quoted() {
+        assert_eq!(field("a,b"), "\"a,b\"");
+        assert_eq!(field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(field("cr\rlf"), "\"cr\rlf\"");
+        assert_eq!(field("\""), "\"\"\"\"");
+    }
+
+    #[test]
+    fn test_adversarial_values_round_trip() {
+        let json = [
+            serde_json::json!({"a": "x,y", "b": "\"quoted\"", "c": "multi\nline\r\n"}),
+            serde_json::json!(["\",\"", ",,,", "\"\"\"", "\r\n\r\n"]),
+            serde_json::json!("plain"),
+            serde_json::json!({"": ""}),
+        ];
+        let raw = ["\"", "\"\"", ",", "\r\n", "a\"b,c\nd\re", " padded ", "\",\r\n\","];
+        let values = json.iter().map(|value| value.to_string()).chain(raw.iter().map(|value| value.to_string()));
+        for value in values {
+            let line = record(&["key", &value, "tail"]);
+            assert_eq!(parse(&line), vec!["key".to_string(), value, "tail".to_string()]);
+        }
+    }
+
+    #[test]
+    fn test_record_joins_with_commas_and_crlf() {
+        assert_eq!(record(&["a", "b,c", "d"]), "a,\"b,c\",d\r\n");
+        assert_eq!(record(&["only"]), "only\r\n");
+    }
+}
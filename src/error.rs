@@ -1,8 +1,12 @@
+use crate::spanner::SpannerError;
 use axum::{
-    http::StatusCode,
+    extract::rejection::{BytesRejection, JsonRejection, QueryRejection},
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use gcloud_gax::grpc::Code;
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -34,14 +38,64 @@ pub struct UnhealthyResponse {
 pub enum ApiError {
     /// Invalid UUID format in path parameter
     InvalidUuid(String),
+    /// String key (`KEY_MODE=string`) that is too long or has disallowed characters
+    InvalidKey(String),
     /// Key not found in database
-    KeyNotFound(Uuid),
+    KeyNotFound(String),
+    /// No document matches the secondary key value
+    SecondaryKeyNotFound(String),
+    /// The document exists but the JSON Pointer doesn't resolve inside it
+    PointerNotFound { id: String, pointer: String },
     /// Database operation error
     DatabaseError(anyhow::Error),
+    /// Spanner call failed; the response status follows its gRPC code
+    Spanner(SpannerError),
     /// JSON parsing error
     JsonError(serde_json::Error),
     /// Invalid query parameter
     InvalidQueryParam(String),
+    /// Request body is well-formed JSON but not a valid request
+    InvalidRequest(String),
+    /// Key belongs to the reserved internal namespace
+    ReservedKey(String),
+    /// Request conflicts with the current state of the store
+    Conflict(String),
+    /// Endpoint depends on a feature that is not configured
+    FeatureDisabled(String),
+    /// Missing or invalid admin credentials
+    Unauthorized,
+    /// No job with this id is running or retained
+    JobNotFound(Uuid),
+    /// The store holds `MAX_DOCUMENTS` documents, so new keys are rejected
+    DocumentLimitReached(u64),
+    /// The document changed (or was deleted) since the version given in `If-Match`
+    PreconditionFailed(String),
+    /// A create-only write found a document already stored under the key
+    AlreadyExists(String),
+    /// The serialized document is larger than `MAX_DOCUMENT_BYTES`
+    PayloadTooLarge { size: usize, max: usize },
+    /// The request body couldn't be extracted; `status` is the one axum chose,
+    /// e.g. 413 for a body over `MAX_BODY_BYTES`
+    BodyRejected { status: StatusCode, message: String },
+    /// The handler ran longer than `REQUEST_TIMEOUT_SECS`
+    Timeout(Duration),
+}
+
+/// `Retry-After` seconds sent with a 503 for a retryable Spanner failure
+const RETRY_AFTER_SECS: u64 = 1;
+
+/// HTTP status for a failed Spanner call with the given gRPC code
+///
+/// Aborted transactions and an unavailable backend are transient, so clients
+/// get a 503 and may retry. Unknown or absent codes are 500.
+pub fn spanner_status(code: Option<Code>) -> StatusCode {
+    match code {
+        Some(Code::Aborted | Code::Unavailable) => StatusCode::SERVICE_UNAVAILABLE,
+        Some(Code::DeadlineExceeded) => StatusCode::GATEWAY_TIMEOUT,
+        Some(Code::InvalidArgument) => StatusCode::BAD_REQUEST,
+        Some(Code::AlreadyExists) => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
 }
 
 impl IntoResponse for ApiError {
@@ -51,14 +105,30 @@ impl IntoResponse for ApiError {
                 StatusCode::BAD_REQUEST,
                 format!("Invalid UUID format: expected format like '550e8400-e29b-41d4-a716-446655440000', got '{}'", id),
             ),
+            ApiError::InvalidKey(msg) => (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid key: {}", msg),
+            ),
             ApiError::KeyNotFound(id) => (
                 StatusCode::NOT_FOUND,
                 format!("Key not found: {}", id),
             ),
+            ApiError::SecondaryKeyNotFound(value) => (
+                StatusCode::NOT_FOUND,
+                format!("Secondary key not found: {}", value),
+            ),
+            ApiError::PointerNotFound { id, pointer } => (
+                StatusCode::NOT_FOUND,
+                format!("Path not found: {} does not resolve in document {}", pointer, id),
+            ),
             ApiError::DatabaseError(err) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Database error: {}", err),
             ),
+            ApiError::Spanner(err) => (
+                spanner_status(err.code()),
+                format!("Database error: {}", err),
+            ),
             ApiError::JsonError(err) => (
                 StatusCode::BAD_REQUEST,
                 format!("JSON parse error: {}", err),
@@ -67,13 +137,64 @@ impl IntoResponse for ApiError {
                 StatusCode::BAD_REQUEST,
                 format!("Invalid query parameter: {}", msg),
             ),
+            ApiError::InvalidRequest(msg) => (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid request: {}", msg),
+            ),
+            ApiError::ReservedKey(key) => (
+                StatusCode::FORBIDDEN,
+                format!("Key is reserved for internal use: {}", key),
+            ),
+            ApiError::Conflict(msg) => (
+                StatusCode::CONFLICT,
+                format!("Conflict: {}", msg),
+            ),
+            ApiError::FeatureDisabled(msg) => (
+                StatusCode::NOT_IMPLEMENTED,
+                format!("Feature disabled: {}", msg),
+            ),
+            ApiError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                "Unauthorized: a valid admin bearer token is required".to_string(),
+            ),
+            ApiError::JobNotFound(id) => (
+                StatusCode::NOT_FOUND,
+                format!("Job not found: {}", id),
+            ),
+            ApiError::DocumentLimitReached(max) => (
+                StatusCode::INSUFFICIENT_STORAGE,
+                format!("Document limit reached: the store is at its maximum of {} documents", max),
+            ),
+            ApiError::PreconditionFailed(id) => (
+                StatusCode::PRECONDITION_FAILED,
+                format!("Precondition failed: document {} has been modified or deleted since the If-Match version", id),
+            ),
+            ApiError::AlreadyExists(id) => (
+                StatusCode::CONFLICT,
+                format!("Key already exists: {}", id),
+            ),
+            ApiError::PayloadTooLarge { size, max } => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("Payload too large: the document is {} bytes, the maximum is {}", size, max),
+            ),
+            ApiError::BodyRejected { status, message } => (status, message),
+            ApiError::Timeout(duration) => (
+                StatusCode::GATEWAY_TIMEOUT,
+                format!("Request timed out after {:?}", duration),
+            ),
         };
 
         let body = Json(ErrorResponse {
             error: error_message,
         });
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if status == StatusCode::SERVICE_UNAVAILABLE {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(RETRY_AFTER_SECS));
+        }
+        response
     }
 }
 
@@ -85,7 +206,60 @@ impl From<uuid::Error> for ApiError {
 
 impl From<anyhow::Error> for ApiError {
     fn from(err: anyhow::Error) -> Self {
-        ApiError::DatabaseError(err)
+        match SpannerError::from(err) {
+            SpannerError::Other(err) => ApiError::DatabaseError(err),
+            err => err.into(),
+        }
+    }
+}
+
+impl From<SpannerError> for ApiError {
+    fn from(err: SpannerError) -> Self {
+        match err {
+            SpannerError::DocumentExists { id, .. } => ApiError::AlreadyExists(id),
+            err => ApiError::Spanner(err),
+        }
+    }
+}
+
+impl From<BytesRejection> for ApiError {
+    /// Keep axum's status but answer with a JSON body like every other error
+    fn from(rejection: BytesRejection) -> Self {
+        let status = rejection.status();
+        let message = if status == StatusCode::PAYLOAD_TOO_LARGE {
+            "Payload too large: the request body is larger than MAX_BODY_BYTES".to_string()
+        } else {
+            format!("Invalid request body: {}", rejection.body_text())
+        };
+        ApiError::BodyRejected { status, message }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            JsonRejection::BytesRejection(rejection) => rejection.into(),
+            rejection => ApiError::BodyRejected {
+                status: rejection.status(),
+                message: format!("Invalid request body: {}", rejection.body_text()),
+            },
+        }
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    /// Answer a query string that doesn't fit the handler's parameters with the
+    /// same JSON 400 as any other bad parameter, naming the field at fault
+    fn from(rejection: QueryRejection) -> Self {
+        let message = match std::error::Error::source(&rejection) {
+            Some(source) => source.to_string(),
+            None => rejection.body_text(),
+        };
+        // axum prefixes the field name, which messages like SortOrder's already start with
+        match message.split_once(": ") {
+            Some((field, rest)) if rest.starts_with(field) => ApiError::InvalidQueryParam(rest.to_string()),
+            _ => ApiError::InvalidQueryParam(message),
+        }
     }
 }
 
@@ -94,3 +268,73 @@ impl From<serde_json::Error> for ApiError {
         ApiError::JsonError(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gcloud_gax::grpc::Status;
+
+    fn response_for(status: Status) -> Response {
+        ApiError::from(SpannerError::from(status)).into_response()
+    }
+
+    #[test]
+    fn test_query_rejection_names_field_once() {
+        use axum::extract::{FromRequestParts, Query};
+        use crate::models::ListQuery;
+
+        let message = |uri: &str| {
+            let (mut parts, _) = axum::http::Request::builder().uri(uri).body(()).unwrap().into_parts();
+            let rejection = futures_util::FutureExt::now_or_never(Query::<ListQuery>::from_request_parts(&mut parts, &()))
+                .unwrap()
+                .err()
+                .unwrap();
+            match ApiError::from(rejection) {
+                ApiError::InvalidQueryParam(message) => message,
+                other => panic!("unexpected error {:?}", other),
+            }
+        };
+        assert!(message("/kv?sort=newest").starts_with("sort must be one of: key_asc,"));
+        assert_eq!(message("/kv?limit=ten"), "limit: invalid digit found in string");
+    }
+
+    #[test]
+    fn test_spanner_status() {
+        assert_eq!(spanner_status(Some(Code::Aborted)), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(spanner_status(Some(Code::Unavailable)), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(spanner_status(Some(Code::DeadlineExceeded)), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(spanner_status(Some(Code::InvalidArgument)), StatusCode::BAD_REQUEST);
+        assert_eq!(spanner_status(Some(Code::Internal)), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(spanner_status(Some(Code::Unknown)), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(spanner_status(None), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_spanner_error_responses() {
+        let response = response_for(Status::new(Code::Aborted, "transaction aborted"));
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+
+        let response = response_for(Status::new(Code::DeadlineExceeded, "deadline exceeded"));
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
+
+        let response = response_for(Status::new(Code::PermissionDenied, "denied"));
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_status_survives_anyhow_context() {
+        let status = Status::new(Code::Unavailable, "backend unavailable");
+        let err = anyhow::Error::new(gcloud_spanner::client::Error::GRPC(status))
+            .context("Failed to upsert data to Spanner");
+
+        let api_error = ApiError::from(err);
+        assert!(matches!(&api_error, ApiError::Spanner(err) if err.code() == Some(Code::Unavailable)));
+        assert_eq!(api_error.into_response().status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // Errors with no gRPC status stay plain database errors
+        let api_error = ApiError::from(anyhow::anyhow!("Failed to serialize JSON data"));
+        assert!(matches!(api_error, ApiError::DatabaseError(_)));
+    }
+}
@@ -0,0 +1,59 @@
+use crate::config::Config;
+use crate::error::ApiError;
+use axum::http::{header, HeaderMap};
+
+/// Check the `Authorization: Bearer <ADMIN_TOKEN>` header on an admin request
+///
+/// Admin endpoints are disabled (501) when `ADMIN_TOKEN` is not configured, and
+/// reject a missing or wrong token with 401.
+pub fn require_admin(config: &Config, headers: &HeaderMap) -> Result<(), ApiError> {
+    let expected = config.admin_token.as_deref().ok_or_else(|| {
+        ApiError::FeatureDisabled("admin endpoints require ADMIN_TOKEN to be configured".to_string())
+    })?;
+
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(()),
+        _ => Err(ApiError::Unauthorized),
+    }
+}
+
+/// Compare secrets without returning early on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_require_admin() {
+        let config = Config::for_emulator("test-instance", "test-database");
+        let mut headers = HeaderMap::new();
+        assert!(matches!(
+            require_admin(&config, &headers),
+            Err(ApiError::FeatureDisabled(_))
+        ));
+
+        let config = Config {
+            admin_token: Some("s3cret".to_string()),
+            ..config
+        };
+        assert!(matches!(require_admin(&config, &headers), Err(ApiError::Unauthorized)));
+
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer wrong"));
+        assert!(matches!(require_admin(&config, &headers), Err(ApiError::Unauthorized)));
+
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("s3cret"));
+        assert!(matches!(require_admin(&config, &headers), Err(ApiError::Unauthorized)));
+
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer s3cret"));
+        assert!(require_admin(&config, &headers).is_ok());
+    }
+}
@@ -0,0 +1,362 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::config::Config;
+use crate::handlers::cache_control::write_cache_headers;
+use crate::handlers::key::parse_key;
+use crate::models::{BatchEntryStatus, BatchPutEntry, BatchPutQuery, BatchPutResponse, BatchPutResult, DryRunResponse};
+use crate::routes;
+use crate::spanner::{BatchWriteResult, ExistingKeys, BATCH_CHUNK_SIZE};
+use crate::state::AppState;
+use axum::{body::Bytes, extract::rejection::BytesRejection, extract::Query, extract::State, http::StatusCode, response::IntoResponse, response::Response, Json};
+use std::collections::HashMap;
+
+/// POST /kv:batch handler - Store many JSON documents in one request
+///
+/// Every entry is validated before anything is written: if any id is malformed
+/// or repeated, the response lists all of them and nothing is stored. Documents
+/// are then committed in chunks of a few hundred, in request order. Each chunk is
+/// atomic, but the batch as a whole is not: if a commit fails after earlier
+/// chunks succeeded, the response is 207 with each entry's status. Re-sending
+/// the batch is safe because every write is an upsert.
+///
+/// With `?dry_run=true` the whole batch is validated the same way, including the
+/// `MAX_DOCUMENTS` check, and the response is 200 with
+/// `{"dry_run": true, "valid": true}`; nothing is written.
+#[utoipa::path(
+    post,
+    path = routes::KV_BATCH,
+    params(
+        ("dry_run" = Option<bool>, Query, description = "Validate every entry and return 200 without storing anything")
+    ),
+    request_body = Vec<BatchPutEntry>,
+    responses(
+        (status = 200, description = "All documents stored, or with dry_run=true a DryRunResponse for a valid batch", body = BatchPutResponse, headers(
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 207, description = "Some chunks were committed before one failed; see per-entry status", body = BatchPutResponse),
+        (status = 400, description = "Invalid JSON, or malformed or duplicate ids; lists every bad entry", body = ErrorResponse),
+        (status = 403, description = "An id is in the reserved internal namespace", body = ErrorResponse),
+        (status = 500, description = "Database error; nothing was written", body = ErrorResponse),
+        (status = 507, description = "New keys rejected because they would exceed MAX_DOCUMENTS", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn batch_put_handler(
+    State(state): State<AppState>,
+    Query(params): Query<BatchPutQuery>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Response, ApiError> {
+    let body = body?;
+    let entries: Vec<BatchPutEntry> = serde_json::from_slice(&body)?;
+    let items = validate_entries(&state.config, entries)?;
+
+    let ids: Vec<String> = items.iter().map(|(id, _)| id.clone()).collect();
+    if !state.spanner_client.has_room_for_many(&ids).await? {
+        let max = state.spanner_client.document_limit().unwrap_or_default();
+        tracing::warn!("Rejected batch of {} documents: store would exceed its limit of {}", ids.len(), max);
+        return Err(ApiError::DocumentLimitReached(max));
+    }
+
+    if params.dry_run.unwrap_or(false) {
+        tracing::info!("Validated a batch of {} documents without storing it (dry run)", ids.len());
+        return Ok((
+            write_cache_headers(&state.config),
+            Json(DryRunResponse { dry_run: true, valid: true }),
+        )
+            .into_response());
+    }
+
+    let BatchWriteResult { written, error, .. } = state.spanner_client.write_batch(items, ExistingKeys::Overwrite).await;
+    let error = match error {
+        // Nothing was committed, so this is an ordinary failed write
+        Some(error) if written == 0 => return Err(ApiError::DatabaseError(error)),
+        error => error.map(|e| format!("{:#}", e)),
+    };
+    let status = if error.is_some() { StatusCode::MULTI_STATUS } else { StatusCode::OK };
+
+    tracing::info!("Stored {} of {} documents in a batch", written, ids.len());
+    Ok((
+        status,
+        write_cache_headers(&state.config),
+        Json(BatchPutResponse {
+            written,
+            results: entry_results(&ids, written, error.is_some()),
+            error,
+        }),
+    )
+        .into_response())
+}
+
+/// Parse every entry's id, rejecting the batch if any is malformed or repeated
+///
+/// A reserved id is reported only once every entry is otherwise well formed.
+fn validate_entries(config: &Config, entries: Vec<BatchPutEntry>) -> Result<Vec<(String, serde_json::Value)>, ApiError> {
+    let mut items = Vec::with_capacity(entries.len());
+    let mut malformed = Vec::new();
+    let mut reserved = None;
+    let mut first_seen: HashMap<String, usize> = HashMap::new();
+    let mut duplicates = Vec::new();
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        let id = match parse_key(config, &entry.id) {
+            Ok(id) => id,
+            Err(ApiError::ReservedKey(key)) => {
+                reserved.get_or_insert(key);
+                continue;
+            }
+            Err(_) => {
+                malformed.push(format!("{} ('{}')", index, entry.id));
+                continue;
+            }
+        };
+        // Spellings of the same UUID are the same key, so compare parsed ids
+        if let Some(first) = first_seen.insert(id.clone(), index) {
+            first_seen.insert(id.clone(), first);
+            duplicates.push(format!("{} (same id as entry {})", index, first));
+        }
+        items.push((id, entry.data));
+    }
+
+    let mut problems = Vec::new();
+    if !malformed.is_empty() {
+        problems.push(format!("entries have invalid keys: {}", malformed.join(", ")));
+    }
+    if !duplicates.is_empty() {
+        problems.push(format!("entries repeat an id: {}", duplicates.join(", ")));
+    }
+    if !problems.is_empty() {
+        return Err(ApiError::InvalidRequest(problems.join("; ")));
+    }
+    if let Some(key) = reserved {
+        return Err(ApiError::ReservedKey(key));
+    }
+    Ok(items)
+}
+
+/// Per-entry status, given how many leading entries were committed
+///
+/// When the batch failed, the chunk right after the written ones is the one
+/// whose commit failed, and everything after it was never attempted.
+fn entry_results(ids: &[String], written: usize, failed: bool) -> Vec<BatchPutResult> {
+    let failed_end = if failed { (written + BATCH_CHUNK_SIZE).min(ids.len()) } else { written };
+    ids.iter()
+        .enumerate()
+        .map(|(index, id)| BatchPutResult {
+            id: id.to_string(),
+            status: if index < written {
+                BatchEntryStatus::Written
+            } else if index < failed_end {
+                BatchEntryStatus::Failed
+            } else {
+                BatchEntryStatus::NotAttempted
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::post, Router};
+    use serde_json::json;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> (Router, SpannerClient) {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("put-endpoint-test", "put-endpoint-test-db");
+
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client: spanner_client.clone(),
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        let app = Router::new()
+            .route(routes::KV_BATCH, post(batch_put_handler))
+            .with_state(state);
+        (app, spanner_client)
+    }
+
+    fn batch_request(entries: &serde_json::Value) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/kv:batch")
+            .header("content-type", "application/json")
+            .body(Body::from(entries.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_batch_put_2500_documents() {
+        let (app, client) = setup_test_app().await;
+
+        let ids: Vec<Uuid> = (0..2500).map(|_| Uuid::new_v4()).collect();
+        let entries: Vec<_> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| json!({"id": id.to_string(), "data": {"index": i}}))
+            .collect();
+
+        let response = app.oneshot(batch_request(&json!(entries))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let batch_response: BatchPutResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(batch_response.written, 2500);
+        assert!(batch_response.error.is_none());
+        assert_eq!(batch_response.results.len(), 2500);
+        assert!(batch_response.results.iter().all(|result| result.status == BatchEntryStatus::Written));
+        assert_eq!(batch_response.results[42].id, ids[42].to_string());
+
+        // Spot-check documents from the first, a middle and the last chunk
+        for i in [0, 1234, 2499] {
+            let stored = client.read(ids[i]).await.unwrap().expect("Document should exist");
+            assert_eq!(stored.data, json!({"index": i}));
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_put_500_documents() {
+        let (app, client) = setup_test_app().await;
+
+        let ids: Vec<Uuid> = (0..500).map(|_| Uuid::new_v4()).collect();
+        let entries: Vec<_> = ids
+            .iter()
+            .map(|id| json!({"id": id.to_string(), "data": {"id": id.to_string()}}))
+            .collect();
+
+        let response = app.oneshot(batch_request(&json!(entries))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for id in ids.iter().step_by(50) {
+            let stored = client.read(*id).await.unwrap().expect("Document should exist");
+            assert_eq!(stored.data, json!({"id": id.to_string()}));
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_put_rejects_duplicate_ids() {
+        let (app, client) = setup_test_app().await;
+
+        let id = Uuid::new_v4();
+        let entries = json!([
+            {"id": id.to_string(), "data": {"copy": 1}},
+            {"id": Uuid::new_v4().to_string(), "data": {}},
+            {"id": id.to_string().to_uppercase(), "data": {"copy": 2}},
+        ]);
+
+        let response = app.oneshot(batch_request(&entries)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains("2 (same id as entry 0)"), "{}", error_response.error);
+        assert!(client.read(id).await.unwrap().is_none(), "Nothing should be written");
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_put_dry_run_writes_nothing() {
+        let (app, client) = setup_test_app().await;
+
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let entries: Vec<_> = ids
+            .iter()
+            .map(|id| json!({"id": id.to_string(), "data": {"id": id.to_string()}}))
+            .collect();
+        let dry_run = |entries: &serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri("/kv:batch?dry_run=true")
+                .header("content-type", "application/json")
+                .body(Body::from(entries.to_string()))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(dry_run(&json!(entries))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let dry_run_response: DryRunResponse = serde_json::from_slice(&body).unwrap();
+        assert!(dry_run_response.dry_run && dry_run_response.valid);
+        for id in &ids {
+            assert!(client.read(*id).await.unwrap().is_none(), "Dry run should not write {}", id);
+        }
+
+        // A bad entry still fails the whole batch
+        let entries = json!([{"id": ids[0].to_string(), "data": {}}, {"id": "not-a-uuid", "data": {}}]);
+        let response = app.oneshot(dry_run(&entries)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[test]
+    fn test_entry_results_after_failed_chunk() {
+        let ids: Vec<String> = (0..BATCH_CHUNK_SIZE * 3).map(|_| Uuid::new_v4().to_string()).collect();
+
+        let results = entry_results(&ids, BATCH_CHUNK_SIZE, true);
+        assert_eq!(results[0].status, BatchEntryStatus::Written);
+        assert_eq!(results[BATCH_CHUNK_SIZE - 1].status, BatchEntryStatus::Written);
+        assert_eq!(results[BATCH_CHUNK_SIZE].status, BatchEntryStatus::Failed);
+        assert_eq!(results[2 * BATCH_CHUNK_SIZE - 1].status, BatchEntryStatus::Failed);
+        assert_eq!(results[2 * BATCH_CHUNK_SIZE].status, BatchEntryStatus::NotAttempted);
+
+        let results = entry_results(&ids, ids.len(), false);
+        assert!(results.iter().all(|result| result.status == BatchEntryStatus::Written));
+    }
+
+    #[tokio::test]
+    async fn test_batch_put_rejects_bad_uuid_before_writing() {
+        let (app, client) = setup_test_app().await;
+
+        let good = Uuid::new_v4();
+        let entries = json!([
+            {"id": good.to_string(), "data": {"ok": true}},
+            {"id": "not-a-uuid", "data": {"ok": false}},
+        ]);
+
+        let response = app.oneshot(batch_request(&entries)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains("1 ('not-a-uuid')"), "{}", error_response.error);
+
+        assert!(client.read(good).await.unwrap().is_none(), "Nothing should be written");
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
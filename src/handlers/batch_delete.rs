@@ -0,0 +1,164 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::batch_get::parse_ids;
+use crate::handlers::cache_control::write_cache_headers;
+use crate::models::{BatchDeleteRequest, BatchDeleteResponse, DeleteQuery};
+use crate::routes;
+use crate::spanner::{BatchDeleteResult, MAX_BATCH_DELETE_IDS};
+use crate::state::AppState;
+use axum::{body::Bytes, extract::rejection::BytesRejection, extract::Query, extract::State, http::HeaderMap, http::StatusCode, Json};
+
+/// POST /kv:batchDelete handler - Remove many JSON documents atomically
+///
+/// Every id is validated before anything is deleted: if any is malformed, the
+/// response lists them and nothing is removed. The deletes are then committed
+/// together, so either all keys are gone afterwards or none were touched.
+/// Like `DELETE /kv/:id`, documents are soft-deleted and can be restored with
+/// `POST /kv/:id/undelete`; `?hard=true` removes them and their history
+/// permanently. Missing keys are not an error; `existed` counts the keys that
+/// had a live document, so repeating a batch is safe and reports `existed: 0`.
+#[utoipa::path(
+    post,
+    path = routes::KV_BATCH_DELETE,
+    params(
+        ("hard" = Option<bool>, Query, description = "Remove the documents permanently instead of soft-deleting them")
+    ),
+    request_body = BatchDeleteRequest,
+    responses(
+        (status = 200, description = "Keys deleted", body = BatchDeleteResponse, headers(
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 400, description = "Invalid JSON, malformed ids, or more than 1000 distinct ids", body = ErrorResponse),
+        (status = 403, description = "An id is in the reserved internal namespace", body = ErrorResponse),
+        (status = 500, description = "Database error; nothing was deleted", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn batch_delete_handler(
+    State(state): State<AppState>,
+    Query(query): Query<DeleteQuery>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<(StatusCode, HeaderMap, Json<BatchDeleteResponse>), ApiError> {
+    let body = body?;
+    let request: BatchDeleteRequest = serde_json::from_slice(&body)?;
+    let ids = parse_ids(&state.config, &request.ids)?;
+    if ids.len() > MAX_BATCH_DELETE_IDS {
+        return Err(ApiError::InvalidRequest(format!(
+            "at most {} distinct ids may be deleted at once (got {})",
+            MAX_BATCH_DELETE_IDS,
+            ids.len()
+        )));
+    }
+
+    let hard = query.hard.unwrap_or(false);
+    let BatchDeleteResult { existed, applied } = state.spanner_client.delete_many(&ids, hard).await?;
+
+    tracing::info!("Batch delete removed {} of {} keys (hard: {})", existed, applied, hard);
+    Ok((
+        StatusCode::OK,
+        write_cache_headers(&state.config),
+        Json(BatchDeleteResponse { existed, applied }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::post, Router};
+    use serde_json::json;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> (Router, SpannerClient) {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("put-endpoint-test", "put-endpoint-test-db");
+
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client: spanner_client.clone(),
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        let app = Router::new()
+            .route(routes::KV_BATCH_DELETE, post(batch_delete_handler))
+            .with_state(state);
+        (app, spanner_client)
+    }
+
+    fn batch_delete_request(query: &str, ids: &[String]) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/kv:batchDelete{}", query))
+            .header("content-type", "application/json")
+            .body(Body::from(json!({"ids": ids}).to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_batch_delete_is_idempotent() {
+        let (app, client) = setup_test_app().await;
+
+        let stored: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        for id in &stored {
+            client.upsert(*id, json!({"doomed": true})).await.unwrap();
+        }
+        let mut ids: Vec<String> = stored.iter().map(Uuid::to_string).collect();
+        ids.push(Uuid::new_v4().to_string());
+
+        // The first run soft-deletes the stored keys; the repeat finds nothing live but
+        // still succeeds, and a hard delete then removes the soft-deleted rows
+        for (query, expected_existed) in [("", 3), ("", 0), ("?hard=true", 3)] {
+            let response = app.clone().oneshot(batch_delete_request(query, &ids)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let batch_response: BatchDeleteResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(batch_response.existed, expected_existed);
+            assert_eq!(batch_response.applied, 4);
+        }
+
+        for id in &stored {
+            assert!(client.read_including_deleted(&id.to_string()).await.unwrap().is_none());
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_delete_bad_uuid_deletes_nothing() {
+        let (app, client) = setup_test_app().await;
+
+        let stored = Uuid::new_v4();
+        client.upsert(stored, json!({"keep": true})).await.unwrap();
+
+        let ids = vec![stored.to_string(), "not-a-uuid".to_string()];
+        let response = app.oneshot(batch_delete_request("", &ids)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains("1 ('not-a-uuid')"), "{}", error_response.error);
+
+        assert!(client.read(stored).await.unwrap().is_some(), "Nothing should be deleted");
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::config::Config;
+use crate::handlers::etag::etag;
+use crate::handlers::key::parse_key;
+use crate::handlers::stream::rows_body;
+use crate::models::{BatchGetRequest, BatchGetResponse, GetResponse};
+use crate::routes;
+use crate::spanner::KvEntry;
+use crate::state::AppState;
+use axum::{
+    body::Bytes,
+    extract::{rejection::BytesRejection, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::HashSet;
+
+/// Content type of newline-delimited JSON
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// POST /kv:batchGet handler - Retrieve many JSON documents in one request
+///
+/// All documents are fetched with a single query. `found` lists the documents
+/// that exist and `missing` the ids that don't, both in request order; an id
+/// repeated in the request appears once. Ids are validated before anything is
+/// read, and at most `MAX_BATCH_GET_IDS` may be requested at once.
+///
+/// With `Accept: application/x-ndjson`, each found document is instead
+/// written as one line, in the shape of a `found` entry, as Spanner returns
+/// it: lines aren't in request order and misses are left out. A read that
+/// fails after the first line aborts the body, as for `GET /kv/stream`.
+#[utoipa::path(
+    post,
+    path = routes::KV_BATCH_GET,
+    request_body = BatchGetRequest,
+    responses(
+        (status = 200, description = "Documents retrieved", content(
+            (BatchGetResponse = "application/json"),
+            (GetResponse = "application/x-ndjson")
+        )),
+        (status = 400, description = "Invalid JSON, malformed ids, or more ids than MAX_BATCH_GET_IDS", body = ErrorResponse),
+        (status = 403, description = "An id is in the reserved internal namespace", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn batch_get_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Response, ApiError> {
+    let body = body?;
+    let request: BatchGetRequest = serde_json::from_slice(&body)?;
+    if request.ids.len() > state.config.max_batch_get_ids {
+        return Err(ApiError::InvalidRequest(format!(
+            "at most {} ids may be requested at once (got {})",
+            state.config.max_batch_get_ids,
+            request.ids.len()
+        )));
+    }
+
+    let ids = parse_ids(&state.config, &request.ids)?;
+
+    if ndjson_requested(&headers) {
+        let rows = state.spanner_client.read_many_stream(&ids);
+        let body = rows_body(rows, Vec::new(), |entry| {
+            let mut line = serde_json::to_vec(&found_entry(entry))?;
+            line.push(b'\n');
+            Ok(line)
+        })
+        .await?;
+        tracing::info!("Batch GET streaming up to {} documents", ids.len());
+        return Ok(([(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)], body).into_response());
+    }
+
+    let mut entries = state.spanner_client.read_many(&ids).await?.into_iter().peekable();
+    let mut found = Vec::new();
+    let mut missing = Vec::new();
+    // Entries come back in the order of `ids`, so the next one either matches or the id is missing
+    for id in ids.iter().cloned() {
+        match entries.next_if(|entry| entry.key == id) {
+            Some(entry) => found.push(found_entry(entry)),
+            None => missing.push(id),
+        }
+    }
+
+    tracing::info!("Batch GET found {} of {} documents", found.len(), ids.len());
+    Ok(Json(BatchGetResponse { found, missing }).into_response())
+}
+
+/// Whether an `Accept` header names `application/x-ndjson`
+fn ndjson_requested(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media| media.split(';').next().unwrap_or("").trim().eq_ignore_ascii_case(NDJSON_CONTENT_TYPE))
+}
+
+/// A found document as listed in the response
+fn found_entry(entry: KvEntry) -> GetResponse {
+    GetResponse {
+        etag: Some(etag(entry.updated_at)),
+        id: entry.key,
+        data: entry.value,
+        version: Some(entry.version),
+        deleted_at: None,
+        created_at: Some(entry.created_at.to_rfc3339()),
+        updated_at: Some(entry.updated_at.to_rfc3339()),
+    }
+}
+
+/// Parse every id as a key for the configured `KEY_MODE`, rejecting the request if any is malformed
+///
+/// Repeated ids, including different spellings of the same UUID, are kept once.
+/// Malformed ids are all listed before a reserved one is reported.
+pub(crate) fn parse_ids(config: &Config, raw_ids: &[String]) -> Result<Vec<String>, ApiError> {
+    let mut ids = Vec::with_capacity(raw_ids.len());
+    let mut seen = HashSet::with_capacity(raw_ids.len());
+    let mut malformed = Vec::new();
+    let mut reserved = None;
+
+    for (index, raw) in raw_ids.iter().enumerate() {
+        match parse_key(config, raw) {
+            Ok(id) => {
+                if seen.insert(id.clone()) {
+                    ids.push(id);
+                }
+            }
+            Err(ApiError::ReservedKey(key)) => {
+                reserved.get_or_insert(key);
+            }
+            Err(_) => malformed.push(format!("{} ('{}')", index, raw)),
+        }
+    }
+
+    if !malformed.is_empty() {
+        return Err(ApiError::InvalidRequest(format!(
+            "ids are not valid keys: {}",
+            malformed.join(", ")
+        )));
+    }
+    if let Some(key) = reserved {
+        return Err(ApiError::ReservedKey(key));
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::KeyMode;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, http::StatusCode, routing::post, Router};
+    use serde_json::json;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app(max_batch_get_ids: usize) -> (Router, SpannerClient) {
+        setup_test_app_with(Config {
+            max_batch_get_ids,
+            ..Config::for_emulator("put-endpoint-test", "put-endpoint-test-db")
+        })
+        .await
+    }
+
+    async fn setup_test_app_with(config: Config) -> (Router, SpannerClient) {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client: spanner_client.clone(),
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        let app = Router::new()
+            .route(routes::KV_BATCH_GET, post(batch_get_handler))
+            .with_state(state);
+        (app, spanner_client)
+    }
+
+    fn batch_get_request(ids: &[String]) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/kv:batchGet")
+            .header("content-type", "application/json")
+            .body(Body::from(json!({"ids": ids}).to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_batch_get_found_and_missing() {
+        let (app, client) = setup_test_app(1000).await;
+
+        let stored: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        for (i, id) in stored.iter().enumerate() {
+            client.upsert(*id, json!({"index": i})).await.unwrap();
+        }
+        let absent = [Uuid::new_v4(), Uuid::new_v4()];
+
+        // Interleave hits and misses, out of insertion order, with a repeat in another spelling
+        let ids = vec![
+            stored[2].to_string(),
+            absent[0].to_string(),
+            stored[0].to_string(),
+            stored[2].to_string().to_uppercase(),
+            absent[1].to_string(),
+            stored[1].to_string(),
+        ];
+
+        let response = app.oneshot(batch_get_request(&ids)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let batch_response: BatchGetResponse = serde_json::from_slice(&body).unwrap();
+
+        let found: Vec<(String, serde_json::Value)> = batch_response
+            .found
+            .into_iter()
+            .map(|document| (document.id, document.data))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (stored[2].to_string(), json!({"index": 2})),
+                (stored[0].to_string(), json!({"index": 0})),
+                (stored[1].to_string(), json!({"index": 1})),
+            ]
+        );
+        assert_eq!(batch_response.missing, vec![absent[0].to_string(), absent[1].to_string()]);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_get_rejects_too_many_or_malformed_ids() {
+        let (app, _client) = setup_test_app(2).await;
+
+        let too_many: Vec<String> = (0..3).map(|_| Uuid::new_v4().to_string()).collect();
+        let response = app.clone().oneshot(batch_get_request(&too_many)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains("at most 2"), "{}", error_response.error);
+
+        let malformed = vec![Uuid::new_v4().to_string(), "not-a-uuid".to_string()];
+        let response = app.oneshot(batch_get_request(&malformed)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains("1 ('not-a-uuid')"), "{}", error_response.error);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_get_string_keys() {
+        let (app, client) = setup_test_app_with(Config {
+            key_mode: KeyMode::String,
+            ..Config::for_emulator("put-endpoint-test", "put-endpoint-test-db")
+        })
+        .await;
+        let base = &Uuid::new_v4().simple().to_string()[..16];
+        let stored = format!("user:{}", base);
+        let absent = format!("member:{}", base);
+        client.upsert_key(&stored, json!({"name": "a"})).await.unwrap();
+
+        let response = app.clone().oneshot(batch_get_request(&[absent.clone(), stored.clone()])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let batch_response: BatchGetResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(batch_response.found.len(), 1);
+        assert_eq!((&batch_response.found[0].id, &batch_response.found[0].data), (&stored, &json!({"name": "a"})));
+        assert_eq!(batch_response.missing, vec![absent]);
+
+        let response = app.oneshot(batch_get_request(&["bad key!".to_string()])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_get_ndjson() {
+        let (app, client) = setup_test_app(1000).await;
+
+        let stored: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        for (i, id) in stored.iter().enumerate() {
+            client.upsert(*id, json!({"index": i})).await.unwrap();
+        }
+        let mut ids: Vec<String> = stored.iter().map(Uuid::to_string).collect();
+        ids.push(Uuid::new_v4().to_string());
+        ids.push(stored[0].to_string());
+
+        let mut request = batch_get_request(&ids);
+        request
+            .headers_mut()
+            .insert(header::ACCEPT, "application/json;q=0.5, application/x-ndjson".parse().unwrap());
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], NDJSON_CONTENT_TYPE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        // One line per found document, in no particular order; the miss and the repeat are left out
+        let mut found: Vec<(String, serde_json::Value)> = std::str::from_utf8(&body)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let document: GetResponse = serde_json::from_str(line).unwrap();
+                assert!(document.etag.is_some() && document.version == Some(1));
+                (document.id, document.data)
+            })
+            .collect();
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut expected: Vec<(String, serde_json::Value)> = stored
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.to_string(), json!({"index": i})))
+            .collect();
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(found, expected);
+
+        // Nothing found is an empty body, not an error
+        let mut request = batch_get_request(&[Uuid::new_v4().to_string()]);
+        request.headers_mut().insert(header::ACCEPT, NDJSON_CONTENT_TYPE.parse().unwrap());
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::config::Config;
+use axum::http::{header, HeaderMap, HeaderValue};
+
+/// `Cache-Control` for successful reads, when `LIST_CACHE_MAX_AGE` is set
+///
+/// Only successful responses should carry this: a cached 404 would hide a
+/// document written shortly after.
+pub fn read_cache_headers(config: &Config) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(max_age) = config.list_cache_max_age
+        && let Ok(value) = HeaderValue::from_str(&format!("public, max-age={}", max_age))
+    {
+        headers.insert(header::CACHE_CONTROL, value);
+    }
+    headers
+}
+
+/// `Cache-Control: no-store` for writes, when `LIST_CACHE_MAX_AGE` is set
+pub fn write_cache_headers(config: &Config) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if config.list_cache_max_age.is_some() {
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_headers() {
+        let config = Config::for_emulator("test-instance", "test-database");
+        assert!(read_cache_headers(&config).is_empty());
+        assert!(write_cache_headers(&config).is_empty());
+
+        let config = Config {
+            list_cache_max_age: Some(60),
+            ..config
+        };
+        assert_eq!(read_cache_headers(&config)[header::CACHE_CONTROL], "public, max-age=60");
+        assert_eq!(write_cache_headers(&config)[header::CACHE_CONTROL], "no-store");
+    }
+}
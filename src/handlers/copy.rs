@@ -0,0 +1,238 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::cache_control::write_cache_headers;
+use crate::handlers::key::parse_key;
+use crate::models::{CopyQuery, CopyRequest, CopyResponse};
+use crate::routes;
+use crate::spanner::CopyOutcome;
+use crate::state::AppState;
+use axum::{extract::rejection::JsonRejection, extract::Path, extract::Query, extract::State, http::HeaderMap, http::StatusCode, Json};
+
+/// POST /kv/:id/copy handler - Copy a document to another key
+///
+/// The source is read and written under `target_id` in one transaction, so
+/// the data never leaves the server. Fails with 409 if `target_id` is taken,
+/// unless `overwrite=true` is passed.
+/// Query parameters:
+/// - overwrite: Replace a document already stored at target_id (optional, default: false)
+#[utoipa::path(
+    post,
+    path = routes::KV_COPY,
+    params(
+        ("id" = String, Path, description = "Key of the document to copy: a UUID, or any valid key in KEY_MODE=string"),
+        ("overwrite" = Option<bool>, Query, description = "Replace a document already stored at target_id")
+    ),
+    request_body = CopyRequest,
+    responses(
+        (status = 201, description = "Document copied", body = CopyResponse, headers(
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 400, description = "Invalid UUID format or KEY_MODE=string key, or target_id equal to id", body = ErrorResponse),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 409, description = "A document already exists at target_id", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+        (status = 507, description = "New key rejected because the store is at MAX_DOCUMENTS", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn copy_handler(
+    State(state): State<AppState>,
+    Path(id_str): Path<String>,
+    Query(query): Query<CopyQuery>,
+    request: Result<Json<CopyRequest>, JsonRejection>,
+) -> Result<(StatusCode, HeaderMap, Json<CopyResponse>), ApiError> {
+    let Json(request) = request?;
+    let id = parse_key(&state.config, &id_str)?;
+    let target_id = parse_key(&state.config, &request.target_id)?;
+    if id == target_id {
+        return Err(ApiError::InvalidRequest("target_id must differ from the source id".to_string()));
+    }
+
+    if !state.spanner_client.has_room_for(&target_id).await? {
+        let max = state.spanner_client.document_limit().unwrap_or_default();
+        tracing::warn!("Rejected copy to {}: store is at its limit of {}", target_id, max);
+        return Err(ApiError::DocumentLimitReached(max));
+    }
+
+    let overwrite = query.overwrite.unwrap_or(false);
+    match state.spanner_client.copy(&id, &target_id, overwrite).await? {
+        CopyOutcome::Copied { bytes, version } => {
+            tracing::info!("Copied document {} to {} ({} bytes)", id, target_id, bytes);
+            Ok((
+                StatusCode::CREATED,
+                write_cache_headers(&state.config),
+                Json(CopyResponse {
+                    id: target_id,
+                    source_id: id,
+                    bytes,
+                    version,
+                }),
+            ))
+        }
+        CopyOutcome::SourceNotFound => Err(ApiError::KeyNotFound(id)),
+        CopyOutcome::DestinationExists => Err(ApiError::Conflict(format!(
+            "a document already exists at {}",
+            target_id
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::handlers::{get_handler, put_handler};
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::models::GetResponse;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::post, routing::put, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> Router {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("copy-endpoint-test", "copy-endpoint-test-db");
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        Router::new()
+            .route(routes::KV_ITEM, put(put_handler).get(get_handler))
+            .route(routes::KV_COPY, post(copy_handler))
+            .with_state(state)
+    }
+
+    async fn put_document(app: &Router, id: Uuid, data: &serde_json::Value) {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(data.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(response.status().is_success(), "PUT /kv/{} returned {}", id, response.status());
+    }
+
+    async fn get_document(app: &Router, id: Uuid) -> GetResponse {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(format!("/kv/{}", id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    async fn copy(app: &Router, uri: &str, target_id: &str) -> (StatusCode, Option<CopyResponse>) {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::json!({"target_id": target_id}).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).ok())
+    }
+
+    #[tokio::test]
+    async fn test_copy_document() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+        let target_id = Uuid::new_v4();
+        let data = serde_json::json!({"name": "original"});
+        put_document(&app, id, &data).await;
+        put_document(&app, id, &data).await;
+
+        let (status, copied) = copy(&app, &format!("/kv/{}/copy", id), &target_id.to_string()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let copied = copied.unwrap();
+        assert_eq!(copied.id, target_id.to_string());
+        assert_eq!(copied.source_id, id.to_string());
+        assert_eq!(copied.bytes, data.to_string().len());
+        assert_eq!(copied.version, 1, "the copy is a new document");
+
+        // Both keys now hold the same data, and the source is untouched
+        let source = get_document(&app, id).await;
+        assert_eq!(source.data, data);
+        assert_eq!(source.version, Some(2));
+        assert_eq!(get_document(&app, target_id).await.data, data);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_copy_conflicts_and_overwrite() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+        let target_id = Uuid::new_v4();
+        put_document(&app, id, &serde_json::json!({"from": "source"})).await;
+        put_document(&app, target_id, &serde_json::json!({"from": "target"})).await;
+
+        let uri = format!("/kv/{}/copy", id);
+        let (status, _) = copy(&app, &uri, &target_id.to_string()).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(get_document(&app, target_id).await.data, serde_json::json!({"from": "target"}));
+
+        let (status, copied) = copy(&app, &format!("{}?overwrite=true", uri), &target_id.to_string()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(copied.unwrap().version, 2, "overwriting bumps the target's version");
+        assert_eq!(get_document(&app, target_id).await.data, serde_json::json!({"from": "source"}));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_copy_rejects_missing_source_and_same_id() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+
+        let (status, _) = copy(&app, &format!("/kv/{}/copy", id), &Uuid::new_v4().to_string()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Copying a document onto itself is rejected even with overwrite
+        put_document(&app, id, &serde_json::json!({"same": true})).await;
+        let (status, _) = copy(&app, &format!("/kv/{}/copy?overwrite=true", id), &id.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(get_document(&app, id).await.version, Some(1));
+
+        let (status, _) = copy(&app, &format!("/kv/{}/copy", id), "not-a-uuid").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::models::{CountQuery, CountResponse};
+use crate::routes;
+use crate::state::AppState;
+use axum::{extract::Query, extract::State, http::StatusCode, Json};
+
+/// GET /kv:count handler - Count documents without fetching them
+///
+/// Returns the same number as `total_count` from `GET /kv` with the same
+/// prefix, without reading any documents.
+/// Query parameters:
+/// - prefix: Only count keys starting with this value (optional)
+#[utoipa::path(
+    get,
+    path = routes::KV_COUNT,
+    params(
+        ("prefix" = Option<String>, Query, description = "Only count keys starting with this value")
+    ),
+    responses(
+        (status = 200, description = "Number of matching documents", body = CountResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn count_handler(
+    State(state): State<AppState>,
+    Query(query): Query<CountQuery>,
+) -> Result<(StatusCode, Json<CountResponse>), ApiError> {
+    let count = state.spanner_client.count(query.prefix.as_deref()).await?;
+    Ok((StatusCode::OK, Json(CountResponse { count })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> (Router, SpannerClient) {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("count-endpoint-test", "count-endpoint-test-db");
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client: spanner_client.clone(),
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        let app = Router::new()
+            .route(routes::KV_COUNT, get(count_handler))
+            .with_state(state);
+        (app, spanner_client)
+    }
+
+    async fn count(app: &Router, uri: &str) -> i64 {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response: CountResponse = serde_json::from_slice(&body).unwrap();
+        response.count
+    }
+
+    #[tokio::test]
+    async fn test_count_with_and_without_prefix() {
+        let (app, client) = setup_test_app().await;
+
+        // Three keys sharing a prefix no other test uses
+        let base = Uuid::new_v4().to_string();
+        let prefix = &base[..24];
+        for n in 0..3 {
+            let id = Uuid::parse_str(&format!("{}{:012x}", prefix, n)).unwrap();
+            client.upsert(id, serde_json::json!({"n": n})).await.unwrap();
+        }
+
+        assert_eq!(count(&app, &format!("/kv:count?prefix={}", prefix)).await, 3);
+        assert!(count(&app, "/kv:count").await >= 3);
+        assert_eq!(count(&app, "/kv:count?prefix=not-a-key-prefix").await, 0);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
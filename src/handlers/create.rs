@@ -0,0 +1,165 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::cache_control::write_cache_headers;
+use crate::handlers::key::parse_key;
+use crate::handlers::location::with_location;
+use crate::models::PutResponse;
+use crate::routes;
+use crate::state::AppState;
+use axum::{body::Bytes, extract::rejection::BytesRejection, extract::State, extract::Path, http::HeaderMap, http::StatusCode, Json};
+use serde_json::Value as JsonValue;
+
+/// POST /kv/:id handler - Store a JSON document only if the key is unused
+///
+/// Unlike PUT this never overwrites: if a document is already stored under the
+/// key the request fails with 409 and the stored document is left as it was.
+/// The check and the write are one Spanner commit, so two concurrent creates
+/// of the same key can't both succeed. Returns 201 with a `Location` header.
+#[utoipa::path(
+    post,
+    path = routes::KV_ITEM,
+    params(
+        ("id" = String, Path, description = "Key for the document: a UUID, or any valid key in KEY_MODE=string")
+    ),
+    request_body = serde_json::Value,
+    responses(
+        (status = 201, description = "Document created", body = PutResponse, headers(
+            ("Location" = String, description = "Path of the new document, /kv/{id}"),
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 400, description = "Invalid UUID format or KEY_MODE=string key, invalid JSON, or trailing data after the JSON value", body = ErrorResponse),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 409, description = "A document already exists under this key", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+        (status = 507, description = "New key rejected because the store is at MAX_DOCUMENTS", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn create_handler(
+    State(state): State<AppState>,
+    Path(id_str): Path<String>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<(StatusCode, HeaderMap, Json<PutResponse>), ApiError> {
+    let body = body?;
+    let id = parse_key(&state.config, &id_str)?;
+    let data: JsonValue = serde_json::from_slice(&body)?;
+
+    if !state.spanner_client.has_room_for(&id).await? {
+        let max = state.spanner_client.document_limit().unwrap_or_default();
+        tracing::warn!("Rejected new document {}: store is at its limit of {}", id, max);
+        return Err(ApiError::DocumentLimitReached(max));
+    }
+
+    // A taken key comes back as DocumentExists, which converts to a 409
+    let version = state.spanner_client.insert(&id, data).await?;
+
+    tracing::info!("Created document with id: {}", id);
+    Ok((
+        StatusCode::CREATED,
+        with_location(write_cache_headers(&state.config), &id),
+        Json(PutResponse {
+            id,
+            version,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::post, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> (Router, SpannerClient) {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("create-endpoint-test", "create-endpoint-test-db");
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client: spanner_client.clone(),
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        let app = Router::new()
+            .route(routes::KV_ITEM, post(create_handler))
+            .with_state(state);
+        (app, spanner_client)
+    }
+
+    fn create_request(id: &str, body: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/kv/{}", id))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_create_twice_conflicts() {
+        let (app, client) = setup_test_app().await;
+
+        let test_id = Uuid::new_v4();
+        let response = app
+            .clone()
+            .oneshot(create_request(&test_id.to_string(), r#"{"version": 1}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()["location"], format!("/kv/{}", test_id));
+
+        let response = app
+            .clone()
+            .oneshot(create_request(&test_id.to_string(), r#"{"version": 2}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error.error.contains(&test_id.to_string()));
+
+        // The first document is untouched
+        let stored = client.read(test_id).await.unwrap().unwrap();
+        assert_eq!(stored.data, serde_json::json!({"version": 1}));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_invalid_requests() {
+        let (app, _client) = setup_test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(create_request("not-a-uuid", "{}"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .oneshot(create_request(&Uuid::new_v4().to_string(), "{invalid"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
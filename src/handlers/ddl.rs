@@ -0,0 +1,108 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::admin::require_admin;
+use crate::models::DdlResponse;
+use crate::routes;
+use crate::state::AppState;
+use axum::{extract::State, http::HeaderMap, http::StatusCode, Json};
+
+/// GET /admin/ddl handler - Return the schema as currently deployed
+///
+/// Reads the DDL straight from Spanner's admin API, so it reflects the live
+/// database rather than what provisioning would create.
+#[utoipa::path(
+    get,
+    path = routes::ADMIN_DDL,
+    responses(
+        (status = 200, description = "Deployed DDL statements and database dialect", body = DdlResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+        (status = 501, description = "Admin endpoints are not configured", body = ErrorResponse)
+    ),
+    tag = "admin"
+)]
+pub async fn ddl_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<DdlResponse>), ApiError> {
+    require_admin(&state.config, &headers)?;
+
+    let schema = state.spanner_client.deployed_schema().await?;
+    tracing::info!("Returned {} deployed DDL statements", schema.statements.len());
+
+    Ok((
+        StatusCode::OK,
+        Json(DdlResponse {
+            dialect: schema.dialect.to_string(),
+            statements: schema.statements,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_ddl_endpoint() {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config {
+            admin_token: Some("test-admin-token".to_string()),
+            ..Config::for_emulator("ddl-endpoint-test", "ddl-endpoint-test-db")
+        };
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+        let app = Router::new()
+            .route(routes::ADMIN_DDL, get(ddl_handler))
+            .with_state(AppState {
+                spanner_client,
+                jobs: Arc::new(JobRegistry::from_config(&config)),
+                config: Arc::new(config),
+                metrics: Metrics::new(),
+            });
+
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/admin/ddl").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/ddl")
+                    .header("authorization", "Bearer test-admin-token")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let ddl: DdlResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(ddl.dialect, "GOOGLE_STANDARD_SQL");
+        assert!(
+            ddl.statements.iter().any(|stmt| stmt.contains("CREATE TABLE kv_store")),
+            "Expected the kv_store table in {:?}",
+            ddl.statements
+        );
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::cache_control::write_cache_headers;
+use crate::handlers::key::parse_key;
+use crate::models::{DeleteQuery, DeleteResponse};
+use crate::routes;
+use crate::state::AppState;
+use axum::{extract::Query, extract::State, extract::Path, http::HeaderMap, http::StatusCode, Json};
+
+/// DELETE /kv/:id handler - Remove a JSON document
+///
+/// By default the document is soft-deleted: reads treat it as missing, but
+/// `POST /kv/:id/undelete` can restore it. With `?hard=true` the row and its
+/// history are removed permanently, whether or not it was soft-deleted first.
+///
+/// The key is read and deleted in one transaction, so a 404 means this request
+/// found nothing to delete, including when a concurrent delete got there first.
+#[utoipa::path(
+    delete,
+    path = routes::KV_ITEM,
+    params(
+        ("id" = String, Path, description = "Key for the document: a UUID, or any valid key in KEY_MODE=string"),
+        ("hard" = Option<bool>, Query, description = "Remove the document permanently instead of soft-deleting it")
+    ),
+    responses(
+        (status = 200, description = "Document deleted", body = DeleteResponse, headers(
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 400, description = "Invalid UUID format, or invalid key in KEY_MODE=string", body = ErrorResponse),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn delete_handler(
+    State(state): State<AppState>,
+    Path(id_str): Path<String>,
+    Query(query): Query<DeleteQuery>,
+) -> Result<(StatusCode, HeaderMap, Json<DeleteResponse>), ApiError> {
+    let id = parse_key(&state.config, &id_str)?;
+
+    let hard = query.hard.unwrap_or(false);
+    let existed = if hard {
+        state.spanner_client.hard_delete(&id).await?
+    } else {
+        state.spanner_client.delete_key(&id).await?
+    };
+    if !existed {
+        tracing::info!("Document not found for delete with id: {}", id);
+        return Err(ApiError::KeyNotFound(id));
+    }
+
+    tracing::info!("Successfully deleted document with id: {} (hard: {})", id, hard);
+    Ok((
+        StatusCode::OK,
+        write_cache_headers(&state.config),
+        Json(DeleteResponse {
+            id,
+            deleted: true,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, KeyMode};
+    use crate::handlers::{get_handler, head_handler, put_handler};
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::put, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> Router {
+        setup_test_app_with(Config::for_emulator("put-endpoint-test", "put-endpoint-test-db")).await
+    }
+
+    async fn setup_test_app_with(config: Config) -> Router {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        Router::new()
+            .route(
+                routes::KV_ITEM,
+                put(put_handler).get(get_handler).head(head_handler).delete(delete_handler),
+            )
+            .with_state(state)
+    }
+
+    fn request(method: &str, uri: String, body: Body) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_delete_then_get_returns_404() {
+        let app = setup_test_app().await;
+        let test_id = Uuid::new_v4();
+
+        let response = app
+            .clone()
+            .oneshot(request("PUT", format!("/kv/{}", test_id), Body::from(r#"{"doomed": true}"#)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = app
+            .clone()
+            .oneshot(request("DELETE", format!("/kv/{}", test_id), Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let delete_response: DeleteResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(delete_response.id, test_id.to_string());
+        assert!(delete_response.deleted);
+
+        let response = app
+            .clone()
+            .oneshot(request("GET", format!("/kv/{}", test_id), Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // A second delete finds nothing to remove
+        let response = app
+            .oneshot(request("DELETE", format!("/kv/{}", test_id), Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains("Key not found"));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_invalid_uuid() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .oneshot(request("DELETE", "/kv/not-a-uuid".to_string(), Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_string_keys() {
+        let app = setup_test_app_with(Config {
+            key_mode: KeyMode::String,
+            ..Config::for_emulator("put-endpoint-test", "put-endpoint-test-db")
+        })
+        .await;
+        let key = format!("user:{}", &Uuid::new_v4().simple().to_string()[..16]);
+        let item = format!("/kv/{}", key);
+
+        let response = app
+            .clone()
+            .oneshot(request("PUT", item.clone(), Body::from(r#"{"name": "string key"}"#)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = app.clone().oneshot(request("GET", item.clone(), Body::empty())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let fetched: crate::models::GetResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(fetched.id, key);
+        assert_eq!(fetched.data, serde_json::json!({"name": "string key"}));
+
+        let response = app.clone().oneshot(request("HEAD", item.clone(), Body::empty())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(request("DELETE", item.clone(), Body::empty())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(request("HEAD", item, Body::empty())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Keys too long for the id column are rejected before reaching Spanner
+        let response = app
+            .oneshot(request("PUT", format!("/kv/{}", "k".repeat(37)), Body::from("{}")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // The default uuid mode still refuses them
+        let response = setup_test_app()
+            .await
+            .oneshot(request("GET", format!("/kv/{}", key), Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::cache_control::write_cache_headers;
+use crate::models::{DeletePrefixQuery, DeletePrefixResponse};
+use crate::routes;
+use crate::state::AppState;
+use axum::{extract::Query, extract::State, http::HeaderMap, http::StatusCode, Json};
+
+/// DELETE /kv?prefix= handler - Remove every document under a key prefix
+///
+/// Unlike `DELETE /kv/:id` and `POST /kv:batchDelete`, this is always a hard
+/// delete: rows and their history are removed permanently and can't be
+/// undeleted. Uses Partitioned DML, so it scales to millions of rows but is not atomic: a
+/// failure part-way leaves some of the prefix deleted, and repeating the request
+/// is safe. `deleted` is the row count Spanner reports. With `dry_run=true`
+/// nothing is deleted and `deleted` is the number of rows that would be.
+///
+/// The prefix is required and must be non-empty, so a typo can't wipe the table.
+#[utoipa::path(
+    delete,
+    path = routes::KV_LIST,
+    params(
+        ("prefix" = String, Query, description = "Permanently delete every key starting with this prefix; required and non-empty"),
+        ("dry_run" = Option<bool>, Query, description = "Only count the keys that would be deleted")
+    ),
+    responses(
+        (status = 200, description = "Documents permanently deleted, with no undelete, or counted with dry_run", body = DeletePrefixResponse, headers(
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 400, description = "Missing or empty prefix", body = ErrorResponse),
+        (status = 500, description = "Database error; part of the prefix may have been deleted", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn delete_prefix_handler(
+    State(state): State<AppState>,
+    Query(params): Query<DeletePrefixQuery>,
+) -> Result<(StatusCode, HeaderMap, Json<DeletePrefixResponse>), ApiError> {
+    let prefix = match params.prefix {
+        Some(prefix) if !prefix.is_empty() => prefix,
+        _ => {
+            return Err(ApiError::InvalidQueryParam(
+                "prefix is required and must not be empty".to_string(),
+            ))
+        }
+    };
+    let dry_run = params.dry_run.unwrap_or(false);
+
+    let deleted = if dry_run {
+        state.spanner_client.count_by_prefix(&prefix).await?
+    } else {
+        state.spanner_client.delete_by_prefix(&prefix).await?
+    };
+
+    tracing::info!(
+        "{} {} documents with prefix {:?}",
+        if dry_run { "Would delete" } else { "Deleted" },
+        deleted,
+        prefix
+    );
+    Ok((
+        StatusCode::OK,
+        write_cache_headers(&state.config),
+        Json(DeletePrefixResponse {
+            prefix,
+            deleted,
+            dry_run,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::delete, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> (Router, SpannerClient) {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("put-endpoint-test", "put-endpoint-test-db");
+
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client: spanner_client.clone(),
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        let app = Router::new()
+            .route(routes::KV_LIST, delete(delete_prefix_handler))
+            .with_state(state);
+        (app, spanner_client)
+    }
+
+    async fn delete_prefix(app: &Router, query: &str) -> (StatusCode, Vec<u8>) {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/kv{}", query))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, body.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_delete_prefix_dry_run_then_delete() {
+        let (app, client) = setup_test_app().await;
+
+        let prefix = Uuid::new_v4().to_string()[..8].to_string();
+        let ids: Vec<String> = (0..2)
+            .map(|_| format!("{}{}", prefix, &Uuid::new_v4().to_string()[8..]))
+            .collect();
+        for id in &ids {
+            client.upsert_key(id, serde_json::json!({})).await.unwrap();
+        }
+
+        let (status, body) = delete_prefix(&app, &format!("?prefix={}&dry_run=true", prefix)).await;
+        assert_eq!(status, StatusCode::OK);
+        let response: DeletePrefixResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!((response.deleted, response.dry_run), (2, true));
+        assert_eq!(client.read_many(&ids).await.unwrap().len(), 2, "A dry run must not delete");
+
+        let (status, body) = delete_prefix(&app, &format!("?prefix={}", prefix)).await;
+        assert_eq!(status, StatusCode::OK);
+        let response: DeletePrefixResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!((response.deleted, response.dry_run), (2, false));
+        assert!(client.read_many(&ids).await.unwrap().is_empty());
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_prefix_requires_prefix() {
+        let (app, _client) = setup_test_app().await;
+
+        for query in ["", "?prefix=", "?dry_run=true"] {
+            let (status, _) = delete_prefix(&app, query).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "DELETE /kv{}", query);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
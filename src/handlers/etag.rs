@@ -0,0 +1,122 @@
+use crate::error::ApiError;
+use axum::http::{header, HeaderMap, HeaderValue};
+use chrono::{DateTime, SecondsFormat, Utc};
+
+/// `ETag` header carrying a document's `updated_at` as its version
+///
+/// The timestamp is quoted as a strong entity tag, e.g.
+/// `"2024-01-01T00:00:00.123456000Z"`, and is what `If-Match` expects back.
+pub fn etag_headers(updated_at: DateTime<Utc>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&etag(updated_at)) {
+        headers.insert(header::ETAG, value);
+    }
+    headers
+}
+
+/// A document's entity tag: its `updated_at` as a quoted RFC 3339 timestamp
+pub fn etag(updated_at: DateTime<Utc>) -> String {
+    format!("\"{}\"", updated_at.to_rfc3339_opts(SecondsFormat::Nanos, true))
+}
+
+/// The `updated_at` a write is conditional on, from the `If-Match` header
+///
+/// Accepts the ETag as returned by GET, with or without its quotes.
+pub fn if_match_version(headers: &HeaderMap) -> Result<Option<DateTime<Utc>>, ApiError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+
+    let invalid = || {
+        ApiError::InvalidRequest(
+            "If-Match must be the ETag returned by GET, e.g. \"2024-01-01T00:00:00.000000000Z\"".to_string(),
+        )
+    };
+    let raw = value.to_str().map_err(|_| invalid())?.trim();
+    let unquoted = raw
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .unwrap_or(raw);
+    let version = DateTime::parse_from_rfc3339(unquoted).map_err(|_| invalid())?;
+    Ok(Some(version.with_timezone(&Utc)))
+}
+
+/// Whether an `If-None-Match` header matches the current ETag, per RFC 9110
+///
+/// The header is either `*`, which matches any stored document, or a
+/// comma-separated list of entity tags compared weakly, so a `W/` prefix is
+/// ignored. A header that doesn't parse is ignored and never matches, so the
+/// client gets the full document rather than an error.
+pub fn if_none_match_matches(headers: &HeaderMap, current: &str) -> bool {
+    let Some(raw) = headers.get(header::IF_NONE_MATCH).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    if raw.trim() == "*" {
+        return true;
+    }
+
+    let mut tags = Vec::new();
+    for member in raw.split(',').map(str::trim).filter(|member| !member.is_empty()) {
+        let opaque = member.strip_prefix("W/").unwrap_or(member);
+        if !is_quoted(opaque) {
+            return false;
+        }
+        tags.push(opaque);
+    }
+    tags.contains(&current.strip_prefix("W/").unwrap_or(current))
+}
+
+/// Whether `tag` is a quoted opaque tag such as `"abc"`
+fn is_quoted(tag: &str) -> bool {
+    tag.len() >= 2 && tag.starts_with('"') && tag.ends_with('"') && !tag[1..tag.len() - 1].contains('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_round_trips_through_if_match() {
+        let updated_at = DateTime::parse_from_rfc3339("2024-05-06T07:08:09.123456Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let etag = etag_headers(updated_at)[header::ETAG].clone();
+        assert_eq!(etag, "\"2024-05-06T07:08:09.123456000Z\"");
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(if_match_version(&headers).unwrap(), None);
+
+        headers.insert(header::IF_MATCH, etag);
+        assert_eq!(if_match_version(&headers).unwrap(), Some(updated_at));
+
+        headers.insert(header::IF_MATCH, HeaderValue::from_static("2024-05-06T07:08:09.123456Z"));
+        assert_eq!(if_match_version(&headers).unwrap(), Some(updated_at));
+
+        headers.insert(header::IF_MATCH, HeaderValue::from_static("\"v1\""));
+        assert!(matches!(if_match_version(&headers), Err(ApiError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let current = "\"2024-05-06T07:08:09.123456000Z\"";
+        let matches = |value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static(value));
+            if_none_match_matches(&headers, current)
+        };
+
+        assert!(!if_none_match_matches(&HeaderMap::new(), current));
+        assert!(matches("\"2024-05-06T07:08:09.123456000Z\""));
+        assert!(matches("W/\"2024-05-06T07:08:09.123456000Z\""));
+        assert!(matches("\"stale\", \"2024-05-06T07:08:09.123456000Z\""));
+        assert!(matches(" * "));
+        assert!(!matches("\"2024-01-01T00:00:00.000000000Z\""));
+        assert!(!matches("\"stale\", W/\"older\""));
+
+        // Malformed headers are ignored rather than rejected
+        assert!(!matches("2024-05-06T07:08:09.123456000Z"));
+        assert!(!matches("\"2024-05-06T07:08:09.123456000Z\", bogus"));
+        assert!(!matches("*, \"2024-05-06T07:08:09.123456000Z\""));
+        assert!(!matches("\""));
+    }
+}
@@ -0,0 +1,593 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::jobs::JobHandle;
+use crate::models::{ExportQuery, ExportRecord};
+use crate::routes;
+use crate::spanner::{KvEntry, ListFilter, SortOrder, SpannerClient, SpannerResult};
+use crate::state::AppState;
+use anyhow::Context;
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use axum::{
+    body::{Body, Bytes},
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use tokio::io::{AsyncWriteExt, DuplexStream};
+use tokio_util::io::ReaderStream;
+
+/// Number of documents fetched from Spanner per page while exporting
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Response header carrying the id of the export job
+const JOB_ID_HEADER: header::HeaderName = header::HeaderName::from_static("x-job-id");
+
+/// Size of the in-memory pipe between the archive writer and the response body
+const EXPORT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Documents read before the export job starts, so read errors still produce an error status
+enum FirstRows {
+    /// First page of a ZIP export, which reads the rest page by page
+    Zip(Vec<KvEntry>),
+    /// Every row of an NDJSON export, with the first one already read
+    Ndjson(BoxStream<'static, SpannerResult<KvEntry>>),
+    /// Every row of a JSON object export, with the first one already read
+    Json(BoxStream<'static, SpannerResult<KvEntry>>),
+}
+
+/// GET /kv/export handler - Download documents as an archive
+///
+/// With `format=zip`, streams a ZIP archive with one `{id}.json` entry per
+/// document. Documents are read page by page, so writes made during a long
+/// export may or may not be included.
+///
+/// With `format=ndjson`, streams one `ExportRecord` per line from a single
+/// read-only query, so the file is a consistent snapshot even while writes
+/// continue. Its lines can be posted back to `POST /kv:batch` to restore it.
+///
+/// With `format=json`, streams the same snapshot as a single JSON object
+/// mapping each key to its document, framed by hand as rows arrive so the
+/// whole dump is never held in memory.
+///
+/// Any format can be restricted to keys starting with `prefix`. Each export
+/// is registered as an `export` job, so it shows up under `/admin/jobs` and can
+/// be cancelled between pages or rows. Its id is returned in the `X-Job-Id`
+/// header.
+#[utoipa::path(
+    get,
+    path = routes::KV_EXPORT,
+    params(
+        ("format" = String, Query, description = "Export format: zip, ndjson or json"),
+        ("prefix" = Option<String>, Query, description = "Only export keys starting with this value")
+    ),
+    responses(
+        (status = 200, description = "ZIP archive, NDJSON snapshot or JSON object of matching documents", headers(
+            ("X-Job-Id" = String, description = "Id of the export job, for /admin/jobs")
+        ), content(
+            ("application/zip"),
+            (ExportRecord = "application/x-ndjson"),
+            (std::collections::HashMap<String, serde_json::Value> = "application/json")
+        )),
+        (status = 400, description = "Unsupported export format", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn export_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    // Read the first rows up front so database errors still produce an error status
+    let (format, first_rows, content_type, filename) = match query.format.as_deref() {
+        Some("zip") => {
+            let first_page = fetch_page(&state.spanner_client, query.prefix.as_deref(), 0).await?;
+            ("zip", FirstRows::Zip(first_page), "application/zip", "kv-export.zip".to_string())
+        }
+        Some("ndjson") => {
+            let rows = snapshot_rows(&state.spanner_client, query.prefix.clone()).await?;
+            let filename = format!("kv-export-{}.ndjson", Utc::now().format("%Y%m%dT%H%M%SZ"));
+            ("ndjson", FirstRows::Ndjson(rows), "application/x-ndjson", filename)
+        }
+        Some("json") => {
+            let rows = snapshot_rows(&state.spanner_client, query.prefix.clone()).await?;
+            let filename = format!("kv-export-{}.json", Utc::now().format("%Y%m%dT%H%M%SZ"));
+            ("json", FirstRows::Json(rows), "application/json", filename)
+        }
+        other => {
+            return Err(ApiError::InvalidQueryParam(format!(
+                "format must be one of: zip, ndjson, json, got '{}'",
+                other.unwrap_or_default()
+            )))
+        }
+    };
+
+    let job = state.jobs.register(
+        "export",
+        serde_json::json!({"format": format, "prefix": query.prefix}),
+    );
+    let job_id = job.id().to_string();
+    let (writer, reader) = tokio::io::duplex(EXPORT_BUFFER_SIZE);
+    let client = state.spanner_client.clone();
+    let prefix = query.prefix.clone();
+    let export = tokio::spawn(async move {
+        let result = match first_rows {
+            FirstRows::Zip(first_page) => write_zip(client, prefix, first_page, writer, &job).await,
+            FirstRows::Ndjson(rows) => write_ndjson(rows, writer, &job).await,
+            FirstRows::Json(rows) => write_json_object(rows, writer, &job).await,
+        };
+        job.finish(&result);
+        result
+    });
+
+    // Surface a failed export as a body error, which aborts the response instead
+    // of ending it cleanly with a truncated archive
+    let outcome = futures_util::stream::once(async move {
+        let result = match export.await {
+            Ok(result) => result,
+            Err(e) => Err(anyhow::anyhow!("Export task failed: {}", e)),
+        };
+        match result {
+            Ok(count) => {
+                tracing::info!("Exported {} documents (prefix: {:?})", count, query.prefix);
+                None
+            }
+            Err(e) => {
+                tracing::error!("Export aborted: {:#}", e);
+                Some(Err(std::io::Error::other(format!("{:#}", e))))
+            }
+        }
+    })
+    .filter_map(|outcome: Option<Result<Bytes, std::io::Error>>| async move { outcome });
+
+    let body = Body::from_stream(ReaderStream::new(reader).chain(outcome));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+            (JOB_ID_HEADER, job_id),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Every matching row in key order from one read-only query, with the first
+/// row already read so a failing query is reported before the response starts
+async fn snapshot_rows(
+    client: &SpannerClient,
+    prefix: Option<String>,
+) -> SpannerResult<BoxStream<'static, SpannerResult<KvEntry>>> {
+    let mut rows = client.list_stream(prefix, SortOrder::KeyAsc.into(), None, 0).boxed();
+    let first = rows.next().await.transpose()?;
+    Ok(futures_util::stream::iter(first.map(Ok)).chain(rows).boxed())
+}
+
+/// Fetch one page of documents in key order
+async fn fetch_page(
+    client: &SpannerClient,
+    prefix: Option<&str>,
+    offset: i64,
+) -> anyhow::Result<Vec<KvEntry>> {
+    let result = client
+        .list_all(
+            &ListFilter {
+                // Pages are read until one comes back short, so the total is never needed
+                skip_count: true,
+                ..prefix.map(ListFilter::prefix).unwrap_or_default()
+            },
+            &SortOrder::KeyAsc.into(),
+            Some(EXPORT_PAGE_SIZE),
+            offset,
+        )
+        .await?;
+    Ok(result.entries)
+}
+
+/// Write every matching document into a ZIP archive, returning how many were written
+async fn write_zip(
+    client: SpannerClient,
+    prefix: Option<String>,
+    first_page: Vec<KvEntry>,
+    writer: DuplexStream,
+    job: &JobHandle,
+) -> anyhow::Result<usize> {
+    let mut zip = ZipFileWriter::with_tokio(writer);
+    let mut page = first_page;
+    let mut offset = 0;
+    let mut written = 0;
+
+    loop {
+        let page_len = page.len() as i64;
+
+        for entry in page {
+            let data = serde_json::to_vec(&entry.value).context("Failed to serialize document")?;
+            let zip_entry = ZipEntryBuilder::new(format!("{}.json", entry.key).into(), Compression::Deflate);
+            zip.write_entry_whole(zip_entry, &data)
+                .await
+                .with_context(|| format!("Failed to write archive entry for {}", entry.key))?;
+            written += 1;
+            job.add_progress(1);
+        }
+
+        if page_len < EXPORT_PAGE_SIZE {
+            break;
+        }
+        // Page boundaries are where an operator's cancellation takes effect
+        if job.is_cancelled() {
+            anyhow::bail!("Export cancelled after {} documents", written);
+        }
+        offset += page_len;
+        page = fetch_page(&client, prefix.as_deref(), offset).await?;
+    }
+
+    zip.close().await.context("Failed to finish archive")?;
+    Ok(written)
+}
+
+/// Write every row as one JSON line, returning how many were written
+async fn write_ndjson(
+    mut rows: BoxStream<'static, SpannerResult<KvEntry>>,
+    mut writer: DuplexStream,
+    job: &JobHandle,
+) -> anyhow::Result<usize> {
+    let mut written = 0;
+    while let Some(entry) = rows.next().await {
+        if job.is_cancelled() {
+            anyhow::bail!("Export cancelled after {} documents", written);
+        }
+        let mut line = serde_json::to_vec(&ExportRecord::from(entry?)).context("Failed to serialize document")?;
+        line.push(b'\n');
+        writer.write_all(&line).await.context("Failed to write export line")?;
+        written += 1;
+        job.add_progress(1);
+    }
+    writer.shutdown().await.context("Failed to finish export")?;
+    Ok(written)
+}
+
+/// Write every row as a member of one JSON object keyed by id, returning how many were written
+async fn write_json_object(
+    mut rows: BoxStream<'static, SpannerResult<KvEntry>>,
+    mut writer: DuplexStream,
+    job: &JobHandle,
+) -> anyhow::Result<usize> {
+    let mut written = 0;
+    writer.write_all(b"{").await.context("Failed to write export")?;
+    while let Some(entry) = rows.next().await {
+        if job.is_cancelled() {
+            anyhow::bail!("Export cancelled after {} documents", written);
+        }
+        let entry = entry?;
+        let mut member = if written == 0 { Vec::new() } else { vec![b','] };
+        serde_json::to_writer(&mut member, &entry.key).context("Failed to serialize key")?;
+        member.push(b':');
+        serde_json::to_writer(&mut member, &entry.value).context("Failed to serialize document")?;
+        writer.write_all(&member).await.context("Failed to write export member")?;
+        written += 1;
+        job.add_progress(1);
+    }
+    writer.write_all(b"}").await.context("Failed to write export")?;
+    writer.shutdown().await.context("Failed to finish export")?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::handlers::batch::batch_put_handler;
+    use crate::handlers::delete::delete_handler;
+    use crate::handlers::get::get_handler;
+    use crate::handlers::put::put_handler;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use async_zip::base::read::mem::ZipFileReader;
+    use axum::{body::Body, http::Request, http::StatusCode, routing::get, routing::post, routing::put, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> (Router, Arc<JobRegistry>) {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("export-test", "export-test-db");
+
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let jobs = Arc::new(JobRegistry::from_config(&config));
+        let state = AppState {
+            spanner_client,
+            jobs: jobs.clone(),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        let app = Router::new()
+            .route(crate::routes::KV_EXPORT, get(export_handler))
+            .route(crate::routes::KV_ITEM, put(put_handler).get(get_handler).delete(delete_handler))
+            .route(crate::routes::KV_BATCH, post(batch_put_handler))
+            .with_state(state);
+        (app, jobs)
+    }
+
+    #[tokio::test]
+    async fn test_export_zip_with_prefix() {
+        let (app, jobs) = setup_test_app().await;
+
+        let id = Uuid::new_v4();
+        let data = serde_json::json!({"name": "exported", "tags": ["a", "b"]});
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&data).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let prefix = &id.to_string()[..13];
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv/export?format=zip&prefix={}", prefix))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/zip");
+        let job_id = response.headers()["x-job-id"].to_str().unwrap().to_string();
+        assert!(response.headers()[header::CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .starts_with("attachment"));
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let archive = ZipFileReader::new(body.to_vec()).await.unwrap();
+
+        let filenames: Vec<String> = archive
+            .file()
+            .entries()
+            .iter()
+            .map(|entry| entry.filename().as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(filenames, vec![format!("{}.json", id)]);
+
+        let mut contents = String::new();
+        archive
+            .reader_with_entry(0)
+            .await
+            .unwrap()
+            .read_to_string_checked(&mut contents)
+            .await
+            .unwrap();
+        let exported: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(exported, data);
+
+        // The export was tracked as a job and recorded as completed
+        let exports = jobs.list();
+        assert_eq!(exports.len(), 1);
+        assert_eq!(exports[0].kind, "export");
+        assert_eq!(exports[0].status, crate::jobs::JobStatus::Completed);
+        assert_eq!(exports[0].processed, 1);
+        assert_eq!(exports[0].params["prefix"], prefix);
+        assert_eq!(exports[0].id.to_string(), job_id);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_ndjson_round_trip() {
+        let (app, jobs) = setup_test_app().await;
+
+        // Keys share a random first group so the export only sees this test's documents
+        let prefix = Uuid::new_v4().simple().to_string()[..8].to_string();
+        let mut documents: Vec<(String, serde_json::Value)> = (0..3)
+            .map(|i| {
+                let id = format!("{}{}", prefix, &Uuid::new_v4().to_string()[8..]);
+                (id, serde_json::json!({"n": i, "nested": {"tags": ["x", i]}}))
+            })
+            .collect();
+        documents.sort_by(|a, b| a.0.cmp(&b.0));
+        for (id, data) in &documents {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("PUT")
+                        .uri(format!("/kv/{}", id))
+                        .header("content-type", "application/json")
+                        .body(Body::from(data.to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv/export?format=ndjson&prefix={}", prefix))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/x-ndjson");
+        let disposition = response.headers()[header::CONTENT_DISPOSITION].to_str().unwrap();
+        assert!(disposition.starts_with("attachment; filename=\"kv-export-"), "{}", disposition);
+        assert!(disposition.ends_with(".ndjson\""), "{}", disposition);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let records: Vec<ExportRecord> = String::from_utf8(body.to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let exported: Vec<(String, serde_json::Value)> =
+            records.iter().map(|record| (record.id.clone(), record.data.clone())).collect();
+        assert_eq!(exported, documents);
+        assert!(records.iter().all(|record| !record.created_at.is_empty() && !record.updated_at.is_empty()));
+
+        let exports = jobs.list();
+        assert_eq!(exports.len(), 1);
+        assert_eq!(exports[0].status, crate::jobs::JobStatus::Completed);
+        assert_eq!(exports[0].params["format"], "ndjson");
+        assert_eq!(exports[0].processed, 3);
+
+        // Delete everything, then restore it from the export through the batch endpoint
+        for (id, _) in &documents {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().method("DELETE").uri(format!("/kv/{}", id)).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert!(response.status().is_success());
+        }
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/kv:batch")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&records).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for (id, data) in &documents {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(format!("/kv/{}", id)).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let restored: crate::models::GetResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(&restored.data, data);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_json_object() {
+        let (app, jobs) = setup_test_app().await;
+
+        let prefix = Uuid::new_v4().simple().to_string()[..8].to_string();
+        let documents: std::collections::HashMap<String, serde_json::Value> = (0..5)
+            .map(|i| {
+                let id = format!("{}{}", prefix, &Uuid::new_v4().to_string()[8..]);
+                (id, serde_json::json!({"n": i, "quote": "say \"hi\"", "list": [i, null]}))
+            })
+            .collect();
+        let entries: Vec<_> = documents
+            .iter()
+            .map(|(id, data)| serde_json::json!({"id": id, "data": data}))
+            .collect();
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/kv:batch")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::json!(entries).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let export = |prefix: String| {
+            app.clone().oneshot(
+                Request::builder()
+                    .uri(format!("/kv/export?format=json&prefix={}", prefix))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+        let response = export(prefix.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let disposition = response.headers()[header::CONTENT_DISPOSITION].to_str().unwrap();
+        assert!(disposition.ends_with(".json\""), "{}", disposition);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let exported: std::collections::HashMap<String, serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(exported.len(), 5);
+        assert_eq!(exported, documents);
+        let exports = jobs.list();
+        assert_eq!(exports[0].params["format"], "json");
+        assert_eq!(exports[0].processed, 5);
+
+        // No matches is still a valid, empty object
+        let response = export(Uuid::new_v4().simple().to_string()).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"{}");
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_unsupported_format() {
+        let (app, jobs) = setup_test_app().await;
+
+        for uri in ["/kv/export", "/kv/export?format=tar"] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+            assert!(error_response.error.contains("format must be one of: zip, ndjson, json"));
+        }
+        assert!(jobs.list().is_empty(), "Rejected exports should not register a job");
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
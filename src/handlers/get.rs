@@ -1,21 +1,132 @@
 use crate::error::{ApiError, ErrorResponse};
-use crate::models::GetResponse;
+use crate::handlers::admin::require_admin;
+use crate::handlers::cache_control::read_cache_headers;
+use crate::handlers::etag::{etag, etag_headers, if_none_match_matches};
+use crate::handlers::key::parse_key;
+use crate::handlers::read_info::{read_info_headers, read_info_requested};
+use crate::models::{GetQuery, GetResponse};
 use crate::routes;
+use crate::spanner::is_valid_field_path;
 use crate::state::AppState;
-use axum::{extract::State, extract::Path, http::StatusCode, Json};
-use uuid::Uuid;
+use axum::{
+    extract::Query, extract::State, extract::Path, http::HeaderMap, http::StatusCode,
+    response::{IntoResponse, Response}, Json,
+};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How often a long-polling GET re-reads a key that is still missing
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Largest `max_staleness_ms` a GET will accept
+const MAX_STALENESS: Duration = Duration::from_secs(60);
+
+/// Validate a `max_staleness_ms` value, which must be between 0 and [`MAX_STALENESS`]
+fn parse_staleness(millis: i64) -> Result<Duration, ApiError> {
+    u64::try_from(millis)
+        .ok()
+        .map(Duration::from_millis)
+        .filter(|staleness| *staleness <= MAX_STALENESS)
+        .ok_or_else(|| ApiError::InvalidQueryParam(format!(
+            "max_staleness_ms must be between 0 and {} (got {})",
+            MAX_STALENESS.as_millis(),
+            millis
+        )))
+}
+
+/// Parse a `wait` value such as `5s` (a bare number is also taken as seconds)
+fn parse_wait(value: &str) -> Result<Duration, ApiError> {
+    value
+        .strip_suffix('s')
+        .unwrap_or(value)
+        .parse::<u64>()
+        .map(Duration::from_secs)
+        .map_err(|_| ApiError::InvalidQueryParam(format!(
+            "wait must be a whole number of seconds, e.g. 5s (got '{}')", value
+        )))
+}
+
+/// Most paths accepted in one `fields` projection
+const MAX_PROJECTED_FIELDS: usize = 32;
+
+/// Parse a `fields` list such as `name,settings.theme` into distinct dotted paths
+fn parse_fields(raw: &str) -> Result<Vec<String>, ApiError> {
+    let mut paths: Vec<String> = Vec::new();
+    for path in raw.split(',').map(str::trim) {
+        if !is_valid_field_path(path) {
+            return Err(ApiError::InvalidQueryParam(format!(
+                "fields must be comma-separated dotted paths such as name,settings.theme (got '{}')",
+                path
+            )));
+        }
+        if !paths.iter().any(|seen| seen == path) {
+            paths.push(path.to_string());
+        }
+    }
+    if paths.len() > MAX_PROJECTED_FIELDS {
+        return Err(ApiError::InvalidQueryParam(format!(
+            "at most {} fields are allowed, got {}",
+            MAX_PROJECTED_FIELDS,
+            paths.len()
+        )));
+    }
+    Ok(paths)
+}
 
 /// GET /kv/:id handler - Retrieve a JSON document
+///
+/// With `X-Debug-Read-Info: true` (or `DEBUG_READ_INFO` set), the response carries
+/// the Spanner read timestamp and mode in `X-Read-Timestamp` and `X-Read-Mode`.
+///
+/// With `?wait=Ns`, a missing key is re-read every [`WAIT_POLL_INTERVAL`] until it
+/// appears or the wait (capped at `MAX_GET_WAIT_SECS`) elapses.
+///
+/// With `?max_staleness_ms=N` (at most 60000), the document is read from a
+/// snapshot up to N ms old, which Spanner can serve without a leader round
+/// trip; recent writes may not be visible. With read info requested, the
+/// snapshot is taken exactly N ms back so its timestamp can be reported, and
+/// `X-Read-Mode` is `stale`.
+///
+/// Soft-deleted documents are missing unless an admin asks for them with
+/// `?include_deleted=true`, which returns them with `deleted_at` set.
+///
+/// With `?fields=name,settings.theme`, `data` holds only those paths, nested as
+/// in the document, and paths that don't exist are left out. The fields are
+/// extracted in Spanner, so the rest of the document is never read out. A
+/// projection is always a strong read and carries no read-info headers.
+///
+/// The `ETag` header carries the document's `updated_at`; send it back in
+/// `If-Match` on PUT to write only if the document hasn't changed since, or
+/// in `If-None-Match` on GET to get an empty 304 while it is unchanged.
+///
+/// With `LIST_CACHE_MAX_AGE` set, a found document is returned with
+/// `Cache-Control: public, max-age=N`.
 #[utoipa::path(
     get,
     path = routes::KV_ITEM,
     params(
-        ("id" = String, Path, description = "UUID key for the document")
+        ("id" = String, Path, description = "Key for the document: a UUID, or any valid key in KEY_MODE=string"),
+        ("wait" = Option<String>, Query, description = "Long-poll up to this long (e.g. 5s) for a missing key to appear"),
+        ("max_staleness_ms" = Option<i64>, Query, description = "Read from a snapshot up to this many milliseconds old (0-60000) instead of a strong read"),
+        ("include_deleted" = Option<bool>, Query, description = "Also return a soft-deleted document, with its deleted_at; requires the admin token"),
+        ("fields" = Option<String>, Query, description = "Comma-separated dotted paths, e.g. name,settings.theme; data holds only these, and missing paths are left out"),
+        ("X-Debug-Read-Info" = Option<bool>, Header, description = "Return the read timestamp and mode in response headers"),
+        ("If-None-Match" = Option<String>, Header, description = "ETags (or *) the client already has; a match returns 304 with no body")
     ),
     responses(
-        (status = 200, description = "Document found", body = GetResponse),
-        (status = 400, description = "Invalid UUID format", body = ErrorResponse),
-        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 200, description = "Document found", body = GetResponse, headers(
+            ("X-Read-Timestamp" = String, description = "RFC 3339 timestamp the read was served at (debug only)"),
+            ("X-Read-Mode" = String, description = "Read mode: strong, or stale with max_staleness_ms (debug only)"),
+            ("Cache-Control" = String, description = "public, max-age=N when LIST_CACHE_MAX_AGE is set"),
+            ("ETag" = String, description = "Quoted updated_at of the document, for If-Match on PUT")
+        )),
+        (status = 304, description = "Document unchanged since the If-None-Match ETag", headers(
+            ("ETag" = String, description = "Quoted updated_at of the document")
+        )),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 400, description = "Invalid UUID format or KEY_MODE=string key, wait, max_staleness_ms or fields value", body = ErrorResponse),
+        (status = 401, description = "include_deleted without a valid admin token", body = ErrorResponse),
+        (status = 404, description = "Key not found (after waiting, if requested)", body = ErrorResponse),
         (status = 500, description = "Database error", body = ErrorResponse)
     ),
     tag = "kv"
@@ -23,21 +134,93 @@ use uuid::Uuid;
 pub async fn get_handler(
     State(state): State<AppState>,
     Path(id_str): Path<String>,
-) -> Result<(StatusCode, Json<GetResponse>), ApiError> {
-    // Parse and validate UUID
-    let id = Uuid::parse_str(&id_str).map_err(|_| ApiError::InvalidUuid(id_str.clone()))?;
+    Query(params): Query<GetQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let id = parse_key(&state.config, &id_str)?;
 
-    // Retrieve the document
-    match state.spanner_client.read(id).await? {
-        Some(data) => {
+    let wait = params
+        .wait
+        .as_deref()
+        .map(parse_wait)
+        .transpose()?
+        .map(|wait| wait.min(Duration::from_secs(state.config.max_get_wait_secs)));
+    let staleness = params.max_staleness_ms.map(parse_staleness).transpose()?;
+    // Soft-deleted documents are only shown to admins, from a strong read
+    let include_deleted = params.include_deleted.unwrap_or(false);
+    if include_deleted {
+        require_admin(&state.config, &headers)?;
+        if staleness.is_some() {
+            return Err(ApiError::InvalidQueryParam(
+                "include_deleted can't be combined with max_staleness_ms".to_string(),
+            ));
+        }
+    }
+    let fields = params.fields.as_deref().map(parse_fields).transpose()?;
+    if fields.is_some() && (include_deleted || staleness.is_some()) {
+        return Err(ApiError::InvalidQueryParam(
+            "fields can't be combined with include_deleted or max_staleness_ms".to_string(),
+        ));
+    }
+    let deadline = wait.map(|wait| Instant::now() + wait);
+    let with_read_info = read_info_requested(&state.config, &headers);
+
+    let (document, read_info) = loop {
+        // Retrieve the document, capturing the read timestamp only when asked to
+        let (document, read_info) = if let Some(paths) = &fields {
+            (state.spanner_client.read_projected(&id, paths).await?, None)
+        } else if include_deleted {
+            (state.spanner_client.read_including_deleted(&id).await?, None)
+        } else if let Some(staleness) = staleness {
+            if with_read_info {
+                let (document, info) = state.spanner_client.read_with_staleness_info(&id, staleness).await?;
+                (document, Some(info))
+            } else {
+                (state.spanner_client.read_with_staleness(&id, staleness).await?, None)
+            }
+        } else if with_read_info {
+            let (document, info) = state.spanner_client.read_with_info(&id).await?;
+            (document, Some(info))
+        } else {
+            (state.spanner_client.read_key(&id).await?, None)
+        };
+
+        let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        match remaining {
+            Some(remaining) if document.is_none() && !remaining.is_zero() => {
+                tokio::time::sleep(remaining.min(WAIT_POLL_INTERVAL)).await;
+            }
+            _ => break (document, read_info),
+        }
+    };
+
+    match document {
+        Some(document) => {
             tracing::info!("Successfully retrieved document with id: {}", id);
+            let mut response_headers = read_info_headers(read_info.as_ref());
+            if !include_deleted {
+                response_headers.extend(read_cache_headers(&state.config));
+            }
+            response_headers.extend(etag_headers(document.updated_at));
+
+            let current = etag(document.updated_at);
+            if if_none_match_matches(&headers, &current) {
+                return Ok((StatusCode::NOT_MODIFIED, response_headers).into_response());
+            }
             Ok((
                 StatusCode::OK,
+                response_headers,
                 Json(GetResponse {
-                    id: id.to_string(),
-                    data,
+                    id,
+                    data: document.data,
+                    etag: Some(current),
+                    version: Some(document.version),
+                    deleted_at: document.deleted_at.map(|deleted_at| deleted_at.to_rfc3339()),
+                    created_at: Some(document.created_at.to_rfc3339()),
+                    updated_at: Some(document.updated_at.to_rfc3339()),
                 }),
-            ))
+            )
+                .into_response())
         }
         None => {
             tracing::info!("Document not found with id: {}", id);
@@ -50,10 +233,13 @@ pub async fn get_handler(
 mod tests {
     use super::*;
     use crate::config::Config;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
     use crate::spanner::SpannerClient;
     use axum::{body::Body, http::Request, routing::put, Router};
     use std::sync::Arc;
     use tower::ServiceExt;
+    use uuid::Uuid;
 
     // PUT handler needed for tests
     use crate::handlers::put::put_handler;
@@ -64,14 +250,7 @@ mod tests {
             std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
         }
 
-        let config = Config {
-            spanner_emulator_host: Some("localhost:9010".to_string()),
-            spanner_project: "test-project".to_string(),
-            spanner_instance: "put-endpoint-test".to_string(),
-            spanner_database: "put-endpoint-test-db".to_string(),
-            service_port: 3000,
-            service_host: "0.0.0.0".to_string(),
-        };
+        let config = Config::for_emulator("put-endpoint-test", "put-endpoint-test-db");
 
         let spanner_client = SpannerClient::from_config(&config)
             .await
@@ -79,7 +258,9 @@ mod tests {
 
         let state = AppState {
             spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
             config: Arc::new(config),
+            metrics: Metrics::new(),
         };
 
         Router::new()
@@ -111,7 +292,7 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(put_response.status(), StatusCode::OK);
+        assert_eq!(put_response.status(), StatusCode::CREATED);
 
         // Now, GET the data
         let get_response = app
@@ -228,7 +409,7 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(put_response.status(), StatusCode::OK);
+        assert_eq!(put_response.status(), StatusCode::CREATED);
 
         // Now, GET the data
         let get_response = app
@@ -254,4 +435,665 @@ mod tests {
             std::env::remove_var("SPANNER_EMULATOR_HOST");
         }
     }
+
+    #[tokio::test]
+    async fn test_get_endpoint_read_info_headers() {
+        let app = setup_test_app().await;
+
+        let test_id = Uuid::new_v4();
+        let put_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", test_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"name": "debug"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(put_response.status(), StatusCode::CREATED);
+
+        // Headers are returned when requested
+        let get_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri(format!("/kv/{}", test_id))
+                    .header("X-Debug-Read-Info", "true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(get_response.status(), StatusCode::OK);
+
+        let timestamp = get_response.headers()["x-read-timestamp"].to_str().unwrap();
+        assert!(
+            chrono::DateTime::parse_from_rfc3339(timestamp).is_ok(),
+            "X-Read-Timestamp should be RFC 3339, got {}",
+            timestamp
+        );
+        assert_eq!(get_response.headers()["x-read-mode"], "strong");
+
+        // A stale read reports the older snapshot it was served from
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let get_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri(format!("/kv/{}?max_staleness_ms=1000", test_id))
+                    .header("X-Debug-Read-Info", "true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(get_response.status(), StatusCode::OK);
+        assert_eq!(get_response.headers()["x-read-mode"], "stale");
+        let timestamp = get_response.headers()["x-read-timestamp"].to_str().unwrap();
+        let timestamp = chrono::DateTime::parse_from_rfc3339(timestamp).unwrap();
+        assert!(
+            timestamp <= chrono::Utc::now() - chrono::Duration::milliseconds(1000),
+            "Stale read should be served at least 1s back, got {}",
+            timestamp
+        );
+
+        // And absent otherwise
+        let get_response = app
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri(format!("/kv/{}", test_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(get_response.status(), StatusCode::OK);
+        assert!(get_response.headers().get("x-read-timestamp").is_none());
+        assert!(get_response.headers().get("x-read-mode").is_none());
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[test]
+    fn test_parse_wait() {
+        assert_eq!(parse_wait("5s").unwrap(), Duration::from_secs(5));
+        assert_eq!(parse_wait("0s").unwrap(), Duration::ZERO);
+        assert_eq!(parse_wait("12").unwrap(), Duration::from_secs(12));
+        for value in ["", "s", "5m", "-1s", "1.5s", "five"] {
+            assert!(parse_wait(value).is_err(), "wait '{}' should be rejected", value);
+        }
+    }
+
+    #[test]
+    fn test_parse_staleness() {
+        assert_eq!(parse_staleness(0).unwrap(), Duration::ZERO);
+        assert_eq!(parse_staleness(1500).unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_staleness(60_000).unwrap(), MAX_STALENESS);
+        for millis in [-1, 60_001, i64::MAX] {
+            assert!(parse_staleness(millis).is_err(), "max_staleness_ms {} should be rejected", millis);
+        }
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!(parse_fields("name").unwrap(), vec!["name"]);
+        assert_eq!(parse_fields("name, settings.theme,name").unwrap(), vec!["name", "settings.theme"]);
+        for raw in ["", "name,", "a..b", "$.name", "tags[0]", "na me"] {
+            assert!(parse_fields(raw).is_err(), "fields '{}' should be rejected", raw);
+        }
+        let too_many = (0..=MAX_PROJECTED_FIELDS).map(|i| format!("f{}", i)).collect::<Vec<_>>().join(",");
+        assert!(parse_fields(&too_many).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_fields_projection() {
+        let app = setup_test_app().await;
+        let test_id = Uuid::new_v4();
+        let test_data = serde_json::json!({
+            "name": "projected",
+            "settings": {"theme": "dark", "font": {"size": 12, "family": "mono"}},
+            "tags": ["a", "b"],
+            "items": [{"sku": 1}, {"sku": 2}],
+            "note": null,
+            "big": "x".repeat(1000)
+        });
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", test_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(test_data.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let get = |fields: &str| {
+            Request::builder()
+                .uri(format!("/kv/{}?fields={}", test_id, fields))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let cases = [
+            // Nested paths keep their nesting
+            ("name,settings.theme", serde_json::json!({"name": "projected", "settings": {"theme": "dark"}})),
+            ("settings.font.size", serde_json::json!({"settings": {"font": {"size": 12}}})),
+            // Arrays come back whole
+            ("tags,items", serde_json::json!({"tags": ["a", "b"], "items": [{"sku": 1}, {"sku": 2}]})),
+            // Missing paths are left out; a stored null is kept
+            ("name,missing,settings.missing.deeper,note", serde_json::json!({"name": "projected", "note": null})),
+            ("missing", serde_json::json!({})),
+        ];
+        for (fields, expected) in cases {
+            let response = app.clone().oneshot(get(fields)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "fields={}", fields);
+            assert!(response.headers().contains_key("etag"));
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let response_json: GetResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(response_json.data, expected, "fields={}", fields);
+            assert_eq!(response_json.version, Some(1));
+        }
+
+        let response = app.clone().oneshot(get("settings..theme")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv/{}?fields=name", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_strong_and_stale_reads() {
+        let app = setup_test_app().await;
+        let test_id = Uuid::new_v4();
+        let test_data = serde_json::json!({"fresh": true});
+
+        let put_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", test_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&test_data).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(put_response.status(), StatusCode::CREATED);
+
+        // Without the parameter the read is strong and sees the write; a zero
+        // staleness bound must see it too
+        for uri in [format!("/kv/{}", test_id), format!("/kv/{}?max_staleness_ms=0", test_id)] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().method("GET").uri(&uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "GET {}", uri);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let response_json: GetResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(response_json.data, test_data);
+        }
+
+        // A stale read may or may not see the write, but must succeed either way
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri(format!("/kv/{}?max_staleness_ms=60000", test_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(
+            matches!(response.status(), StatusCode::OK | StatusCode::NOT_FOUND),
+            "Stale read failed with {}",
+            response.status()
+        );
+
+        for value in ["-1", "60001", "soon"] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("GET")
+                        .uri(format!("/kv/{}?max_staleness_ms={}", test_id, value))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "max_staleness_ms={}", value);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_uuid_spellings_address_the_same_key() {
+        let app = setup_test_app().await;
+
+        let test_id = Uuid::new_v4();
+        let canonical = test_id.to_string();
+        let upper = canonical.to_uppercase();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", upper))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"spelling": "upper"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let put_response: crate::models::PutResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(put_response.id, canonical, "Stored key should be normalized");
+
+        for spelling in [
+            canonical.clone(),
+            upper.clone(),
+            format!("%7B{}%7D", upper),
+            format!("urn:uuid:{}", canonical),
+            test_id.simple().to_string(),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("/kv/{}", spelling))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "GET /kv/{}", spelling);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let get_response: GetResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(get_response.id, canonical);
+            assert_eq!(get_response.data["spelling"], "upper");
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_etag_stable_until_put() {
+        let app = setup_test_app().await;
+        let test_id = Uuid::new_v4();
+
+        let put_document = |body: &'static str| {
+            app.clone().oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", test_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+        };
+        let get_etag = || async {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("/kv/{}", test_id))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let header = response.headers()["etag"].to_str().unwrap().to_string();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let get_response: GetResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(get_response.etag.as_deref(), Some(header.as_str()), "Body and header ETags should match");
+            header
+        };
+
+        assert_eq!(put_document(r#"{"v": 1}"#).await.unwrap().status(), StatusCode::CREATED);
+        let first = get_etag().await;
+        assert!(first.starts_with('"') && first.ends_with('"'), "ETag should be quoted: {}", first);
+        assert_eq!(get_etag().await, first, "Repeated GETs should return the same ETag");
+
+        assert_eq!(put_document(r#"{"v": 2}"#).await.unwrap().status(), StatusCode::OK);
+        let second = get_etag().await;
+        assert_ne!(second, first, "ETag should change after a PUT");
+        assert_eq!(get_etag().await, second);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_returns_timestamps() {
+        let app = setup_test_app().await;
+        let test_id = Uuid::new_v4();
+
+        let put_document = |body: &'static str| {
+            app.clone().oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", test_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+        };
+        let get_timestamps = || async {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(format!("/kv/{}", test_id)).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let get_response: GetResponse = serde_json::from_slice(&body).unwrap();
+            let parse = |timestamp: Option<String>| {
+                chrono::DateTime::parse_from_rfc3339(&timestamp.expect("timestamp should be present")).unwrap()
+            };
+            (parse(get_response.created_at), parse(get_response.updated_at))
+        };
+
+        assert_eq!(put_document(r#"{"v": 1}"#).await.unwrap().status(), StatusCode::CREATED);
+        let (created_at, updated_at) = get_timestamps().await;
+        assert_eq!(created_at, updated_at, "A new document is created and updated in the same commit");
+
+        assert_eq!(put_document(r#"{"v": 2}"#).await.unwrap().status(), StatusCode::OK);
+        let (created_again, updated_again) = get_timestamps().await;
+        assert!(updated_again > updated_at, "updated_at should move to the later commit");
+        assert_eq!(created_again, created_at, "Rewriting a live document should keep its created_at");
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_if_none_match() {
+        let app = setup_test_app().await;
+        let test_id = Uuid::new_v4();
+
+        let put_document = |body: &'static str| {
+            app.clone().oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", test_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+        };
+        let get_with = |if_none_match: Option<String>| {
+            let mut builder = Request::builder().uri(format!("/kv/{}", test_id));
+            if let Some(value) = if_none_match {
+                builder = builder.header("if-none-match", value);
+            }
+            app.clone().oneshot(builder.body(Body::empty()).unwrap())
+        };
+
+        assert_eq!(put_document(r#"{"v": 1}"#).await.unwrap().status(), StatusCode::CREATED);
+        let response = get_with(None).await.unwrap();
+        let current = response.headers()["etag"].to_str().unwrap().to_string();
+
+        // A matching tag, alone, weak or in a list, gets an empty 304 that still carries the ETag
+        for value in [current.clone(), format!("W/{}", current), format!("\"other\", {}", current), "*".to_string()] {
+            let response = get_with(Some(value.clone())).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "If-None-Match: {}", value);
+            assert_eq!(response.headers()["etag"], current.as_str());
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert!(body.is_empty());
+        }
+
+        // Non-matching and malformed headers get the full document
+        for value in ["\"other\"", "not-quoted", "\""] {
+            let response = get_with(Some(value.to_string())).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "If-None-Match: {}", value);
+        }
+
+        // Once the document changes, the old tag no longer matches
+        assert_eq!(put_document(r#"{"v": 2}"#).await.unwrap().status(), StatusCode::OK);
+        let response = get_with(Some(current)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // A missing key is still 404, even for *
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv/{}", Uuid::new_v4()))
+                    .header("if-none-match", "*")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_cache_control_headers() {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config {
+            list_cache_max_age: Some(120),
+            ..Config::for_emulator("put-endpoint-test", "put-endpoint-test-db")
+        };
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+        let app = Router::new()
+            .route(crate::routes::KV_ITEM, put(put_handler).get(get_handler))
+            .with_state(AppState {
+                spanner_client,
+                jobs: Arc::new(JobRegistry::from_config(&config)),
+                config: Arc::new(config),
+                metrics: Metrics::new(),
+            });
+
+        let test_id = Uuid::new_v4();
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", test_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"cached": true}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()["cache-control"], "no-store");
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv/{}", test_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["cache-control"], "public, max-age=120");
+
+        // Misses are never marked cacheable
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv/{}", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(response.headers().get("cache-control").is_none());
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_wait_returns_key_written_during_wait() {
+        let app = setup_test_app().await;
+        let test_id = Uuid::new_v4();
+        let test_data = serde_json::json!({"ready": true});
+
+        // Write the key shortly after the GET starts waiting
+        let writer = {
+            let app = app.clone();
+            let body = serde_json::to_string(&test_data).unwrap();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                app.oneshot(
+                    Request::builder()
+                        .method("PUT")
+                        .uri(format!("/kv/{}", test_id))
+                        .header("content-type", "application/json")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+            })
+        };
+
+        let get_response = app
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri(format!("/kv/{}?wait=10s", test_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(writer.await.unwrap().status(), StatusCode::CREATED);
+        assert_eq!(get_response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(get_response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response_json: GetResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response_json.data, test_data);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_wait_times_out_and_is_capped() {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config {
+            max_get_wait_secs: 1,
+            ..Config::for_emulator("put-endpoint-test", "put-endpoint-test-db")
+        };
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+        let app = Router::new()
+            .route(crate::routes::KV_ITEM, put(put_handler).get(get_handler))
+            .with_state(AppState {
+                spanner_client,
+                jobs: Arc::new(JobRegistry::from_config(&config)),
+                config: Arc::new(config),
+                metrics: Metrics::new(),
+            });
+
+        // Asking for 60s is capped to the configured 1s
+        let started = std::time::Instant::now();
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri(format!("/kv/{}?wait=60s", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let elapsed = started.elapsed();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(elapsed >= Duration::from_secs(1), "Should wait before giving up, took {:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(10), "Wait should be capped, took {:?}", elapsed);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri(format!("/kv/{}?wait=soon", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
 }
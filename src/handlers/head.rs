@@ -0,0 +1,135 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::key::parse_key;
+use crate::routes;
+use crate::state::AppState;
+use axum::{extract::State, extract::Path, http::header, http::HeaderMap, http::HeaderValue, http::StatusCode};
+
+/// HEAD /kv/:id handler - Check whether a document exists without returning it
+///
+/// Only the key is looked up, so this is cheaper than GET for large documents.
+#[utoipa::path(
+    head,
+    path = routes::KV_ITEM,
+    params(
+        ("id" = String, Path, description = "Key for the document: a UUID, or any valid key in KEY_MODE=string")
+    ),
+    responses(
+        (status = 200, description = "Document exists"),
+        (status = 400, description = "Invalid UUID format, or invalid key in KEY_MODE=string", body = ErrorResponse),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn head_handler(
+    State(state): State<AppState>,
+    Path(id_str): Path<String>,
+) -> Result<(StatusCode, HeaderMap), ApiError> {
+    let id = parse_key(&state.config, &id_str)?;
+
+    if !state.spanner_client.exists(&id).await? {
+        return Err(ApiError::KeyNotFound(id));
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("0"));
+    Ok((StatusCode::OK, headers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::handlers::{get_handler, put_handler};
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::put, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> Router {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("put-endpoint-test", "put-endpoint-test-db");
+
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        Router::new()
+            .route(
+                routes::KV_ITEM,
+                put(put_handler).get(get_handler).head(head_handler),
+            )
+            .with_state(state)
+    }
+
+    fn request(method: &str, uri: String) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(if method == "PUT" { Body::from(r#"{"large": "value"}"#) } else { Body::empty() })
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_head_existing_key() {
+        let app = setup_test_app().await;
+        let test_id = Uuid::new_v4();
+
+        let response = app.clone().oneshot(request("PUT", format!("/kv/{}", test_id))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = app.clone().oneshot(request("HEAD", format!("/kv/{}", test_id))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "0");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty(), "HEAD must not return a body");
+
+        // GET on the same path still returns the document
+        let response = app.oneshot(request("GET", format!("/kv/{}", test_id))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("large"));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_head_missing_and_invalid_keys() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(request("HEAD", format!("/kv/{}", Uuid::new_v4())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app.oneshot(request("HEAD", "/kv/not-a-uuid".to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -3,20 +3,41 @@ use crate::routes;
 use crate::state::AppState;
 use axum::{extract::State, http::StatusCode, Json};
 
-/// GET /health handler - Health check endpoint
+/// GET /health/live handler - Liveness check
+///
+/// Returns 200 OK whenever the process can serve requests, without touching
+/// Spanner, so a database outage doesn't get the process restarted.
+#[utoipa::path(
+    get,
+    path = routes::HEALTH_LIVE,
+    responses(
+        (status = 200, description = "Process is running", body = HealthResponse)
+    ),
+    tag = "health"
+)]
+pub async fn liveness_handler() -> (StatusCode, Json<HealthResponse>) {
+    (
+        StatusCode::OK,
+        Json(HealthResponse {
+            status: "alive".to_string(),
+        }),
+    )
+}
+
+/// GET /health/ready handler - Readiness check, also served at /health
 ///
 /// Performs a simple query to Spanner to verify database connectivity.
 /// Returns 200 OK if the database is reachable, 503 Service Unavailable otherwise.
 #[utoipa::path(
     get,
-    path = routes::HEALTH,
+    path = routes::HEALTH_READY,
     responses(
         (status = 200, description = "Service is healthy", body = HealthResponse),
         (status = 503, description = "Service is unhealthy", body = UnhealthyResponse)
     ),
     tag = "health"
 )]
-pub async fn health_handler(
+pub async fn readiness_handler(
     State(state): State<AppState>,
 ) -> Result<(StatusCode, Json<HealthResponse>), (StatusCode, Json<UnhealthyResponse>)> {
     // Perform a simple query to verify Spanner connectivity
@@ -48,6 +69,8 @@ pub async fn health_handler(
 mod tests {
     use super::*;
     use crate::config::Config;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
     use crate::spanner::SpannerClient;
     use axum::{body::Body, http::Request, routing::get, Router};
     use std::sync::Arc;
@@ -60,14 +83,7 @@ mod tests {
             std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
         }
 
-        let config = Config {
-            spanner_emulator_host: Some("localhost:9010".to_string()),
-            spanner_project: "test-project".to_string(),
-            spanner_instance: "health-endpoint-test".to_string(),
-            spanner_database: "health-endpoint-test-db".to_string(),
-            service_port: 3000,
-            service_host: "0.0.0.0".to_string(),
-        };
+        let config = Config::for_emulator("health-endpoint-test", "health-endpoint-test-db");
 
         let spanner_client = SpannerClient::from_config(&config)
             .await
@@ -75,31 +91,37 @@ mod tests {
 
         let state = AppState {
             spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
             config: Arc::new(config),
+            metrics: Metrics::new(),
         };
 
         let app = Router::new()
-            .route(crate::routes::HEALTH, get(health_handler))
+            .route(crate::routes::HEALTH, get(readiness_handler))
+            .route(crate::routes::HEALTH_READY, get(readiness_handler))
             .with_state(state);
 
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .method("GET")
-                    .uri("/health")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-
-        assert_eq!(response.status(), StatusCode::OK);
-
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-            .await
-            .unwrap();
-        let response_json: HealthResponse = serde_json::from_slice(&body).unwrap();
-        assert_eq!(response_json.status, "healthy");
+        for uri in ["/health", "/health/ready"] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("GET")
+                        .uri(uri)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let response_json: HealthResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(response_json.status, "healthy");
+        }
 
         unsafe {
             std::env::remove_var("SPANNER_EMULATOR_HOST");
@@ -115,11 +137,7 @@ mod tests {
 
         let config = Config {
             spanner_emulator_host: Some("localhost:9999".to_string()),
-            spanner_project: "test-project".to_string(),
-            spanner_instance: "health-endpoint-unhealthy-test".to_string(),
-            spanner_database: "health-endpoint-unhealthy-test-db".to_string(),
-            service_port: 3000,
-            service_host: "0.0.0.0".to_string(),
+            ..Config::for_emulator("health-endpoint-unhealthy-test", "health-endpoint-unhealthy-test-db")
         };
 
         // Try to create a client - this should fail because the emulator doesn't exist
@@ -142,4 +160,35 @@ mod tests {
             return;
         }
     }
+
+    #[tokio::test]
+    async fn test_liveness_with_unreachable_database() {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9999");
+        }
+
+        // Liveness needs no Spanner client at all, so it answers even when none can be created
+        let app = Router::new().route(crate::routes::HEALTH_LIVE, get(liveness_handler));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri("/health/live")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response_json: HealthResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response_json.status, "alive");
+    }
 }
@@ -0,0 +1,212 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::key::parse_key;
+use crate::models::{HistoryEntryResponse, HistoryQuery, HistoryResponse};
+use crate::routes;
+use crate::state::AppState;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+
+/// GET /kv/:id/history handler - List a document's retained versions
+///
+/// Every write records the document's new version in the same commit, so the
+/// newest entry is always the current document. Only the last
+/// `HISTORY_MAX_VERSIONS` versions are kept; older ones are pruned on write.
+/// Query parameters:
+/// - limit: Maximum number of versions to return (optional)
+/// - offset: Number of versions to skip (optional, default: 0)
+#[utoipa::path(
+    get,
+    path = routes::KV_HISTORY,
+    params(
+        ("id" = String, Path, description = "Key of the document: a UUID, or any valid key in KEY_MODE=string"),
+        ("limit" = Option<u32>, Query, description = "Maximum number of versions to return"),
+        ("offset" = Option<u32>, Query, description = "Number of versions to skip")
+    ),
+    responses(
+        (status = 200, description = "Retained versions, newest first", body = HistoryResponse),
+        (status = 400, description = "Invalid UUID format, or invalid key in KEY_MODE=string", body = ErrorResponse),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn history_handler(
+    State(state): State<AppState>,
+    Path(id_str): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<(StatusCode, Json<HistoryResponse>), ApiError> {
+    let id = parse_key(&state.config, &id_str)?;
+
+    let limit = query.limit.map(i64::from);
+    let offset = query.offset.map(i64::from).unwrap_or(0);
+    let page = state
+        .spanner_client
+        .history(&id, limit, offset)
+        .await?
+        .ok_or_else(|| ApiError::KeyNotFound(id.clone()))?;
+
+    tracing::debug!("Returned {} of {} versions of {}", page.entries.len(), page.total_count, id);
+
+    Ok((
+        StatusCode::OK,
+        Json(HistoryResponse {
+            id,
+            entries: page
+                .entries
+                .into_iter()
+                .map(|entry| HistoryEntryResponse {
+                    version: entry.version,
+                    data: entry.data,
+                    committed_at: entry.committed_at.to_rfc3339(),
+                })
+                .collect(),
+            total_count: page.total_count,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::handlers::{delete_handler, patch_handler, put_handler, rename_handler};
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::get, routing::post, routing::put, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    const MAX_VERSIONS: u32 = 3;
+
+    async fn setup_test_app() -> Router {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config {
+            history_max_versions: MAX_VERSIONS,
+            ..Config::for_emulator("history-endpoint-test", "history-endpoint-test-db")
+        };
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        Router::new()
+            .route(routes::KV_ITEM, put(put_handler).patch(patch_handler).delete(delete_handler))
+            .route(routes::KV_RENAME, post(rename_handler))
+            .route(routes::KV_HISTORY, get(history_handler))
+            .with_state(state)
+    }
+
+    async fn send(app: &Router, method: &str, uri: &str, body: Option<serde_json::Value>) -> StatusCode {
+        let mut builder = Request::builder().method(method).uri(uri);
+        let body = match body {
+            Some(body) => {
+                let content_type = if method == "PATCH" { "application/merge-patch+json" } else { "application/json" };
+                builder = builder.header("content-type", content_type);
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+        app.clone().oneshot(builder.body(body).unwrap()).await.unwrap().status()
+    }
+
+    async fn history(app: &Router, uri: &str) -> (StatusCode, Option<HistoryResponse>) {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).ok())
+    }
+
+    fn versions(response: &HistoryResponse) -> Vec<i64> {
+        response.entries.iter().map(|entry| entry.version).collect()
+    }
+
+    #[tokio::test]
+    async fn test_history_newest_first_and_pruned() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+
+        for n in 1..=4 {
+            let expected = if n == 1 { StatusCode::CREATED } else { StatusCode::OK };
+            assert_eq!(send(&app, "PUT", &format!("/kv/{}", id), Some(serde_json::json!({"n": n}))).await, expected);
+        }
+        assert_eq!(
+            send(&app, "PATCH", &format!("/kv/{}", id), Some(serde_json::json!({"patched": true}))).await,
+            StatusCode::OK
+        );
+
+        // Only the last MAX_VERSIONS writes are kept, the patch being the newest
+        let (status, page) = history(&app, &format!("/kv/{}/history", id)).await;
+        assert_eq!(status, StatusCode::OK);
+        let page = page.unwrap();
+        assert_eq!(page.id, id.to_string());
+        assert_eq!(page.total_count, i64::from(MAX_VERSIONS));
+        assert_eq!(versions(&page), vec![5, 4, 3]);
+        assert_eq!(page.entries[0].data, serde_json::json!({"n": 4, "patched": true}));
+        assert_eq!(page.entries[1].data, serde_json::json!({"n": 4}));
+        assert!(page.entries[0].committed_at >= page.entries[1].committed_at);
+
+        let (_, page) = history(&app, &format!("/kv/{}/history?limit=1&offset=1", id)).await;
+        let page = page.unwrap();
+        assert_eq!(versions(&page), vec![4]);
+        assert_eq!(page.total_count, i64::from(MAX_VERSIONS));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_follows_delete_and_rename() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+        let new_id = Uuid::new_v4();
+
+        let (status, _) = history(&app, &format!("/kv/{}/history", id)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = history(&app, "/kv/not-a-uuid/history").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Deleting a document deletes its history, so a new one starts afresh
+        send(&app, "PUT", &format!("/kv/{}", id), Some(serde_json::json!({"gen": 1}))).await;
+        send(&app, "PUT", &format!("/kv/{}", id), Some(serde_json::json!({"gen": 1}))).await;
+        assert_eq!(send(&app, "DELETE", &format!("/kv/{}", id), None).await, StatusCode::OK);
+        let (status, _) = history(&app, &format!("/kv/{}/history", id)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        send(&app, "PUT", &format!("/kv/{}", id), Some(serde_json::json!({"gen": 2}))).await;
+        send(&app, "PUT", &format!("/kv/{}", id), Some(serde_json::json!({"gen": 2, "n": 2}))).await;
+        let (_, page) = history(&app, &format!("/kv/{}/history", id)).await;
+        assert_eq!(versions(&page.unwrap()), vec![2, 1]);
+
+        // A renamed document takes its history with it
+        let status = send(&app, "POST", &format!("/kv/{}/rename", id), Some(serde_json::json!({"new_id": new_id.to_string()}))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = history(&app, &format!("/kv/{}/history", id)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, page) = history(&app, &format!("/kv/{}/history", new_id)).await;
+        let page = page.unwrap();
+        assert_eq!(versions(&page), vec![3, 2, 1]);
+        assert_eq!(page.entries[0].data, serde_json::json!({"gen": 2, "n": 2}));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -0,0 +1,516 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::cache_control::write_cache_headers;
+use crate::handlers::key::parse_key;
+use crate::jobs::JobHandle;
+use crate::models::{BatchPutEntry, ImportLineError, ImportQuery, ImportResponse};
+use crate::routes;
+use crate::spanner::{ExistingKeys, BATCH_CHUNK_SIZE};
+use crate::state::AppState;
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use futures_util::StreamExt;
+use serde_json::Value as JsonValue;
+use std::collections::HashSet;
+
+/// Malformed lines listed in an import response; the rest are only counted
+const MAX_REPORTED_ERRORS: usize = 100;
+
+/// Why an import stopped before the end of its body
+enum Stop {
+    /// A malformed line with `strict=true`
+    Strict { line: usize, error: String },
+    /// The next chunk's new keys would exceed `MAX_DOCUMENTS`
+    DocumentLimit(u64),
+    /// A quota check or commit failed
+    Database(anyhow::Error),
+    /// The request body could not be read
+    Body(String),
+    /// An operator cancelled the job through `/admin/jobs`
+    Cancelled,
+}
+
+impl Stop {
+    /// Reported in `error` when some documents were already imported
+    fn message(&self) -> String {
+        match self {
+            Stop::Strict { line, error } => format!("Stopped at malformed line {}: {}", line, error),
+            Stop::DocumentLimit(max) => format!("Stopped at the document limit of {}", max),
+            Stop::Database(err) => format!("Database error: {:#}", err),
+            Stop::Body(err) => format!("Failed to read the request body: {}", err),
+            Stop::Cancelled => "Import cancelled".to_string(),
+        }
+    }
+
+    /// Returned instead of a response when nothing was imported yet
+    fn into_api_error(self) -> ApiError {
+        match self {
+            Stop::Strict { line, error } => ApiError::InvalidRequest(format!("line {}: {}", line, error)),
+            Stop::DocumentLimit(max) => ApiError::DocumentLimitReached(max),
+            Stop::Database(err) => err.into(),
+            Stop::Body(err) => ApiError::InvalidRequest(format!("failed to read the body: {}", err)),
+            Stop::Cancelled => ApiError::Conflict("import was cancelled".to_string()),
+        }
+    }
+}
+
+/// POST /kv/import handler - Load documents from an NDJSON export
+///
+/// Reads one `{"id", "data"}` object per line, the format written by
+/// `GET /kv/export?format=ndjson` (other fields are ignored). The body is read
+/// as it arrives and written in chunks as `POST /kv:batch` does, so it isn't
+/// subject to `MAX_BODY_BYTES`; only each line is.
+///
+/// Malformed lines are reported with their line numbers and skipped, unless
+/// `strict=true`, which stops at the first one without writing the chunk in
+/// progress. With `mode=skip_existing`, keys that already hold a document are
+/// left alone and counted as skipped.
+///
+/// Chunks are committed as they fill, so an import that stops early (a strict
+/// failure, the document limit, a database error) keeps what it wrote and
+/// answers 207 with the reason in `error`. If it stops before writing anything
+/// it fails like any other request. Re-sending an import is safe.
+///
+/// Each import is registered as an `import` job, so it shows up under
+/// `/admin/jobs` and can be cancelled between chunks.
+#[utoipa::path(
+    post,
+    path = routes::KV_IMPORT,
+    params(
+        ("mode" = Option<String>, Query, description = "overwrite (default) replaces existing documents; skip_existing leaves them alone"),
+        ("strict" = Option<bool>, Query, description = "Stop at the first malformed line instead of skipping it")
+    ),
+    request_body(content = String, content_type = "application/x-ndjson", description = "One {\"id\", \"data\"} object per line, as written by GET /kv/export?format=ndjson"),
+    responses(
+        (status = 200, description = "Every line was read; malformed ones are listed in errors", body = ImportResponse),
+        (status = 207, description = "The import stopped partway; documents before that point were written", body = ImportResponse),
+        (status = 400, description = "Invalid mode, or a malformed first line with strict=true", body = ErrorResponse),
+        (status = 409, description = "Cancelled before anything was written", body = ErrorResponse),
+        (status = 500, description = "Database error; nothing was written", body = ErrorResponse),
+        (status = 507, description = "New keys rejected because they would exceed MAX_DOCUMENTS", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn import_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ImportQuery>,
+    body: Body,
+) -> Result<(StatusCode, HeaderMap, Json<ImportResponse>), ApiError> {
+    let (mode, existing) = match query.mode.as_deref() {
+        None | Some("overwrite") => ("overwrite", ExistingKeys::Overwrite),
+        Some("skip_existing") => ("skip_existing", ExistingKeys::Skip),
+        Some(other) => {
+            return Err(ApiError::InvalidQueryParam(format!(
+                "mode must be one of: overwrite, skip_existing, got '{}'",
+                other
+            )))
+        }
+    };
+    let strict = query.strict.unwrap_or(false);
+
+    let job = state
+        .jobs
+        .register("import", serde_json::json!({"mode": mode, "strict": strict}));
+    let mut import = Import {
+        state: &state,
+        existing,
+        strict,
+        job,
+        pending: Vec::new(),
+        pending_ids: HashSet::new(),
+        imported: 0,
+        skipped: 0,
+        failed: 0,
+        errors: Vec::new(),
+    };
+    let outcome = read_lines(&mut import, body, state.config.max_body_bytes).await;
+    let Import { job, imported, skipped, failed, errors, .. } = import;
+
+    job.finish(&outcome.as_ref().map_err(|stop| anyhow::anyhow!(stop.message())));
+    let error = match outcome {
+        // Nothing was written, so this is an ordinary failed request
+        Err(stop) if imported + skipped == 0 => return Err(stop.into_api_error()),
+        Err(stop) => Some(stop.message()),
+        Ok(()) => None,
+    };
+    let status = if error.is_some() { StatusCode::MULTI_STATUS } else { StatusCode::OK };
+
+    tracing::info!("Imported {} documents, skipped {}, {} malformed lines ({})", imported, skipped, failed, mode);
+    Ok((
+        status,
+        write_cache_headers(&state.config),
+        Json(ImportResponse {
+            imported,
+            skipped,
+            errors,
+            failed,
+            error,
+        }),
+    ))
+}
+
+/// Split the body into lines and feed them to the import, then commit what's left
+///
+/// A line longer than `max_line` bytes is reported as malformed and dropped
+/// without being held in memory.
+async fn read_lines(import: &mut Import<'_>, body: Body, max_line: usize) -> Result<(), Stop> {
+    let mut stream = body.into_data_stream();
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut number = 0;
+    // Set while dropping the rest of a line that was too long
+    let mut oversized = false;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| Stop::Body(e.to_string()))?;
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            if std::mem::take(&mut oversized) {
+                continue;
+            }
+            number += 1;
+            import.line(number, &line).await?;
+        }
+        if !oversized && buffer.len() > max_line {
+            number += 1;
+            import.reject(number, format!("line is longer than {} bytes", max_line))?;
+            oversized = true;
+        }
+        if oversized {
+            buffer.clear();
+        }
+    }
+
+    // The last line needn't end with a newline
+    if !oversized && !buffer.is_empty() {
+        number += 1;
+        import.line(number, &buffer).await?;
+    }
+    import.flush().await
+}
+
+/// Progress of one import, with the documents waiting for their chunk to fill
+struct Import<'a> {
+    state: &'a AppState,
+    existing: ExistingKeys,
+    strict: bool,
+    job: JobHandle,
+    pending: Vec<(String, JsonValue)>,
+    pending_ids: HashSet<String>,
+    imported: usize,
+    skipped: usize,
+    failed: usize,
+    errors: Vec<ImportLineError>,
+}
+
+impl Import<'_> {
+    /// Validate one line and queue its document, committing once a chunk is full
+    async fn line(&mut self, number: usize, line: &[u8]) -> Result<(), Stop> {
+        let line = line.trim_ascii();
+        if line.is_empty() {
+            return Ok(());
+        }
+        let (id, data) = match self.parse(line) {
+            Ok(document) => document,
+            Err(error) => return self.reject(number, error),
+        };
+
+        // A key repeated within one commit would be written twice; committing
+        // the earlier line first keeps the file's order, last line winning
+        if self.pending_ids.contains(&id) {
+            self.flush().await?;
+        }
+        self.pending_ids.insert(id.clone());
+        self.pending.push((id, data));
+        if self.pending.len() >= BATCH_CHUNK_SIZE {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// The key and document of a line, or why it can't be imported
+    fn parse(&self, line: &[u8]) -> Result<(String, JsonValue), String> {
+        let entry: BatchPutEntry = serde_json::from_slice(line).map_err(|e| format!("invalid entry: {}", e))?;
+        let id = parse_key(&self.state.config, &entry.id).map_err(|e| match e {
+            ApiError::ReservedKey(key) => format!("key {} is reserved for internal use", key),
+            _ => format!("malformed key '{}'", entry.id),
+        })?;
+        let size = entry.data.to_string().len();
+        if size > self.state.config.max_document_bytes {
+            return Err(format!(
+                "document is {} bytes, the maximum is {}",
+                size, self.state.config.max_document_bytes
+            ));
+        }
+        Ok((id, entry.data))
+    }
+
+    /// Record a malformed line, stopping the import if it is strict
+    fn reject(&mut self, line: usize, error: String) -> Result<(), Stop> {
+        self.failed += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(ImportLineError { line, error: error.clone() });
+        }
+        if self.strict {
+            return Err(Stop::Strict { line, error });
+        }
+        Ok(())
+    }
+
+    /// Commit the pending documents
+    async fn flush(&mut self) -> Result<(), Stop> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        // Chunk boundaries are where an operator's cancellation takes effect
+        if self.job.is_cancelled() {
+            return Err(Stop::Cancelled);
+        }
+        let items = std::mem::take(&mut self.pending);
+        self.pending_ids.clear();
+
+        let client = &self.state.spanner_client;
+        let ids: Vec<String> = items.iter().map(|(id, _)| id.clone()).collect();
+        let has_room = client
+            .has_room_for_many(&ids)
+            .await
+            .map_err(|e| Stop::Database(e.into()))?;
+        if !has_room {
+            return Err(Stop::DocumentLimit(client.document_limit().unwrap_or_default()));
+        }
+
+        let result = client.write_batch(items, self.existing).await;
+        self.imported += result.written;
+        self.skipped += result.skipped;
+        self.job.add_progress((result.written + result.skipped) as u64);
+        match result.error {
+            Some(error) => Err(Stop::Database(error)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::handlers::export::export_handler;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::models::ExportRecord;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Bytes, http::Request, routing::get, routing::post, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> (Router, SpannerClient) {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("import-test", "import-test-db");
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client: spanner_client.clone(),
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        let app = Router::new()
+            .route(routes::KV_IMPORT, post(import_handler))
+            .route(routes::KV_EXPORT, get(export_handler))
+            .with_state(state);
+        (app, spanner_client)
+    }
+
+    /// A UUID key whose first group is `prefix`
+    fn key_under(prefix: &str) -> String {
+        format!("{}{}", prefix, &Uuid::new_v4().to_string()[8..])
+    }
+
+    fn run_prefix() -> String {
+        Uuid::new_v4().simple().to_string()[..8].to_string()
+    }
+
+    async fn import(app: &Router, query: &str, body: impl Into<Body>) -> (StatusCode, ImportResponse) {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/kv/import{}", query))
+                    .header("content-type", "application/x-ndjson")
+                    .body(body.into())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_else(|_| panic!("{}", String::from_utf8_lossy(&body))))
+    }
+
+    async fn export(app: &Router, prefix: &str) -> Bytes {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv/export?format=ndjson&prefix={}", prefix))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()
+    }
+
+    fn documents(export: &[u8]) -> Vec<(String, JsonValue)> {
+        String::from_utf8(export.to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let record: ExportRecord = serde_json::from_str(line).unwrap();
+                (record.id, record.data)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_export_wipe_import_round_trip() {
+        let (app, client) = setup_test_app().await;
+        let prefix = run_prefix();
+        let items: Vec<(Uuid, JsonValue)> = (0..100)
+            .map(|i| {
+                let id = Uuid::parse_str(&key_under(&prefix)).unwrap();
+                (id, serde_json::json!({"i": i, "nested": {"list": [i, "x"]}}))
+            })
+            .collect();
+        assert_eq!(client.upsert_batch(items).await.written, 100);
+
+        let exported = export(&app, &prefix).await;
+        let before = documents(&exported);
+        assert_eq!(before.len(), 100);
+
+        assert_eq!(client.delete_by_prefix(&prefix).await.unwrap(), 100);
+        assert!(documents(&export(&app, &prefix).await).is_empty());
+
+        // Streamed in small pieces that split lines, as a real upload would be
+        let pieces: Vec<Result<Bytes, std::io::Error>> =
+            exported.chunks(777).map(|piece| Ok(Bytes::copy_from_slice(piece))).collect();
+        let (status, response) = import(&app, "", Body::from_stream(futures_util::stream::iter(pieces))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response.imported, 100);
+        assert_eq!(response.skipped, 0);
+        assert!(response.errors.is_empty());
+
+        assert_eq!(documents(&export(&app, &prefix).await), before);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_modes() {
+        let (app, client) = setup_test_app().await;
+        let prefix = run_prefix();
+        let stored = key_under(&prefix);
+        let new = key_under(&prefix);
+        client
+            .upsert(Uuid::parse_str(&stored).unwrap(), serde_json::json!({"v": "original"}))
+            .await
+            .unwrap();
+
+        let body = format!(
+            "{}\n{}\n",
+            serde_json::json!({"id": stored, "data": {"v": "imported"}}),
+            serde_json::json!({"id": new, "data": {"v": "imported"}})
+        );
+        let (status, response) = import(&app, "?mode=skip_existing", body.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((response.imported, response.skipped), (1, 1));
+        let data = |key: &str| {
+            let client = client.clone();
+            let key = Uuid::parse_str(key).unwrap();
+            async move { client.read(key).await.unwrap().unwrap().data }
+        };
+        assert_eq!(data(&stored).await, serde_json::json!({"v": "original"}));
+        assert_eq!(data(&new).await, serde_json::json!({"v": "imported"}));
+
+        let (status, response) = import(&app, "?mode=overwrite", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((response.imported, response.skipped), (2, 0));
+        assert_eq!(data(&stored).await, serde_json::json!({"v": "imported"}));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/kv/import?mode=merge")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_malformed_lines() {
+        let (app, client) = setup_test_app().await;
+        let prefix = run_prefix();
+        let good = [key_under(&prefix), key_under(&prefix)];
+        let body = [
+            serde_json::json!({"id": good[0], "data": 1}).to_string(),
+            "{not json".to_string(),
+            String::new(),
+            serde_json::json!({"id": "not-a-uuid", "data": 2}).to_string(),
+            serde_json::json!({"id": good[1], "data": 3}).to_string(),
+        ]
+        .join("\n");
+
+        // Blank lines are ignored but still counted
+        let (status, response) = import(&app, "", body.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response.imported, 2);
+        assert_eq!(response.failed, 2);
+        let lines: Vec<usize> = response.errors.iter().map(|error| error.line).collect();
+        assert_eq!(lines, vec![2, 4]);
+        assert!(response.errors[1].error.contains("not-a-uuid"), "{:?}", response.errors);
+
+        // Strict mode stops at line 2, before line 1's chunk was committed
+        assert_eq!(client.delete_by_prefix(&prefix).await.unwrap(), 2);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/kv/import?strict=true")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains("line 2"), "{}", error_response.error);
+        assert!(client.read(Uuid::parse_str(&good[0]).unwrap()).await.unwrap().is_none());
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
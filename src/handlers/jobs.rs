@@ -0,0 +1,242 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::admin::require_admin;
+use crate::jobs::{CancelOutcome, JobInfo};
+use crate::models::JobListResponse;
+use crate::routes;
+use crate::state::AppState;
+use axum::{extract::State, extract::Path, http::HeaderMap, http::StatusCode, Json};
+use uuid::Uuid;
+
+/// GET /admin/jobs handler - List running and recently finished jobs
+#[utoipa::path(
+    get,
+    path = routes::ADMIN_JOBS,
+    responses(
+        (status = 200, description = "Retained jobs, most recent first, with counts by status", body = JobListResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 501, description = "Admin endpoints are not configured", body = ErrorResponse)
+    ),
+    tag = "admin"
+)]
+pub async fn list_jobs_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<JobListResponse>), ApiError> {
+    require_admin(&state.config, &headers)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(JobListResponse {
+            jobs: state.jobs.list(),
+            counts: state.jobs.counts(),
+        }),
+    ))
+}
+
+/// GET /admin/jobs/:id handler - Show a single job
+#[utoipa::path(
+    get,
+    path = routes::ADMIN_JOB,
+    params(
+        ("id" = String, Path, description = "Job id")
+    ),
+    responses(
+        (status = 200, description = "Job detail", body = JobInfo),
+        (status = 400, description = "Invalid job id", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Job not found or no longer retained", body = ErrorResponse),
+        (status = 501, description = "Admin endpoints are not configured", body = ErrorResponse)
+    ),
+    tag = "admin"
+)]
+pub async fn get_job_handler(
+    State(state): State<AppState>,
+    Path(id_str): Path<String>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<JobInfo>), ApiError> {
+    require_admin(&state.config, &headers)?;
+    let id = Uuid::parse_str(&id_str).map_err(|_| ApiError::InvalidUuid(id_str.clone()))?;
+
+    let job = state.jobs.get(id).ok_or(ApiError::JobNotFound(id))?;
+    Ok((StatusCode::OK, Json(job)))
+}
+
+/// POST /admin/jobs/:id/cancel handler - Request cancellation of a running job
+///
+/// Cancellation is cooperative: the job stops at its next chunk boundary, so the
+/// returned status is usually still `running` with `cancel_requested` set.
+#[utoipa::path(
+    post,
+    path = routes::ADMIN_JOB_CANCEL,
+    params(
+        ("id" = String, Path, description = "Job id")
+    ),
+    responses(
+        (status = 202, description = "Cancellation requested", body = JobInfo),
+        (status = 400, description = "Invalid job id", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Job not found or no longer retained", body = ErrorResponse),
+        (status = 409, description = "Job has already finished", body = ErrorResponse),
+        (status = 501, description = "Admin endpoints are not configured", body = ErrorResponse)
+    ),
+    tag = "admin"
+)]
+pub async fn cancel_job_handler(
+    State(state): State<AppState>,
+    Path(id_str): Path<String>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<JobInfo>), ApiError> {
+    require_admin(&state.config, &headers)?;
+    let id = Uuid::parse_str(&id_str).map_err(|_| ApiError::InvalidUuid(id_str.clone()))?;
+
+    match state.jobs.cancel(id) {
+        CancelOutcome::Requested(job) => Ok((StatusCode::ACCEPTED, Json(job))),
+        CancelOutcome::AlreadyFinished(job) => Err(ApiError::Conflict(format!(
+            "job {} has already finished as {:?}",
+            id, job.status
+        ))),
+        CancelOutcome::NotFound => Err(ApiError::JobNotFound(id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::jobs::{JobRegistry, JobStatus};
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::get, routing::post, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "test-admin-token";
+
+    async fn setup_test_app() -> (Router, Arc<JobRegistry>) {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config {
+            admin_token: Some(TOKEN.to_string()),
+            ..Config::for_emulator("jobs-endpoint-test", "jobs-endpoint-test-db")
+        };
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let jobs = Arc::new(JobRegistry::from_config(&config));
+        let state = AppState {
+            spanner_client,
+            jobs: jobs.clone(),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        let app = Router::new()
+            .route(routes::ADMIN_JOBS, get(list_jobs_handler))
+            .route(routes::ADMIN_JOB, get(get_job_handler))
+            .route(routes::ADMIN_JOB_CANCEL, post(cancel_job_handler))
+            .with_state(state);
+        (app, jobs)
+    }
+
+    fn admin_request(method: &str, uri: &str, token: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header("authorization", format!("Bearer {}", token));
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    async fn json_body<T: serde::de::DeserializeOwned>(response: axum::response::Response) -> T {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_jobs_require_admin_token() {
+        let (app, _jobs) = setup_test_app().await;
+
+        for token in [None, Some("wrong")] {
+            let response = app
+                .clone()
+                .oneshot(admin_request("GET", "/admin/jobs", token))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_detail_and_cancel_job() {
+        let (app, jobs) = setup_test_app().await;
+
+        // A synthetic job that processes chunks until cancelled
+        let handle = jobs.register("synthetic", serde_json::json!({"chunk_size": 10}));
+        let id = handle.id();
+        let job = tokio::spawn(async move {
+            while !handle.is_cancelled() {
+                handle.add_progress(10);
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+            handle.finish::<()>(&Err(anyhow::anyhow!("Cancelled")));
+        });
+
+        let response = app
+            .clone()
+            .oneshot(admin_request("GET", "/admin/jobs", Some(TOKEN)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let list: JobListResponse = json_body(response).await;
+        assert!(list.jobs.iter().any(|job| job.id == id && job.status == JobStatus::Running));
+        assert_eq!(list.counts.running, 1);
+
+        let response = app
+            .clone()
+            .oneshot(admin_request("GET", &format!("/admin/jobs/{}", id), Some(TOKEN)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let detail: JobInfo = json_body(response).await;
+        assert_eq!(detail.kind, "synthetic");
+        assert_eq!(detail.params["chunk_size"], 10);
+
+        let response = app
+            .clone()
+            .oneshot(admin_request("POST", &format!("/admin/jobs/{}/cancel", id), Some(TOKEN)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let cancelled: JobInfo = json_body(response).await;
+        assert!(cancelled.cancel_requested);
+
+        job.await.unwrap();
+        assert_eq!(jobs.get(id).unwrap().status, JobStatus::Cancelled);
+
+        // Cancelling again conflicts, and unknown jobs are 404
+        let response = app
+            .clone()
+            .oneshot(admin_request("POST", &format!("/admin/jobs/{}/cancel", id), Some(TOKEN)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = app
+            .oneshot(admin_request("GET", &format!("/admin/jobs/{}", Uuid::new_v4()), Some(TOKEN)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
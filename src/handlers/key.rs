@@ -0,0 +1,76 @@
+use crate::config::{Config, KeyMode};
+use crate::error::ApiError;
+use uuid::Uuid;
+
+/// Longest string key, the width of the `id STRING(36)` column
+pub const MAX_KEY_LEN: usize = 36;
+
+/// Characters allowed in string keys besides ASCII letters and digits
+const KEY_PUNCTUATION: [char; 5] = ['-', '_', '.', ':', '@'];
+
+/// Parse the document key from a request path according to `KEY_MODE`
+///
+/// In `uuid` mode the key is returned in canonical hyphenated form, so every
+/// spelling of a UUID addresses the same document. In `string` mode it is
+/// returned as given. Keys in the reserved namespace are refused in both.
+pub fn parse_key(config: &Config, raw: &str) -> Result<String, ApiError> {
+    let key = match config.key_mode {
+        KeyMode::Uuid => Uuid::parse_str(raw)
+            .map_err(|_| ApiError::InvalidUuid(raw.to_string()))?
+            .to_string(),
+        KeyMode::String => {
+            if raw.is_empty() || raw.len() > MAX_KEY_LEN {
+                return Err(ApiError::InvalidKey(format!(
+                    "keys must be 1 to {} characters, got {}",
+                    MAX_KEY_LEN,
+                    raw.len()
+                )));
+            }
+            if let Some(c) = raw.chars().find(|c| !c.is_ascii_alphanumeric() && !KEY_PUNCTUATION.contains(c)) {
+                return Err(ApiError::InvalidKey(format!(
+                    "'{}' is not allowed in keys; use letters, digits and {}",
+                    c,
+                    KEY_PUNCTUATION.iter().collect::<String>()
+                )));
+            }
+            raw.to_string()
+        }
+    };
+    if config.is_reserved_key(&key) {
+        return Err(ApiError::ReservedKey(key));
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(key_mode: KeyMode) -> Config {
+        Config {
+            key_mode,
+            ..Config::for_emulator("test-instance", "test-database")
+        }
+    }
+
+    #[test]
+    fn test_uuid_mode() {
+        let config = config(KeyMode::Uuid);
+        assert_eq!(
+            parse_key(&config, "550E8400E29B41D4A716446655440000").unwrap(),
+            "550e8400-e29b-41d4-a716-446655440000"
+        );
+        assert!(matches!(parse_key(&config, "user:1234"), Err(ApiError::InvalidUuid(_))));
+    }
+
+    #[test]
+    fn test_string_mode() {
+        let config = config(KeyMode::String);
+        for key in ["user:1234", "a", "order-2024_01.v2@eu", &"k".repeat(MAX_KEY_LEN)] {
+            assert_eq!(parse_key(&config, key).unwrap(), key);
+        }
+        for key in ["", &"k".repeat(MAX_KEY_LEN + 1), "a b", "a/b", "ключ", "a%20b"] {
+            assert!(matches!(parse_key(&config, key), Err(ApiError::InvalidKey(_))), "{:?} should be rejected", key);
+        }
+    }
+}
@@ -1,96 +1,457 @@
+use crate::config::Config;
+use crate::csv;
 use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::admin::require_admin;
+use crate::handlers::cache_control::read_cache_headers;
+use crate::handlers::page_token;
+use crate::handlers::read_info::{read_info_headers, read_info_requested};
+use crate::handlers::stream::rows_body;
 use crate::models::{KvEntryResponse, ListQuery, ListResponse};
 use crate::routes;
-use crate::spanner::SortOrder;
+use crate::spanner::{is_valid_field_path, ListFilter, MatchMode, Sort, SortOrder, SyncCursor};
 use crate::state::AppState;
-use axum::{extract::Query, extract::State, http::StatusCode, Json};
+use axum::{
+    extract::{rejection::QueryRejection, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::Value as JsonValue;
+
+/// Longest accepted `q` search term, in characters
+const MAX_SEARCH_LEN: usize = 256;
+
+/// Most `where` filters accepted in one list request
+const MAX_WHERE_FILTERS: usize = 16;
+
+/// Bytes escaped in `next`/`prev` query values: all but RFC 3986 unreserved characters
+const QUERY_VALUE: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// Content type of the CSV listing
+const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+
+/// Header row of the CSV listing
+const CSV_COLUMNS: [&str; 4] = ["key", "created_at", "updated_at", "value_json"];
 
 /// GET /kv handler - List all key-value pairs
 ///
 /// Returns a paginated, filterable, and sortable list of all key-value pairs.
 /// Query parameters:
-/// - limit: Maximum number of results to return (optional)
-/// - offset: Number of results to skip (optional, default: 0)
+/// - limit: Maximum number of results to return (optional, default: DEFAULT_LIMIT, at most MAX_LIMIT)
+/// - offset: Number of results to skip (optional, default: 0); prefer page_token
+/// - page_token: Resume after the page that returned this `next_page_token` (optional)
+/// - include_count: Set to false to skip counting every match, leaving out `total_count` (optional, default: true)
 /// - prefix: Filter keys starting with this value (optional)
+/// - match: How `prefix` matches keys - one of: prefix, suffix, contains, exact (optional, default: prefix)
 /// - sort: Sort order - one of: key_asc, key_desc, created_asc, created_desc, updated_asc, updated_desc (optional, default: key_asc)
+/// - updated_since: Only rows updated after this RFC 3339 timestamp, in `updated_at, id` order (optional)
+/// - after_key: With `updated_since`, resume after this key among rows updated at exactly that time (optional)
+/// - q: Case-insensitive substring to find anywhere in the serialized document; requires a limit of at most MAX_SEARCH_ROWS (optional)
+/// - where: `field:value` equality on a JSON field, repeatable; all must match (optional)
+/// - include_deleted: Also list soft-deleted documents; requires the admin token (optional)
+/// - created_after, created_before, updated_after, updated_before: RFC 3339 time range
+///   on `created_at` or `updated_at`; `_after` is inclusive and `_before` exclusive (optional)
+///
+/// Field filters: `field` is a dotted path such as `address.city`. A value of
+/// `true`/`false` or a number only matches a field of that JSON type; anything
+/// else, or a value in double quotes (`code:"42"`), matches a string field.
+///
+/// Search: `q` matches against each document's JSON text, keys and punctuation
+/// included, so it is a full scan of every row that passes the other filters.
+/// Pair it with `prefix` or `updated_since` to keep the scan small on large stores.
+///
+/// Pagination: while more rows follow, the response carries `next_page_token`,
+/// which resumes right after the page's last row in the same sort. Unlike
+/// `offset`, it stays fast deep into the list and never skips or repeats a row
+/// when others are written between pages. Each page also links to its
+/// neighbours: `next` and `prev` are relative URLs repeating this request's
+/// filters, left out on the last and first page. After a `page_token` only
+/// `next` is given, and it carries the next token.
+///
+/// Incremental sync: a response to an `updated_since` request carries `sync_timestamp`
+/// (and `sync_after_key` when more changes remain), which the client passes back as
+/// `updated_since` and `after_key` on its next call. Once caught up, `sync_timestamp`
+/// is the snapshot's read timestamp, so a later commit can never fall at or before it.
+///
+/// With `X-Debug-Read-Info: true` (or `DEBUG_READ_INFO` set), the response carries
+/// the Spanner read timestamp and mode in `X-Read-Timestamp` and `X-Read-Mode`.
+///
+/// With `LIST_CACHE_MAX_AGE` set, responses carry `Cache-Control: public, max-age=N`.
+///
+/// CSV: with `format=csv`, or `Accept: text/csv` and no `format`, the page is
+/// streamed as CSV instead, with a `key,created_at,updated_at,value_json` header
+/// row and the document's JSON in the last column. Only `prefix`, `sort`,
+/// `limit` and `offset` apply, and there is no total or next page.
 #[utoipa::path(
     get,
     path = routes::KV_LIST,
     params(
-        ("limit" = Option<u32>, Query, description = "Maximum number of results to return"),
-        ("offset" = Option<u32>, Query, description = "Number of results to skip"),
-        ("prefix" = Option<String>, Query, description = "Filter keys starting with this value"),
-        ("sort" = Option<String>, Query, description = "Sort order: key_asc, key_desc, created_asc, created_desc, updated_asc, updated_desc")
+        ("limit" = Option<u32>, Query, description = "Maximum number of results to return; defaults to DEFAULT_LIMIT (100), and a value above MAX_LIMIT (1000) is rejected with 400"),
+        ("offset" = Option<u32>, Query, description = "Number of results to skip; page_token is preferred, since large offsets are slow and shift when rows are written"),
+        ("include_count" = Option<bool>, Query, description = "Whether to count all matching rows into total_count (default true); false saves a full COUNT(*) scan, and has_more still tells whether another page follows"),
+        ("page_token" = Option<String>, Query, description = "next_page_token from the previous page; must be used with the same sort and prefix"),
+        ("prefix" = Option<String>, Query, description = "Filter keys starting with this value, or as match says; taken literally"),
+        ("match" = Option<MatchMode>, Query, description = "How prefix matches keys: prefix (default), suffix, contains or exact; suffix and contains scan every key"),
+        ("sort" = Option<Vec<SortOrder>>, Query, style = Form, explode = false, description = "Comma-separated sort orders applied in turn, e.g. updated_desc,key_asc (default key_asc); each column at most once, a key order last, and the key breaks any remaining ties"),
+        ("updated_since" = Option<String>, Query, description = "Only rows updated after this RFC 3339 timestamp; pass the previous sync_timestamp"),
+        ("after_key" = Option<String>, Query, description = "With updated_since, resume after this key; pass the previous sync_after_key"),
+        ("q" = Option<String>, Query, description = "Case-insensitive substring search across each document's JSON (full scan); requires a limit of at most MAX_SEARCH_ROWS"),
+        ("where" = Option<Vec<String>>, Query, description = "Repeatable field:value filter on a JSON field, e.g. type:fruit or count:3; all must match"),
+        ("include_deleted" = Option<bool>, Query, description = "Also list soft-deleted documents, with their deleted_at; requires the admin token"),
+        ("created_after" = Option<String>, Query, description = "Only rows created at or after this RFC 3339 timestamp"),
+        ("created_before" = Option<String>, Query, description = "Only rows created before this RFC 3339 timestamp"),
+        ("updated_after" = Option<String>, Query, description = "Only rows last updated at or after this RFC 3339 timestamp"),
+        ("updated_before" = Option<String>, Query, description = "Only rows last updated before this RFC 3339 timestamp"),
+        ("format" = Option<String>, Query, description = "Response format: json (default) or csv; csv only supports prefix, sort, limit and offset"),
+        ("X-Debug-Read-Info" = Option<bool>, Header, description = "Return the read timestamp and mode in response headers"),
+        ("Accept" = Option<String>, Header, description = "text/csv selects the CSV format when format is not given")
     ),
     responses(
-        (status = 200, description = "List of key-value pairs", body = ListResponse),
+        (status = 200, description = "List of key-value pairs", content(
+            (ListResponse = "application/json"),
+            (String = "text/csv", example = "key,created_at,updated_at,value_json\r\n")
+        ), headers(
+            ("X-Read-Timestamp" = String, description = "RFC 3339 timestamp the read was served at (debug only)"),
+            ("X-Read-Mode" = String, description = "Read mode, e.g. strong (debug only)"),
+            ("Cache-Control" = String, description = "public, max-age=N when LIST_CACHE_MAX_AGE is set")
+        )),
         (status = 400, description = "Invalid query parameter", body = ErrorResponse),
+        (status = 401, description = "include_deleted without a valid admin token", body = ErrorResponse),
+        (status = 501, description = "include_deleted while admin endpoints are not configured", body = ErrorResponse),
         (status = 500, description = "Database error", body = ErrorResponse)
     ),
     tag = "kv"
 )]
 pub async fn list_handler(
     State(state): State<AppState>,
-    Query(query): Query<ListQuery>,
-) -> Result<(StatusCode, Json<ListResponse>), ApiError> {
-    // Parse and validate sort parameter
-    let sort = if let Some(sort_str) = &query.sort {
-        match sort_str.as_str() {
-            "key_asc" => SortOrder::KeyAsc,
-            "key_desc" => SortOrder::KeyDesc,
-            "created_asc" => SortOrder::CreatedAsc,
-            "created_desc" => SortOrder::CreatedDesc,
-            "updated_asc" => SortOrder::UpdatedAsc,
-            "updated_desc" => SortOrder::UpdatedDesc,
-            _ => {
+    query: Result<Query<ListQuery>, QueryRejection>,
+    Query(pairs): Query<Vec<(String, String)>>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let Query(query) = query?;
+    let sort = query.sort.clone().unwrap_or_default();
+    if csv_requested(query.format.as_deref(), &headers)? {
+        return list_csv(&state, &query, sort).await;
+    }
+
+    let updated_since = match (&query.updated_since, &query.after_key) {
+        (Some(since), after_key) => Some(SyncCursor {
+            updated_at: parse_timestamp("updated_since", since)?,
+            after_key: after_key.clone(),
+        }),
+        (None, Some(_)) => {
+            return Err(ApiError::InvalidQueryParam(
+                "after_key requires updated_since".to_string(),
+            ))
+        }
+        (None, None) => None,
+    };
+    if updated_since.is_some() && query.sort.is_some() && !sort.is_sync_order() {
+        return Err(ApiError::InvalidQueryParam(
+            "updated_since always sorts by updated_asc".to_string(),
+        ));
+    }
+
+    let bound = |name: &str, raw: &Option<String>| raw.as_deref().map(|raw| parse_timestamp(name, raw)).transpose();
+    let created_after = bound("created_after", &query.created_after)?;
+    let created_before = bound("created_before", &query.created_before)?;
+    let updated_after = bound("updated_after", &query.updated_after)?;
+    let updated_before = bound("updated_before", &query.updated_before)?;
+
+    if let Some(q) = &query.q {
+        if q.is_empty() {
+            return Err(ApiError::InvalidQueryParam("q must not be empty".to_string()));
+        }
+        if q.chars().count() > MAX_SEARCH_LEN {
+            return Err(ApiError::InvalidQueryParam(format!(
+                "q must be at most {} characters",
+                MAX_SEARCH_LEN
+            )));
+        }
+        // A search scans every document, so the caller has to bound the page explicitly
+        match query.limit {
+            None => {
+                return Err(ApiError::InvalidQueryParam(format!(
+                    "q requires a limit of at most {}",
+                    state.config.max_search_rows
+                )))
+            }
+            Some(limit) if limit > state.config.max_search_rows => {
                 return Err(ApiError::InvalidQueryParam(format!(
-                    "sort must be one of: key_asc, key_desc, created_asc, created_desc, updated_asc, updated_desc, got '{}'",
-                    sort_str
+                    "limit must be at most {} with q, got {}",
+                    state.config.max_search_rows, limit
                 )))
             }
+            Some(_) => {}
         }
-    } else {
-        SortOrder::KeyAsc // default
-    };
+    }
+
+    // Repeated keys don't fit ListQuery, so `where` is read from the raw pairs
+    let fields = pairs
+        .iter()
+        .filter(|(key, _)| key == "where")
+        .map(|(_, raw)| parse_where(raw))
+        .collect::<Result<Vec<_>, _>>()?;
+    if fields.len() > MAX_WHERE_FILTERS {
+        return Err(ApiError::InvalidQueryParam(format!(
+            "at most {} where filters are allowed, got {}",
+            MAX_WHERE_FILTERS,
+            fields.len()
+        )));
+    }
+
+    // Soft-deleted documents are only shown to admins
+    let include_deleted = query.include_deleted.unwrap_or(false);
+    if include_deleted {
+        require_admin(&state.config, &headers)?;
+    }
 
-    // Convert limit and offset to i64
-    let limit = query.limit.map(|l| l as i64);
+    let limit = page_limit(&state.config, query.limit)?;
     let offset = query.offset.unwrap_or(0) as i64;
 
+    let page_after = match &query.page_token {
+        Some(_) if updated_since.is_some() => {
+            return Err(ApiError::InvalidQueryParam(
+                "page_token can't be combined with updated_since; pass after_key instead".to_string(),
+            ))
+        }
+        Some(_) if query.offset.is_some() => {
+            return Err(ApiError::InvalidQueryParam(
+                "page_token can't be combined with offset".to_string(),
+            ))
+        }
+        Some(token) => Some(page_token::decode(token, &sort, query.prefix.as_deref())?),
+        None => None,
+    };
+
     // Query the database
-    let result = state
+    let filter = ListFilter {
+        prefix: query.prefix.as_deref(),
+        match_mode: query.match_mode.unwrap_or_default(),
+        updated_since,
+        search: query.q.as_deref(),
+        fields: (!fields.is_empty()).then_some(fields),
+        include_deleted,
+        skip_count: !query.include_count.unwrap_or(true),
+        created_after,
+        created_before,
+        updated_after,
+        updated_before,
+        page_after,
+    };
+    // One row past the page tells whether another page follows
+    let mut result = state
         .spanner_client
-        .list_all(query.prefix.as_deref(), sort, limit, offset)
+        .list_all(&filter, &sort, Some(limit + 1), offset)
         .await?;
+    let has_more = result.entries.len() as i64 > limit;
+    result.entries.truncate(limit as usize);
+    let next_page_token = match result.entries.last() {
+        Some(last) if has_more && filter.updated_since.is_none() => {
+            Some(page_token::encode(&sort, query.prefix.as_deref(), &sort.cursor_after(last)))
+        }
+        _ => None,
+    };
+
+    // Where the next sync resumes: after the last row if more changes remain,
+    // otherwise at the snapshot, which includes every commit up to its timestamp
+    let (sync_timestamp, sync_after_key) = if filter.updated_since.is_none() {
+        (None, None)
+    } else {
+        match result.entries.last() {
+            Some(last) if has_more => {
+                (Some(format_sync_timestamp(last.updated_at)), Some(last.key.clone()))
+            }
+            _ => (Some(format_sync_timestamp(result.read_info.timestamp)), None),
+        }
+    };
+
+    let mut response_headers = if read_info_requested(&state.config, &headers) {
+        read_info_headers(Some(&result.read_info))
+    } else {
+        HeaderMap::new()
+    };
+    if !include_deleted {
+        response_headers.extend(read_cache_headers(&state.config));
+    }
 
     // Convert to response format with ISO 8601 timestamps
-    let data: Vec<KvEntryResponse> = result
-        .entries
-        .into_iter()
-        .map(|entry| KvEntryResponse {
-            key: entry.key,
-            value: entry.value,
-            created_at: entry.created_at.to_rfc3339(),
-            updated_at: entry.updated_at.to_rfc3339(),
-        })
-        .collect();
+    let data: Vec<KvEntryResponse> = result.entries.into_iter().map(KvEntryResponse::from).collect();
+
+    // Links repeat every filter of this request, so following one pages the same listing
+    let sort_name = sort.name();
+    let filters: Vec<(&str, &str)> = [
+        ("sort", Some(sort_name.as_str())),
+        ("prefix", query.prefix.as_deref()),
+        ("match", query.match_mode.map(MatchMode::name)),
+        ("q", query.q.as_deref()),
+        ("created_after", query.created_after.as_deref()),
+        ("created_before", query.created_before.as_deref()),
+        ("updated_after", query.updated_after.as_deref()),
+        ("updated_before", query.updated_before.as_deref()),
+        ("include_deleted", include_deleted.then_some("true")),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| value.map(|value| (name, value)))
+    .chain(pairs.iter().filter(|(key, _)| key == "where").map(|(_, raw)| ("where", raw.as_str())))
+    .collect();
+    let (next, prev) = if filter.updated_since.is_some() {
+        // Syncs resume from sync_timestamp and sync_after_key instead
+        (None, None)
+    } else if filter.page_after.is_some() {
+        // A token only leads forward
+        let next = next_page_token.as_deref().map(|token| page_link(limit, ("page_token", token), &filters));
+        (next, None)
+    } else {
+        let next = has_more.then(|| page_link(limit, ("offset", &(offset + limit).to_string()), &filters));
+        let prev = (offset > 0).then(|| page_link(limit, ("offset", &(offset - limit).max(0).to_string()), &filters));
+        (next, prev)
+    };
 
     let response = ListResponse {
         data,
         total_count: result.total_count,
+        has_more,
+        limit,
+        sync_timestamp,
+        sync_after_key,
+        next_page_token,
+        next,
+        prev,
     };
 
     tracing::info!(
-        "Listed {} entries (total: {}, prefix: {:?}, sort: {:?}, limit: {:?}, offset: {})",
+        "Listed {} entries (total: {:?}, prefix: {:?}, q: {:?}, sort: {}, limit: {}, offset: {})",
         response.data.len(),
         response.total_count,
         query.prefix,
-        sort,
+        query.q,
+        sort_name,
+        limit,
+        offset
+    );
+
+    Ok((StatusCode::OK, response_headers, Json(response)).into_response())
+}
+
+/// Whether to answer in CSV: `format` decides, else an `Accept` naming `text/csv`
+fn csv_requested(format: Option<&str>, headers: &HeaderMap) -> Result<bool, ApiError> {
+    match format {
+        Some("csv") => Ok(true),
+        Some("json") => Ok(false),
+        Some(other) => Err(ApiError::InvalidQueryParam(format!(
+            "format must be one of: json, csv, got '{}'",
+            other
+        ))),
+        None => Ok(headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|media| media.split(';').next().unwrap_or("").trim().eq_ignore_ascii_case("text/csv"))),
+    }
+}
+
+/// Stream one page of the listing as CSV
+async fn list_csv(state: &AppState, query: &ListQuery, sort: Sort) -> Result<Response, ApiError> {
+    let unsupported = query.updated_since.is_some()
+        || query.after_key.is_some()
+        || query.q.is_some()
+        || query.include_deleted.is_some()
+        || query.created_after.is_some()
+        || query.created_before.is_some()
+        || query.updated_after.is_some()
+        || query.updated_before.is_some()
+        || query.page_token.is_some()
+        || query.match_mode.is_some_and(|mode| mode != MatchMode::Prefix);
+    if unsupported {
+        return Err(ApiError::InvalidQueryParam(
+            "format=csv supports only prefix, sort, limit and offset".to_string(),
+        ));
+    }
+
+    let limit = page_limit(&state.config, query.limit)?;
+    let offset = i64::from(query.offset.unwrap_or(0));
+
+    let sort_name = sort.name();
+    let rows = state.spanner_client.list_stream(query.prefix.clone(), sort, Some(limit), offset);
+    let body = rows_body(rows, csv::record(&CSV_COLUMNS).into_bytes(), |entry| {
+        let value = serde_json::to_string(&entry.value)?;
+        let (created_at, updated_at) = (entry.created_at.to_rfc3339(), entry.updated_at.to_rfc3339());
+        Ok(csv::record(&[&entry.key, &created_at, &updated_at, &value]).into_bytes())
+    })
+    .await?;
+
+    tracing::info!(
+        "Streaming CSV listing (prefix: {:?}, sort: {}, limit: {}, offset: {})",
+        query.prefix,
+        sort_name,
         limit,
         offset
     );
 
-    Ok((StatusCode::OK, Json(response)))
+    let mut headers = read_cache_headers(&state.config);
+    headers.insert(header::CONTENT_TYPE, header::HeaderValue::from_static(CSV_CONTENT_TYPE));
+    Ok((headers, body).into_response())
+}
+
+/// The page size for a listing: `limit`, or `DEFAULT_LIMIT` without one
+///
+/// Every page is bounded, so a client can't pull the whole table in one request.
+/// A `limit` above `MAX_LIMIT` is an error rather than being clamped, so a caller
+/// expecting everything finds out instead of silently getting a partial page.
+fn page_limit(config: &Config, limit: Option<u32>) -> Result<i64, ApiError> {
+    match limit {
+        Some(limit) if limit > config.max_limit => Err(ApiError::InvalidQueryParam(format!(
+            "limit must be at most {}, got {}",
+            config.max_limit, limit
+        ))),
+        limit => Ok(i64::from(limit.unwrap_or(config.default_limit))),
+    }
+}
+
+/// Relative URL of another page of a listing, with each value percent-encoded
+fn page_link(limit: i64, position: (&str, &str), filters: &[(&str, &str)]) -> String {
+    let mut link = format!("{}?limit={}", routes::KV_LIST, limit);
+    for (name, value) in std::iter::once(&position).chain(filters) {
+        link.push_str(&format!("&{}={}", name, utf8_percent_encode(value, QUERY_VALUE)));
+    }
+    link
+}
+
+/// Parse an RFC 3339 timestamp query parameter
+fn parse_timestamp(name: &str, raw: &str) -> Result<DateTime<Utc>, ApiError> {
+    DateTime::parse_from_rfc3339(raw)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|_| ApiError::InvalidQueryParam(format!("{} must be an RFC 3339 timestamp, got '{}'", name, raw)))
+}
+
+/// Parse a `where` filter of the form `field:value`
+///
+/// The value is read as JSON when it is a string, number or boolean literal,
+/// and otherwise taken as a bare string, so `type:fruit` and `type:"fruit"` agree.
+fn parse_where(raw: &str) -> Result<(String, JsonValue), ApiError> {
+    let invalid = |reason: &str| {
+        ApiError::InvalidQueryParam(format!("where must be field:value, {} (got '{}')", reason, raw))
+    };
+    let (path, value) = raw.split_once(':').ok_or_else(|| invalid("missing ':'"))?;
+    if !is_valid_field_path(path) {
+        return Err(invalid("with field a dotted path of letters, digits and '_'"));
+    }
+
+    let value = match serde_json::from_str::<JsonValue>(value) {
+        Ok(value @ (JsonValue::String(_) | JsonValue::Number(_) | JsonValue::Bool(_))) => value,
+        Ok(_) => return Err(invalid("with value a string, number or boolean")),
+        Err(_) => JsonValue::String(value.to_string()),
+    };
+    Ok((path.to_string(), value))
+}
+
+/// Format a sync position with full precision so no commit is skipped on resume
+fn format_sync_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true)
 }
 
 #[cfg(test)]
@@ -99,9 +460,12 @@ mod tests {
     use crate::config::Config;
     use crate::error::ErrorResponse;
     use crate::handlers::{get_handler, put_handler};
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
     use crate::models::GetResponse;
     use crate::spanner::SpannerClient;
     use axum::{body::Body, http::Request, routing::get, routing::put, Router};
+    use serde_json::json;
     use std::sync::Arc;
     use tower::ServiceExt;
     use uuid::Uuid;
@@ -112,14 +476,7 @@ mod tests {
             std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
         }
 
-        let config = Config {
-            spanner_emulator_host: Some("localhost:9010".to_string()),
-            spanner_project: "test-project".to_string(),
-            spanner_instance: "put-endpoint-test".to_string(),
-            spanner_database: "put-endpoint-test-db".to_string(),
-            service_port: 3000,
-            service_host: "0.0.0.0".to_string(),
-        };
+        let config = Config::for_emulator("put-endpoint-test", "put-endpoint-test-db");
 
         let spanner_client = SpannerClient::from_config(&config)
             .await
@@ -127,7 +484,9 @@ mod tests {
 
         let state = AppState {
             spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
             config: Arc::new(config),
+            metrics: Metrics::new(),
         };
 
         Router::new()
@@ -159,8 +518,8 @@ mod tests {
         let response_json: ListResponse = serde_json::from_slice(&body).unwrap();
 
         // Should return a list with total_count (may have data from other tests)
-        assert!(response_json.data.len() <= response_json.total_count as usize);
-        assert!(response_json.total_count >= 0);
+        assert!(response_json.data.len() <= response_json.total_count.unwrap() as usize);
+        assert!(response_json.total_count.unwrap() >= 0);
 
         unsafe {
             std::env::remove_var("SPANNER_EMULATOR_HOST");
@@ -226,7 +585,7 @@ mod tests {
 
         // Should have at least our 2 documents
         assert!(response_json.data.len() >= 2);
-        assert!(response_json.total_count >= 2);
+        assert!(response_json.total_count.unwrap() >= 2);
 
         // Verify response format
         for entry in &response_json.data {
@@ -378,7 +737,7 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(put_response.status(), StatusCode::OK);
+        assert_eq!(put_response.status(), StatusCode::CREATED);
 
         // GET specific key should work
         let get_response = app
@@ -420,7 +779,7 @@ mod tests {
             .await
             .unwrap();
         let list_json: ListResponse = serde_json::from_slice(&body).unwrap();
-        assert!(list_json.data.len() >= 1);
+        assert!(!list_json.data.is_empty());
 
         unsafe {
             std::env::remove_var("SPANNER_EMULATOR_HOST");
@@ -436,14 +795,7 @@ mod tests {
             std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
         }
 
-        let config = Config {
-            spanner_emulator_host: Some("localhost:9010".to_string()),
-            spanner_project: "test-project".to_string(),
-            spanner_instance: "list-integration-test".to_string(),
-            spanner_database: "list-integration-test-db".to_string(),
-            service_port: 3000,
-            service_host: "0.0.0.0".to_string(),
-        };
+        let config = Config::for_emulator("list-integration-test", "list-integration-test-db");
 
         let spanner_client = SpannerClient::from_config(&config)
             .await
@@ -451,7 +803,9 @@ mod tests {
 
         let state = AppState {
             spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
             config: Arc::new(config),
+            metrics: Metrics::new(),
         };
 
         let app = Router::new()
@@ -518,7 +872,7 @@ mod tests {
         // Should return exactly 2 entries
         assert_eq!(response_json.data.len(), 2);
         // Total count should reflect all entries
-        assert!(response_json.total_count >= 4);
+        assert!(response_json.total_count.unwrap() >= 4);
 
         unsafe {
             std::env::remove_var("SPANNER_EMULATOR_HOST");
@@ -570,8 +924,11 @@ mod tests {
             .unwrap();
         let response_json: ListResponse = serde_json::from_slice(&body).unwrap();
 
-        // Should skip first entry
-        assert_eq!(response_json.data.len(), all_json.data.len() - 1);
+        // Should skip first entry; both are pages of at most DEFAULT_LIMIT
+        assert_eq!(
+            response_json.data.len() as i64,
+            (all_json.total_count.unwrap() - 1).min(response_json.limit)
+        );
         // First key should be the second key from all results
         assert_eq!(response_json.data[0].key, all_json.data[1].key);
 
@@ -924,7 +1281,7 @@ mod tests {
         // Total count should reflect filtered count
         assert_eq!(
             response_json.total_count,
-            response_json.data.len() as i64
+            Some(response_json.data.len() as i64)
         );
 
         unsafe {
@@ -1110,6 +1467,84 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_list_malformed_query_returns_json() {
+        let app = setup_test_app().await;
+
+        // Values serde can't parse get the same JSON 400 as other bad parameters
+        let response = app
+            .oneshot(Request::builder().uri("/kv?limit=ten").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.starts_with("Invalid query parameter: limit"), "{}", error_response.error);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_match_modes() {
+        let (app, ids) = setup_list_test_app().await;
+
+        let list = |uri: String| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, body)
+            }
+        };
+        let keys = |body: &[u8]| -> Vec<String> {
+            let response: ListResponse = serde_json::from_slice(body).unwrap();
+            response.data.into_iter().map(|entry| entry.key).collect()
+        };
+
+        // UUIDs are random, so each of these pieces belongs to one key only
+        let id = ids[0].to_string();
+        let (status, body) = list(format!("/kv?prefix={}&match=suffix", &id[24..])).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(keys(&body), vec![id.clone()]);
+
+        let (_, body) = list(format!("/kv?prefix={}&match=contains", &id[9..23])).await;
+        assert_eq!(keys(&body), vec![id.clone()]);
+
+        let (_, body) = list(format!("/kv?prefix={}&match=exact", id)).await;
+        assert_eq!(keys(&body), vec![id.clone()]);
+        let (_, body) = list(format!("/kv?prefix={}&match=exact", &id[..35])).await;
+        assert!(keys(&body).is_empty());
+
+        // Every v4 UUID contains "-4", and links keep the match mode
+        let (_, body) = list("/kv?prefix=-4&match=contains&limit=1".to_string()).await;
+        let response: ListResponse = serde_json::from_slice(&body).unwrap();
+        let next = response.next.unwrap();
+        assert!(next.contains("prefix=-4") && next.contains("match=contains"), "{}", next);
+
+        let (status, body) = list("/kv?prefix=a&match=regex".to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            error.error,
+            "Invalid query parameter: match must be one of: prefix, suffix, contains, exact, got 'regex'"
+        );
+
+        let (status, _) = list("/kv?prefix=a&match=suffix&format=csv".to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
     #[tokio::test]
     async fn test_list_integration_default_sort() {
         let (app, _ids) = setup_list_test_app().await;
@@ -1145,4 +1580,961 @@ mod tests {
             std::env::remove_var("SPANNER_EMULATOR_HOST");
         }
     }
+
+    #[tokio::test]
+    async fn test_list_integration_excludes_reserved_keys() {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config {
+            reserved_key_prefix: Some("00000000-".to_string()),
+            ..Config::for_emulator("reserved-key-test", "reserved-key-test-db")
+        };
+
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        // Internal rows are written directly, bypassing the public endpoints
+        let reserved_id = Uuid::parse_str("00000000-0000-4000-8000-000000000001").unwrap();
+        spanner_client
+            .upsert(reserved_id, serde_json::json!({"internal": true}))
+            .await
+            .unwrap();
+        let public_id = Uuid::new_v4();
+        spanner_client
+            .upsert(public_id, serde_json::json!({"internal": false}))
+            .await
+            .unwrap();
+
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+        let app = Router::new()
+            .route(crate::routes::KV_LIST, get(list_handler))
+            .route(crate::routes::KV_ITEM, put(put_handler).get(get_handler))
+            .with_state(state);
+
+        for uri in ["/kv", "/kv?prefix=0000"] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let response_json: ListResponse = serde_json::from_slice(&body).unwrap();
+            assert!(
+                response_json.data.iter().all(|entry| !entry.key.starts_with("00000000-")),
+                "Reserved keys must not be listed by {}",
+                uri
+            );
+        }
+
+        // The public endpoints refuse to touch reserved keys
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv/{}", reserved_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", reserved_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"overwritten": true}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv/{}", public_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_integration_read_info_headers() {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        // DEBUG_READ_INFO enables the headers without a request header
+        let config = Config {
+            debug_read_info: true,
+            ..Config::for_emulator("list-integration-test", "list-integration-test-db")
+        };
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+        let app = Router::new()
+            .route(crate::routes::KV_LIST, get(list_handler))
+            .with_state(state);
+
+        let response = app
+            .oneshot(Request::builder().uri("/kv?limit=1").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let timestamp = response.headers()["x-read-timestamp"].to_str().unwrap();
+        assert!(
+            chrono::DateTime::parse_from_rfc3339(timestamp).is_ok(),
+            "X-Read-Timestamp should be RFC 3339, got {}",
+            timestamp
+        );
+        assert_eq!(response.headers()["x-read-mode"], "strong");
+
+        // Without the config or request header, no read info is returned
+        let (app, _ids) = setup_list_test_app().await;
+        let response = app
+            .oneshot(Request::builder().uri("/kv?limit=1").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get("x-read-timestamp").is_none());
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    async fn list_json(app: &Router, uri: &str) -> ListResponse {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "GET {}", uri);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn sync_uri(prefix: &str, response: &ListResponse, limit: u32) -> String {
+        let mut uri = format!(
+            "/kv?prefix={}&limit={}&updated_since={}",
+            prefix,
+            limit,
+            response.sync_timestamp.as_deref().unwrap().replace('+', "%2B")
+        );
+        if let Some(after_key) = &response.sync_after_key {
+            uri.push_str(&format!("&after_key={}", after_key));
+        }
+        uri
+    }
+
+    #[tokio::test]
+    async fn test_list_incremental_sync() {
+        let app = setup_test_app().await;
+
+        let prefix = &Uuid::new_v4().to_string()[..8];
+        let ids: Vec<String> = (1..=3)
+            .map(|i| format!("{}-0000-4000-8000-00000000000{}", prefix, i))
+            .collect();
+        for id in &ids {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("PUT")
+                        .uri(format!("/kv/{}", id))
+                        .header("content-type", "application/json")
+                        .body(Body::from(r#"{"version": 1}"#))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+
+        // Initial sync in pages of two
+        let first = list_json(
+            &app,
+            &format!("/kv?prefix={}&limit=2&updated_since=1970-01-01T00:00:00Z", prefix),
+        )
+        .await;
+        assert_eq!(first.data.len(), 2);
+        assert!(first.sync_after_key.is_some(), "More changes remain");
+
+        let second = list_json(&app, &sync_uri(prefix, &first, 2)).await;
+        assert_eq!(second.data.len(), 1);
+        assert_eq!(second.sync_after_key, None, "Caught up");
+
+        let mut synced: Vec<String> = first.data.iter().chain(&second.data).map(|e| e.key.clone()).collect();
+        synced.sort();
+        assert_eq!(synced, ids);
+
+        // Nothing changed since
+        let idle = list_json(&app, &sync_uri(prefix, &second, 2)).await;
+        assert!(idle.data.is_empty());
+
+        // An update shows up in the next sync, and only it
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", ids[1]))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"version": 2}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let changed = list_json(&app, &sync_uri(prefix, &idle, 2)).await;
+        assert_eq!(changed.data.len(), 1);
+        assert_eq!(changed.data[0].key, ids[1]);
+        assert_eq!(changed.data[0].value["version"], 2);
+
+        // Plain listings carry no sync position
+        let plain = list_json(&app, &format!("/kv?prefix={}", prefix)).await;
+        assert_eq!(plain.sync_timestamp, None);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_incremental_sync_invalid_params() {
+        let app = setup_test_app().await;
+
+        for uri in [
+            "/kv?updated_since=yesterday",
+            "/kv?after_key=abc",
+            "/kv?updated_since=2024-01-01T00:00:00Z&sort=key_asc",
+            "/kv?q=",
+            &format!("/kv?q={}", "a".repeat(MAX_SEARCH_LEN + 1)),
+            "/kv?q=apple",
+            "/kv?q=apple&limit=101",
+            "/kv?where=type",
+            "/kv?where=type:null",
+            &format!("/kv?{}", "where=type:fruit&".repeat(MAX_WHERE_FILTERS + 1)),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "GET {}", uri);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_csv() {
+        let app = setup_test_app().await;
+
+        // Keys sharing a fresh 8-character prefix, so only this test's rows match
+        let prefix = Uuid::new_v4().simple().to_string()[..8].to_string();
+        let mut keys: Vec<String> = (0..5)
+            .map(|_| format!("{}{}", prefix, &Uuid::new_v4().to_string()[8..]))
+            .collect();
+        keys.sort();
+        for (i, key) in keys.iter().enumerate() {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("PUT")
+                        .uri(format!("/kv/{}", key))
+                        .header("content-type", "application/json")
+                        .body(Body::from(json!({"i": i, "note": "a, \"quoted\"\nvalue"}).to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+
+        let get_csv = |uri: String, accept: Option<&'static str>| {
+            let app = app.clone();
+            async move {
+                let mut request = Request::builder().uri(uri);
+                if let Some(accept) = accept {
+                    request = request.header("accept", accept);
+                }
+                let response = app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                assert_eq!(response.headers()["content-type"], CSV_CONTENT_TYPE);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
+        };
+
+        let body = get_csv(format!("/kv?prefix={}&format=csv", prefix), None).await;
+        let lines: Vec<&str> = body.split_terminator("\r\n").collect();
+        assert_eq!(lines[0], "key,created_at,updated_at,value_json");
+        assert_eq!(lines.len(), 1 + keys.len());
+        for (i, (line, key)) in lines[1..].iter().zip(&keys).enumerate() {
+            assert!(line.starts_with(&format!("{},", key)), "{}", line);
+            // The JSON's commas and quotes are escaped inside one quoted field
+            let value_json = format!(r#""{{""i"":{},""note"":""a, \""quoted\""\nvalue""}}""#, i);
+            assert!(line.ends_with(&format!(",{}", value_json)), "{}", line);
+        }
+
+        // Accept: text/csv also selects CSV; sort, limit and offset still apply
+        let body = get_csv(format!("/kv?prefix={}&sort=key_desc&limit=2&offset=1", prefix), Some("text/csv")).await;
+        let lines: Vec<&str> = body.split_terminator("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with(&keys[3]));
+        assert!(lines[2].starts_with(&keys[2]));
+
+        // An empty listing is just the header row
+        let body = get_csv("/kv?prefix=zzzz-no-such-prefix&format=csv".to_string(), None).await;
+        assert_eq!(body, "key,created_at,updated_at,value_json\r\n");
+
+        // format wins over Accept, and CSV rejects what it can't express
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/kv?limit=1&format=json")
+                    .header("accept", "text/csv")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        for uri in ["/kv?format=xml", "/kv?format=csv&q=apple&limit=10", "/kv?format=csv&updated_since=2024-01-01T00:00:00Z"] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "GET {}", uri);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[test]
+    fn test_parse_where() {
+        assert_eq!(parse_where("type:fruit").unwrap(), ("type".to_string(), json!("fruit")));
+        assert_eq!(parse_where("type:\"fruit\"").unwrap(), ("type".to_string(), json!("fruit")));
+        assert_eq!(parse_where("count:3").unwrap(), ("count".to_string(), json!(3)));
+        assert_eq!(parse_where("count:\"3\"").unwrap(), ("count".to_string(), json!("3")));
+        assert_eq!(parse_where("price:2.5").unwrap(), ("price".to_string(), json!(2.5)));
+        assert_eq!(parse_where("ripe:true").unwrap(), ("ripe".to_string(), json!(true)));
+        assert_eq!(parse_where("origin.country:NZ").unwrap(), ("origin.country".to_string(), json!("NZ")));
+        assert_eq!(parse_where("time:12:30").unwrap(), ("time".to_string(), json!("12:30")));
+        assert_eq!(parse_where("name:").unwrap(), ("name".to_string(), json!("")));
+
+        for raw in ["type", ":fruit", "$.type:fruit", "a..b:1", "type':x", "tags:[1]", "meta:{}", "type:null"] {
+            assert!(parse_where(raw).is_err(), "where={} should be rejected", raw);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_integration_where_filters() {
+        // Earlier runs leave more fixtures behind than fit in a page, so only
+        // look at documents written by this run
+        let since = format_sync_timestamp(Utc::now() - chrono::Duration::seconds(1));
+        let (app, ids) = setup_list_test_app().await;
+        let keys = |response: &ListResponse| -> Vec<String> {
+            response.data.iter().map(|entry| entry.key.clone()).collect()
+        };
+
+        // ids are apple, banana, carrot and date
+        let fruit = list_json(&app, &format!("/kv?where=type:fruit&updated_since={}", since)).await;
+        assert!(fruit.data.iter().all(|entry| entry.value["type"] == "fruit"));
+        assert_eq!(fruit.total_count, Some(fruit.data.len() as i64));
+        for (i, id) in ids.iter().enumerate() {
+            assert_eq!(keys(&fruit).contains(&id.to_string()), i != 2, "fixture {}", i);
+        }
+
+        // Repeated filters must all match
+        let red_fruit = list_json(&app, &format!("/kv?where=type:fruit&where=color:red&updated_since={}", since)).await;
+        assert!(red_fruit.data.iter().all(|entry| entry.value["type"] == "fruit" && entry.value["color"] == "red"));
+        assert!(keys(&red_fruit).contains(&ids[0].to_string()));
+        assert!(!keys(&red_fruit).contains(&ids[1].to_string()));
+
+        let vegetables = list_json(&app, &format!("/kv?where=type:vegetable&where=color:orange&updated_since={}", since)).await;
+        assert!(keys(&vegetables).contains(&ids[2].to_string()));
+
+        // Numbers, strings and booleans only match their own JSON type
+        let batch = Uuid::new_v4().to_string();
+        let typed = [
+            json!({"batch": batch, "count": 3, "ripe": true}),
+            json!({"batch": batch, "count": "3", "ripe": "true"}),
+            json!({"batch": batch, "count": 3.0, "origin": {"country": "NZ"}}),
+        ];
+        let mut typed_ids = Vec::new();
+        for data in &typed {
+            let id = Uuid::new_v4();
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("PUT")
+                        .uri(format!("/kv/{}", id))
+                        .body(Body::from(data.to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+            typed_ids.push(id.to_string());
+        }
+
+        let cases = [
+            ("count:3", vec![0, 2]),
+            ("count:3.0", vec![0, 2]),
+            ("count:%223%22", vec![1]),
+            ("ripe:true", vec![0]),
+            ("ripe:%22true%22", vec![1]),
+            ("ripe:false", vec![]),
+            ("origin.country:NZ", vec![2]),
+        ];
+        for (filter, expected) in cases {
+            let response = list_json(&app, &format!("/kv?where=batch:{}&where={}&sort=key_asc", batch, filter)).await;
+            let mut expected: Vec<String> = expected.into_iter().map(|i| typed_ids[i].clone()).collect();
+            expected.sort();
+            assert_eq!(keys(&response), expected, "where={}", filter);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_integration_search() {
+        let (app, _) = setup_list_test_app().await;
+        let keys = |response: &ListResponse| -> Vec<String> {
+            response.data.iter().map(|entry| entry.key.clone()).collect()
+        };
+
+        // A marker unique to this run keeps earlier runs' rows out of the results
+        let marker = Uuid::new_v4().simple().to_string();
+        let documents = [
+            json!({"profile": {"address": {"city": format!("Wellington {}", marker)}}}),
+            json!({"tags": ["red", format!("Blue-{}", marker.to_uppercase())]}),
+            json!({"orders": [{"items": [{"sku": format!("sku-{}", marker)}]}]}),
+            json!({"note": "no marker here"}),
+        ];
+        let mut ids = Vec::new();
+        for data in &documents {
+            let id = Uuid::new_v4();
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("PUT")
+                        .uri(format!("/kv/{}", id))
+                        .body(Body::from(data.to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+            ids.push(id.to_string());
+        }
+        let mut matching = ids[..3].to_vec();
+        matching.sort();
+
+        // Nested objects, arrays and arrays of objects all match, ignoring case
+        let all = list_json(&app, &format!("/kv?q={}&limit=10", marker.to_uppercase())).await;
+        assert_eq!(keys(&all), matching);
+        assert_eq!(all.total_count, Some(3));
+
+        // Combined with sort, limit and offset; total_count still counts every match
+        let page = list_json(&app, &format!("/kv?q={}&sort=key_desc&limit=1&offset=1", marker)).await;
+        assert_eq!(keys(&page), vec![matching[1].clone()]);
+        assert_eq!(page.total_count, Some(3));
+
+        // Combined with a prefix
+        let prefix = &ids[1][..13];
+        let prefixed = list_json(&app, &format!("/kv?q={}&prefix={}&limit=10", marker, prefix)).await;
+        assert_eq!(keys(&prefixed), vec![ids[1].clone()]);
+        assert_eq!(prefixed.total_count, Some(1));
+
+        // Keys aren't part of the document, so they don't match
+        let by_key = list_json(&app, &format!("/kv?q={}&limit=10", ids[3])).await;
+        assert_eq!(by_key.total_count, Some(0));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_integration_time_range() {
+        let (app, _) = setup_list_test_app().await;
+        let marker = Uuid::new_v4().simple().to_string();
+        let put = |id: Uuid, version: u32| {
+            let app = app.clone();
+            let body = json!({"marker": marker, "version": version}).to_string();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("PUT")
+                            .uri(format!("/kv/{}", id))
+                            .body(Body::from(body))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                let expected = if version == 1 { StatusCode::CREATED } else { StatusCode::OK };
+                assert_eq!(response.status(), expected);
+            }
+        };
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        for id in &ids {
+            put(*id, 1).await;
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+
+        // The marker keeps rows from other runs and tests out of the results
+        let list = |range: String| {
+            let uri = format!("/kv?q={}&limit=10&sort=created_asc&{}", marker, range.replace('+', "%2B"));
+            let app = app.clone();
+            async move { list_json(&app, &uri).await }
+        };
+        let keys = |response: &ListResponse| -> Vec<String> {
+            response.data.iter().map(|entry| entry.key.clone()).collect()
+        };
+        let all = list(String::new()).await;
+        assert_eq!(keys(&all), ids.iter().map(Uuid::to_string).collect::<Vec<_>>());
+        let created: Vec<String> = all.data.iter().map(|entry| entry.created_at.clone()).collect();
+
+        // `_after` is inclusive and `_before` exclusive
+        let from_second = list(format!("created_after={}", created[1])).await;
+        assert_eq!(keys(&from_second), keys(&all)[1..]);
+        assert_eq!(from_second.total_count, Some(2));
+        let before_second = list(format!("created_before={}", created[1])).await;
+        assert_eq!(keys(&before_second), keys(&all)[..1]);
+        assert_eq!(before_second.total_count, Some(1));
+        let window = list(format!("created_after={}&created_before={}", created[1], created[2])).await;
+        assert_eq!(keys(&window), keys(&all)[1..2]);
+
+        // Rewriting the first row moves its updated_at past the others
+        put(ids[0], 2).await;
+        let rewritten = list(String::new()).await;
+        let first = ids[0].to_string();
+        let updated = &rewritten.data.iter().find(|entry| entry.key == first).unwrap().updated_at;
+        let recent = list(format!("updated_after={}", updated)).await;
+        assert_eq!(keys(&recent), vec![first]);
+        let older = list(format!("updated_before={}", updated)).await;
+        assert_eq!(keys(&older), keys(&all)[1..]);
+        assert_eq!(older.total_count, Some(2));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_invalid_time_range() {
+        let app = setup_test_app().await;
+
+        for param in ["created_after", "created_before", "updated_after", "updated_before"] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(format!("/kv?{}=last-tuesday", param)).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+            assert!(error.error.contains(param), "{}", error.error);
+            assert!(error.error.contains("'last-tuesday'"), "{}", error.error);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_integration_page_token() {
+        let (app, _) = setup_list_test_app().await;
+        let batch = Uuid::new_v4().simple().to_string();
+        let mut ids = Vec::new();
+        for i in 0..50 {
+            let id = Uuid::new_v4();
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("PUT")
+                        .uri(format!("/kv/{}", id))
+                        .body(Body::from(json!({"batch": batch, "i": i}).to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+            ids.push(id.to_string());
+        }
+
+        for sort in ["key_asc", "key_desc", "created_asc", "updated_desc"] {
+            let mut seen = Vec::new();
+            let mut token: Option<String> = None;
+            loop {
+                let mut uri = format!("/kv?where=batch:{}&sort={}&limit=7", batch, sort);
+                if let Some(token) = &token {
+                    uri.push_str(&format!("&page_token={}", token));
+                }
+                let page = list_json(&app, &uri).await;
+                assert_eq!(page.total_count, Some(50));
+                assert!(page.data.len() <= 7);
+                seen.extend(page.data.iter().map(|entry| entry.key.clone()));
+                match page.next_page_token {
+                    Some(next) => token = Some(next),
+                    None => break,
+                }
+            }
+
+            // Every row exactly once, in the requested order
+            let mut expected = ids.clone();
+            match sort {
+                "key_asc" => expected.sort(),
+                "key_desc" => expected.sort_by(|a, b| b.cmp(a)),
+                "updated_desc" => expected.reverse(),
+                _ => {}
+            }
+            assert_eq!(seen, expected, "sort={}", sort);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_multi_column_sort_pages_through_tied_timestamps() {
+        let (app, _) = setup_list_test_app().await;
+        let client = SpannerClient::from_config(&Config::for_emulator("list-integration-test", "list-integration-test-db"))
+            .await
+            .expect("Failed to create Spanner client");
+
+        // One batch commits together, so every row shares created_at and updated_at
+        let base = Uuid::new_v4().to_string();
+        let prefix = &base[..24];
+        let ids: Vec<Uuid> = (0..9)
+            .map(|n| Uuid::parse_str(&format!("{}{:012x}", prefix, n)).unwrap())
+            .collect();
+        let written = client
+            .upsert_batch(ids.iter().map(|id| (*id, json!({"tied": true}))).collect())
+            .await;
+        assert_eq!(written.written, 9);
+
+        let keys: Vec<String> = ids.iter().map(Uuid::to_string).collect();
+        let mut reversed = keys.clone();
+        reversed.reverse();
+        for (sort, expected) in [
+            ("updated_desc,key_asc", &keys),
+            ("created_asc,updated_desc", &reversed),
+            ("updated_desc,created_asc", &keys),
+            ("created_desc", &reversed),
+        ] {
+            let mut seen = Vec::new();
+            let mut token: Option<String> = None;
+            loop {
+                let mut uri = format!("/kv?prefix={}&sort={}&limit=2", prefix, sort);
+                if let Some(token) = &token {
+                    uri.push_str(&format!("&page_token={}", token));
+                }
+                let page = list_json(&app, &uri).await;
+                seen.extend(page.data.iter().map(|entry| entry.key.clone()));
+                match page.next_page_token {
+                    Some(next) => token = Some(next),
+                    None => break,
+                }
+            }
+            // Ties fall back to the key, so pages neither repeat nor skip a row
+            assert_eq!(&seen, expected, "sort={}", sort);
+        }
+
+        for sort in ["key_asc,key_desc", "updated_asc,updated_desc", "key_asc,created_desc", "created_asc,"] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(format!("/kv?sort={}", sort)).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "sort={}", sort);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_page_token_rejected_for_other_listings() {
+        let (app, _) = setup_list_test_app().await;
+        let page = list_json(&app, "/kv?sort=key_asc&limit=1").await;
+        let token = page.next_page_token.expect("fixtures fill more than one page");
+
+        for uri in [
+            format!("/kv?sort=key_desc&limit=1&page_token={}", token),
+            format!("/kv?prefix=a&limit=1&page_token={}", token),
+            format!("/kv?limit=1&offset=1&page_token={}", token),
+            format!("/kv?updated_since=2024-01-01T00:00:00Z&page_token={}", token),
+            "/kv?page_token=garbage".to_string(),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "GET {}", uri);
+        }
+
+        // The same sort, spelled by default, accepts it
+        let next = list_json(&app, &format!("/kv?limit=1&page_token={}", token)).await;
+        assert!(next.data[0].key > page.data[0].key);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[test]
+    fn test_page_link_encoding() {
+        assert_eq!(
+            page_link(10, ("offset", "20"), &[("sort", "key_asc"), ("prefix", "user:a b&c=d/é~x_y.z-")]),
+            "/kv?limit=10&offset=20&sort=key_asc&prefix=user%3Aa%20b%26c%3Dd%2F%C3%A9~x_y.z-"
+        );
+        assert_eq!(
+            page_link(5, ("page_token", "abc-_"), &[("sort", "created_desc"), ("where", "name:\"a+b\"")]),
+            "/kv?limit=5&page_token=abc-_&sort=created_desc&where=name%3A%22a%2Bb%22"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_integration_page_links() {
+        let (app, _) = setup_list_test_app().await;
+        let batch = Uuid::new_v4().simple().to_string();
+        for i in 0..5 {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("PUT")
+                        .uri(format!("/kv/{}", Uuid::new_v4()))
+                        .body(Body::from(json!({"batch": batch, "i": i}).to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+        let link = |offset: u32| format!("/kv?limit=2&offset={}&sort=key_desc&where=batch%3A{}", offset, batch);
+
+        let first = list_json(&app, &format!("/kv?limit=2&sort=key_desc&where=batch:{}", batch)).await;
+        assert_eq!(first.next.as_deref(), Some(link(2).as_str()));
+        assert_eq!(first.prev, None);
+
+        // Links lead to the pages they name
+        let middle = list_json(&app, first.next.as_deref().unwrap()).await;
+        assert_eq!(middle.next.as_deref(), Some(link(4).as_str()));
+        assert_eq!(middle.prev.as_deref(), Some(link(0).as_str()));
+
+        let last = list_json(&app, middle.next.as_deref().unwrap()).await;
+        assert_eq!(last.data.len(), 1);
+        assert_eq!(last.next, None);
+        assert_eq!(last.prev.as_deref(), Some(link(2).as_str()));
+
+        // An offset that isn't a multiple of the limit steps back to the start, not past it
+        let shifted = list_json(&app, &format!("/kv?limit=2&offset=1&sort=key_desc&where=batch:{}", batch)).await;
+        assert_eq!(shifted.prev.as_deref(), Some(link(0).as_str()));
+        assert_eq!(shifted.next.as_deref(), Some(link(3).as_str()));
+
+        // After a page token, next carries the following token
+        let token = first.next_page_token.unwrap();
+        let by_token = list_json(&app, &format!("/kv?limit=2&sort=key_desc&where=batch:{}&page_token={}", batch, token)).await;
+        assert_eq!(
+            by_token.next.as_deref(),
+            Some(format!(
+                "/kv?limit=2&page_token={}&sort=key_desc&where=batch%3A{}",
+                by_token.next_page_token.as_deref().unwrap(),
+                batch
+            ).as_str())
+        );
+        assert_eq!(by_token.prev, None);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_integration_include_count() {
+        let (app, _) = setup_list_test_app().await;
+        let batch = Uuid::new_v4().simple().to_string();
+        for i in 0..3 {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("PUT")
+                        .uri(format!("/kv/{}", Uuid::new_v4()))
+                        .body(Body::from(json!({"batch": batch, "i": i}).to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+        let raw = |uri: String| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK, "GET {}", uri);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<JsonValue>(&body).unwrap()
+            }
+        };
+
+        // Counting is the default
+        let counted = raw(format!("/kv?where=batch:{}&limit=2", batch)).await;
+        assert_eq!(counted["total_count"], 3);
+        assert_eq!(counted["has_more"], true);
+
+        // Without the count, has_more still says whether to page
+        let first = raw(format!("/kv?where=batch:{}&limit=2&include_count=false", batch)).await;
+        assert!(first.get("total_count").is_none(), "{}", first);
+        assert_eq!(first["data"].as_array().unwrap().len(), 2);
+        assert_eq!(first["has_more"], true);
+        assert_eq!(
+            first["next"],
+            format!("/kv?limit=2&offset=2&sort=key_asc&where=batch%3A{}", batch)
+        );
+
+        let last = raw(format!("/kv?where=batch:{}&limit=2&offset=2&include_count=false", batch)).await;
+        assert!(last.get("total_count").is_none(), "{}", last);
+        assert_eq!(last["data"].as_array().unwrap().len(), 1);
+        assert_eq!(last["has_more"], false);
+        assert!(last.get("next").is_none());
+
+        // A page that exactly fills the limit isn't mistaken for having more
+        let exact = raw(format!("/kv?where=batch:{}&limit=3&include_count=false", batch)).await;
+        assert_eq!(exact["has_more"], false);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_applies_default_and_rejects_over_max_limit() {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config {
+            default_limit: 2,
+            max_limit: 3,
+            ..Config::for_emulator("list-integration-test", "list-integration-test-db")
+        };
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        // Four documents under a prefix no other test uses
+        let base = Uuid::new_v4().to_string();
+        let prefix = &base[..24];
+        for n in 0..4 {
+            let id = Uuid::parse_str(&format!("{}{:012x}", prefix, n)).unwrap();
+            spanner_client.upsert(id, json!({"n": n})).await.unwrap();
+        }
+
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+        let app = Router::new()
+            .route(crate::routes::KV_LIST, get(list_handler))
+            .with_state(state);
+
+        // No limit: DEFAULT_LIMIT applies
+        let response = list_json(&app, &format!("/kv?prefix={}", prefix)).await;
+        assert_eq!(response.data.len(), 2);
+        assert_eq!(response.limit, 2);
+        assert_eq!(response.total_count, Some(4));
+
+        // Within the cap: applied as given, up to MAX_LIMIT itself
+        let response = list_json(&app, &format!("/kv?prefix={}&limit=1", prefix)).await;
+        assert_eq!(response.data.len(), 1);
+        assert_eq!(response.limit, 1);
+        let response = list_json(&app, &format!("/kv?prefix={}&limit=3", prefix)).await;
+        assert_eq!(response.data.len(), 3);
+        assert_eq!(response.limit, 3);
+
+        // Over the cap: rejected rather than clamped, for JSON and CSV alike
+        for uri in [
+            format!("/kv?prefix={}&limit=4", prefix),
+            "/kv?limit=4294967295".to_string(),
+            format!("/kv?prefix={}&limit=4&format=csv", prefix),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+            assert!(error_response.error.contains("limit must be at most 3"), "{}", error_response.error);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
 }
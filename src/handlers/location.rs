@@ -0,0 +1,36 @@
+use crate::routes;
+use axum::http::{header, HeaderMap, HeaderValue};
+
+/// Path of the document stored under `id`
+pub fn item_path(id: &str) -> String {
+    routes::KV_ITEM.replace("{id}", id)
+}
+
+/// `headers` plus a `Location` pointing at the document stored under `id`
+///
+/// Sent with 201 Created; keys are validated before this, so they are always
+/// safe to use as a path segment.
+pub fn with_location(mut headers: HeaderMap, id: &str) -> HeaderMap {
+    if let Ok(value) = HeaderValue::from_str(&item_path(id)) {
+        headers.insert(header::LOCATION, value);
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_location() {
+        let id = "0b5a4c3e-8f2d-4a6b-9c1e-2d3f4a5b6c7d";
+        let headers = with_location(HeaderMap::new(), id);
+        assert_eq!(headers[header::LOCATION], "/kv/0b5a4c3e-8f2d-4a6b-9c1e-2d3f4a5b6c7d");
+
+        let mut cached = HeaderMap::new();
+        cached.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        let headers = with_location(cached, "user:1234");
+        assert_eq!(headers[header::LOCATION], "/kv/user:1234");
+        assert_eq!(headers[header::CACHE_CONTROL], "no-store");
+    }
+}
@@ -0,0 +1,139 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::key::parse_key;
+use crate::models::KvMetaResponse;
+use crate::routes;
+use crate::state::AppState;
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+
+/// GET /kv/:id/meta handler - Fetch a document's timestamps and size
+///
+/// Only the timestamps and the byte length of the stored JSON are read, so
+/// this costs the same for a 2 MB document as for an empty one.
+#[utoipa::path(
+    get,
+    path = routes::KV_META,
+    params(
+        ("id" = String, Path, description = "Key of the document: a UUID, or any valid key in KEY_MODE=string")
+    ),
+    responses(
+        (status = 200, description = "Document metadata", body = KvMetaResponse),
+        (status = 400, description = "Invalid UUID format, or invalid key in KEY_MODE=string", body = ErrorResponse),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn meta_handler(
+    State(state): State<AppState>,
+    Path(id_str): Path<String>,
+) -> Result<(StatusCode, Json<KvMetaResponse>), ApiError> {
+    let id = parse_key(&state.config, &id_str)?;
+
+    let meta = state
+        .spanner_client
+        .read_meta(&id)
+        .await?
+        .ok_or_else(|| ApiError::KeyNotFound(id.clone()))?;
+
+    Ok((
+        StatusCode::OK,
+        Json(KvMetaResponse {
+            id,
+            created_at: meta.created_at.to_rfc3339(),
+            updated_at: meta.updated_at.to_rfc3339(),
+            size_bytes: meta.size_bytes,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::handlers::put_handler;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::get, routing::put, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> Router {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("put-endpoint-test", "put-endpoint-test-db");
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        Router::new()
+            .route(routes::KV_ITEM, put(put_handler))
+            .route(routes::KV_META, get(meta_handler))
+            .with_state(state)
+    }
+
+    async fn send(app: &Router, method: &str, uri: &str, body: &str) -> (StatusCode, axum::body::Bytes) {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        (status, axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_meta_returns_timestamps_and_size() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+        let data = serde_json::json!({"name": "meta", "tags": ["a", "é"]});
+
+        let (status, _) = send(&app, "PUT", &format!("/kv/{}", id), &data.to_string()).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (status, body) = send(&app, "GET", &format!("/kv/{}/meta", id), "").await;
+        assert_eq!(status, StatusCode::OK);
+        let meta: KvMetaResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(meta.id, id.to_string());
+        assert_eq!(meta.size_bytes, data.to_string().len() as i64, "size is in bytes, not characters");
+        let created_at = chrono::DateTime::parse_from_rfc3339(&meta.created_at).unwrap();
+        let updated_at = chrono::DateTime::parse_from_rfc3339(&meta.updated_at).unwrap();
+        assert!(updated_at >= created_at);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_meta_missing_or_invalid_key() {
+        let app = setup_test_app().await;
+
+        let (status, _) = send(&app, "GET", &format!("/kv/{}/meta", Uuid::new_v4()), "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send(&app, "GET", "/kv/not-a-uuid/meta", "").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use crate::routes;
+use crate::state::AppState;
+use axum::{extract::State, http::header, http::HeaderMap, http::HeaderValue};
+
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// GET /metrics handler - Prometheus metrics
+///
+/// Renders request counts per handler and outcome (`kv_requests_total`),
+/// Spanner call latency per operation (`kv_spanner_call_duration_seconds`),
+/// reads that joined one already in flight (`kv_reads_coalesced_total`) and
+/// retained background jobs per state (`kv_jobs`, read from the job registry
+/// at scrape time) in the Prometheus text exposition format.
+/// Unauthenticated, like `/health`.
+#[utoipa::path(
+    get,
+    path = routes::METRICS,
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text exposition format", body = String, content_type = "text/plain")
+    ),
+    tag = "health"
+)]
+pub async fn metrics_handler(State(state): State<AppState>) -> (HeaderMap, String) {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(PROMETHEUS_CONTENT_TYPE));
+    state.metrics.record_job_counts(&state.jobs.counts());
+    (headers, state.metrics.render())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::handlers::{get_handler, put_handler};
+    use crate::jobs::JobRegistry;
+    use crate::metrics::{track_requests, Metrics};
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, http::StatusCode, middleware, routing::get, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> Router {
+        app(setup_test_state().await)
+    }
+
+    async fn setup_test_state() -> AppState {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("put-endpoint-test", "put-endpoint-test-db");
+        let metrics = Metrics::new();
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client")
+            .with_metrics(metrics.clone());
+
+        AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics,
+        }
+    }
+
+    fn app(state: AppState) -> Router {
+        Router::new()
+            .route(routes::METRICS, get(metrics_handler))
+            .route(routes::KV_ITEM, get(get_handler).put(put_handler))
+            .layer(middleware::from_fn_with_state(state.clone(), track_requests))
+            .with_state(state)
+    }
+
+    async fn send(app: &Router, method: &str, uri: &str, body: &str) -> axum::response::Response {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    async fn scrape(app: &Router) -> String {
+        let response = send(app, "GET", "/metrics", "").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/plain"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_requests_and_spanner_calls() {
+        let app = setup_test_app().await;
+        let uri = format!("/kv/{}", Uuid::new_v4());
+
+        assert_eq!(send(&app, "PUT", &uri, r#"{"n": 1}"#).await.status(), StatusCode::CREATED);
+        assert_eq!(send(&app, "GET", &uri, "").await.status(), StatusCode::OK);
+        assert_eq!(send(&app, "GET", &uri, "").await.status(), StatusCode::OK);
+        assert_eq!(send(&app, "GET", "/kv/not-a-uuid", "").await.status(), StatusCode::BAD_REQUEST);
+
+        let body = scrape(&app).await;
+        for expected in [
+            r#"kv_requests_total{handler="PUT /kv/{id}",outcome="success"} 1"#,
+            r#"kv_requests_total{handler="GET /kv/{id}",outcome="success"} 2"#,
+            r#"kv_requests_total{handler="GET /kv/{id}",outcome="client_error"} 1"#,
+            r#"kv_spanner_call_duration_seconds_count{op="upsert"} 1"#,
+            r#"kv_spanner_call_duration_seconds_count{op="read"}"#,
+        ] {
+            assert!(body.contains(expected), "Missing '{}' in:\n{}", expected, body);
+        }
+
+        // The counter keeps incrementing, and scrapes are counted too
+        assert_eq!(send(&app, "GET", &uri, "").await.status(), StatusCode::OK);
+        let body = scrape(&app).await;
+        assert!(body.contains(r#"kv_requests_total{handler="GET /kv/{id}",outcome="success"} 3"#), "{}", body);
+        assert!(body.contains(r#"kv_requests_total{handler="GET /metrics",outcome="success"} 1"#), "{}", body);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    /// The value of the sample named exactly `series`, or 0 if it hasn't been recorded
+    fn sample(body: &str, series: &str) -> u64 {
+        body.lines()
+            .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+            .map_or(0, |value| value.parse().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_simultaneous_gets_share_spanner_reads() {
+        let app = setup_test_app().await;
+        let uri = format!("/kv/{}", Uuid::new_v4());
+        assert_eq!(send(&app, "PUT", &uri, r#"{"hot": true}"#).await.status(), StatusCode::CREATED);
+
+        let gets = (0..50).map(|_| send(&app, "GET", &uri, ""));
+        for response in futures_util::future::join_all(gets).await {
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        // Every GET either ran a Spanner read or joined one, and most joined
+        let body = scrape(&app).await;
+        let reads = sample(&body, r#"kv_spanner_call_duration_seconds_count{op="read"}"#);
+        let coalesced = sample(&body, "kv_reads_coalesced_total");
+        assert_eq!(reads + coalesced, 50, "{}", body);
+        assert!(reads < 25, "expected most GETs to be coalesced, {} reached Spanner", reads);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics_report_jobs_by_state() {
+        let state = setup_test_state().await;
+        let jobs = state.jobs.clone();
+        let app = app(state);
+
+        let body = scrape(&app).await;
+        assert_eq!(sample(&body, r#"kv_jobs{state="running"}"#), 0, "{}", body);
+
+        let running = jobs.register("export", serde_json::json!({}));
+        jobs.register("export", serde_json::json!({}))
+            .finish(&Ok::<_, anyhow::Error>(()));
+        let cancelled = jobs.register("import", serde_json::json!({}));
+        jobs.cancel(cancelled.id());
+        drop(cancelled);
+
+        let body = scrape(&app).await;
+        assert_eq!(sample(&body, r#"kv_jobs{state="running"}"#), 1, "{}", body);
+        assert_eq!(sample(&body, r#"kv_jobs{state="completed"}"#), 1, "{}", body);
+        assert_eq!(sample(&body, r#"kv_jobs{state="cancelled"}"#), 1, "{}", body);
+        assert_eq!(sample(&body, r#"kv_jobs{state="failed"}"#), 0, "{}", body);
+
+        // The gauge follows the registry rather than accumulating
+        drop(running);
+        let body = scrape(&app).await;
+        assert_eq!(sample(&body, r#"kv_jobs{state="running"}"#), 0, "{}", body);
+        assert_eq!(sample(&body, r#"kv_jobs{state="failed"}"#), 1, "{}", body);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -1,9 +1,60 @@
 pub mod health;
+pub mod metrics;
 pub mod put;
+pub mod create;
+pub mod batch;
+pub mod batch_get;
+pub mod batch_delete;
 pub mod get;
+pub mod head;
+pub mod delete;
+pub mod undelete;
+pub mod delete_prefix;
+pub mod patch;
 pub mod list;
+pub mod count;
+pub mod secondary;
+pub mod export;
+pub mod import;
+pub mod stream;
+pub mod read_info;
+pub mod cache_control;
+pub mod etag;
+pub mod location;
+pub mod key;
+pub mod page_token;
+pub mod admin;
+pub mod ddl;
+pub mod jobs;
+pub mod rename;
+pub mod copy;
+pub mod history;
+pub mod meta;
+pub mod path;
 
-pub use health::health_handler;
+pub use health::{liveness_handler, readiness_handler};
+pub use metrics::metrics_handler;
 pub use put::put_handler;
+pub use create::create_handler;
+pub use batch::batch_put_handler;
+pub use batch_get::batch_get_handler;
+pub use batch_delete::batch_delete_handler;
 pub use get::get_handler;
+pub use head::head_handler;
+pub use delete::delete_handler;
+pub use undelete::undelete_handler;
+pub use delete_prefix::delete_prefix_handler;
+pub use patch::patch_handler;
 pub use list::list_handler;
+pub use count::count_handler;
+pub use secondary::secondary_key_handler;
+pub use export::export_handler;
+pub use import::import_handler;
+pub use stream::stream_handler;
+pub use rename::{move_handler, rename_handler};
+pub use copy::copy_handler;
+pub use history::history_handler;
+pub use meta::meta_handler;
+pub use path::{path_handler, put_path_handler};
+pub use ddl::ddl_handler;
+pub use jobs::{cancel_job_handler, get_job_handler, list_jobs_handler};
@@ -0,0 +1,108 @@
+use crate::error::ApiError;
+use crate::spanner::{PageCursor, Sort};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Contents of a `page_token`, before base64 encoding
+///
+/// The sort and prefix it was issued for are carried along, so a token can't
+/// silently resume a different listing.
+#[derive(Serialize, Deserialize)]
+struct PageToken {
+    sort: String,
+    prefix: Option<String>,
+    key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated_at: Option<String>,
+}
+
+/// Encode the position after `cursor` in a listing as an opaque, URL-safe token
+pub fn encode(sort: &Sort, prefix: Option<&str>, cursor: &PageCursor) -> String {
+    let format = |at: DateTime<Utc>| at.to_rfc3339_opts(SecondsFormat::Nanos, true);
+    let token = PageToken {
+        sort: sort.name(),
+        prefix: prefix.map(str::to_string),
+        key: cursor.key.clone(),
+        created_at: cursor.created_at.map(format),
+        updated_at: cursor.updated_at.map(format),
+    };
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&token).expect("page tokens always serialize"))
+}
+
+/// Decode a `page_token`, checking it was issued for this sort and prefix
+pub fn decode(raw: &str, sort: &Sort, prefix: Option<&str>) -> Result<PageCursor, ApiError> {
+    let invalid = || ApiError::InvalidQueryParam("page_token is not a token returned by GET /kv".to_string());
+    let bytes = URL_SAFE_NO_PAD.decode(raw).map_err(|_| invalid())?;
+    let token: PageToken = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+
+    if token.sort != sort.name() {
+        return Err(ApiError::InvalidQueryParam(format!(
+            "page_token was issued for sort={}, not sort={}",
+            token.sort,
+            sort.name()
+        )));
+    }
+    if token.prefix.as_deref() != prefix {
+        return Err(ApiError::InvalidQueryParam(
+            "page_token was issued for a different prefix".to_string(),
+        ));
+    }
+    let parse = |at: Option<String>| match at {
+        Some(at) => DateTime::parse_from_rfc3339(&at)
+            .map(|at| Some(at.with_timezone(&Utc)))
+            .map_err(|_| invalid()),
+        None => Ok(None),
+    };
+    let cursor = PageCursor {
+        key: token.key,
+        created_at: parse(token.created_at)?,
+        updated_at: parse(token.updated_at)?,
+    };
+    if !sort.fits_cursor(&cursor) {
+        return Err(invalid());
+    }
+    Ok(cursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spanner::SortOrder;
+
+    #[test]
+    fn test_round_trip() {
+        let at = DateTime::parse_from_rfc3339("2024-05-01T12:00:00.123456789Z").unwrap().with_timezone(&Utc);
+        let sort = Sort::from(SortOrder::CreatedDesc);
+        let cursor = PageCursor { key: "user:7".to_string(), created_at: Some(at), updated_at: None };
+        let token = encode(&sort, Some("user:"), &cursor);
+        assert!(token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'), "{}", token);
+        assert_eq!(decode(&token, &sort, Some("user:")).unwrap(), cursor);
+
+        let sort = Sort::from(SortOrder::KeyAsc);
+        let cursor = PageCursor { key: "a".to_string(), created_at: None, updated_at: None };
+        let token = encode(&sort, None, &cursor);
+        assert_eq!(decode(&token, &sort, None).unwrap(), cursor);
+
+        // A sort over both timestamps carries both
+        let sort: Sort = "updated_desc,created_asc".parse().unwrap();
+        let cursor = PageCursor { key: "b".to_string(), created_at: Some(at), updated_at: Some(at) };
+        let token = encode(&sort, None, &cursor);
+        assert_eq!(decode(&token, &sort, None).unwrap(), cursor);
+    }
+
+    #[test]
+    fn test_rejects_other_listings() {
+        let sort = Sort::from(SortOrder::KeyAsc);
+        let cursor = PageCursor { key: "a".to_string(), created_at: None, updated_at: None };
+        let token = encode(&sort, Some("user:"), &cursor);
+        assert!(decode(&token, &SortOrder::KeyDesc.into(), Some("user:")).is_err());
+        assert!(decode(&token, &"updated_desc,key_asc".parse().unwrap(), Some("user:")).is_err());
+        assert!(decode(&token, &sort, Some("order:")).is_err());
+        assert!(decode(&token, &sort, None).is_err());
+        assert!(decode("not a token", &sort, None).is_err());
+        assert!(decode(&URL_SAFE_NO_PAD.encode("{}"), &sort, None).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::cache_control::write_cache_headers;
+use crate::handlers::key::parse_key;
+use crate::models::GetResponse;
+use crate::routes;
+use crate::spanner::MergeOutcome;
+use crate::state::AppState;
+use axum::{body::Bytes, extract::rejection::BytesRejection, extract::State, extract::Path, http::HeaderMap, http::StatusCode, Json};
+use serde_json::Value as JsonValue;
+
+/// PATCH /kv/:id handler - Merge changes into a stored JSON document
+///
+/// The body is an RFC 7386 JSON Merge Patch: members set to `null` are removed,
+/// nested objects are merged, and any other value replaces the field. Only
+/// documents that are JSON objects can be patched; use PUT to replace others.
+#[utoipa::path(
+    patch,
+    path = routes::KV_ITEM,
+    params(
+        ("id" = String, Path, description = "Key for the document: a UUID, or any valid key in KEY_MODE=string")
+    ),
+    request_body(content = serde_json::Value, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "Patch applied; returns the merged document", body = GetResponse, headers(
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 400, description = "Invalid UUID format or KEY_MODE=string key, invalid JSON, patch not an object, or stored document not an object", body = ErrorResponse),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn patch_handler(
+    State(state): State<AppState>,
+    Path(id_str): Path<String>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<(StatusCode, HeaderMap, Json<GetResponse>), ApiError> {
+    let body = body?;
+    let id = parse_key(&state.config, &id_str)?;
+
+    let patch: JsonValue = serde_json::from_slice(&body)?;
+    if !patch.is_object() {
+        return Err(ApiError::InvalidRequest(
+            "merge patch must be a JSON object; use PUT to replace the whole document".to_string(),
+        ));
+    }
+
+    match state.spanner_client.merge_patch(&id, patch).await? {
+        MergeOutcome::Merged { data, version } => {
+            tracing::info!("Successfully patched document with id: {}", id);
+            Ok((
+                StatusCode::OK,
+                write_cache_headers(&state.config),
+                Json(GetResponse {
+                    id: id.clone(),
+                    data,
+                    etag: None,
+                    version: Some(version),
+                    deleted_at: None,
+                    created_at: None,
+                    updated_at: None,
+                }),
+            ))
+        }
+        MergeOutcome::NotFound => Err(ApiError::KeyNotFound(id)),
+        MergeOutcome::NotAnObject => Err(ApiError::InvalidRequest(format!(
+            "document {} is not a JSON object, so a merge patch cannot be applied; use PUT to replace it",
+            id
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, KeyMode};
+    use crate::handlers::{get_handler, put_handler};
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::put, Router};
+    use serde_json::json;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> Router {
+        setup_test_app_with(Config::for_emulator("put-endpoint-test", "put-endpoint-test-db")).await
+    }
+
+    async fn setup_test_app_with(config: Config) -> Router {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        Router::new()
+            .route(
+                routes::KV_ITEM,
+                put(put_handler).get(get_handler).patch(patch_handler),
+            )
+            .with_state(state)
+    }
+
+    fn request(method: &str, id: &str, body: &JsonValue) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(format!("/kv/{}", id))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    async fn body_json<T: serde::de::DeserializeOwned>(response: axum::response::Response) -> T {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_patch_removes_and_merges_nested_fields() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4().to_string();
+
+        let original = json!({
+            "name": "widget",
+            "obsolete": true,
+            "dimensions": {"width": 10, "height": 20, "depth": {"outer": 5, "inner": 4}},
+            "tags": ["a", "b"]
+        });
+        let response = app.clone().oneshot(request("PUT", &id, &original)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let patch = json!({
+            "obsolete": null,
+            "dimensions": {"height": 25, "depth": {"inner": null, "lining": "felt"}},
+            "tags": ["c"]
+        });
+        let response = app.clone().oneshot(request("PATCH", &id, &patch)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let expected = json!({
+            "name": "widget",
+            "dimensions": {"width": 10, "height": 25, "depth": {"outer": 5, "lining": "felt"}},
+            "tags": ["c"]
+        });
+        let patched: GetResponse = body_json(response).await;
+        assert_eq!(patched.data, expected);
+
+        // The merged document is what was stored
+        let response = app
+            .oneshot(Request::builder().uri(format!("/kv/{}", id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let stored: GetResponse = body_json(response).await;
+        assert_eq!(stored.data, expected);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_patch_errors() {
+        let app = setup_test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(request("PATCH", &Uuid::new_v4().to_string(), &json!({"a": 1})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // A stored array is not merged into or overwritten
+        let id = Uuid::new_v4().to_string();
+        let response = app.clone().oneshot(request("PUT", &id, &json!([1, 2, 3]))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = app.clone().oneshot(request("PATCH", &id, &json!({"a": 1}))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let error: ErrorResponse = body_json(response).await;
+        assert!(error.error.contains("not a JSON object"), "{}", error.error);
+
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(format!("/kv/{}", id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let stored: GetResponse = body_json(response).await;
+        assert_eq!(stored.data, json!([1, 2, 3]));
+
+        // Non-object patches are rejected
+        let response = app.clone().oneshot(request("PATCH", &id, &json!("replace"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app.oneshot(request("PATCH", "not-a-uuid", &json!({}))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_patch_string_key() {
+        let app = setup_test_app_with(Config {
+            key_mode: KeyMode::String,
+            ..Config::for_emulator("put-endpoint-test", "put-endpoint-test-db")
+        })
+        .await;
+        let key = format!("user:{}", &Uuid::new_v4().simple().to_string()[..16]);
+
+        let response = app.clone().oneshot(request("PUT", &key, &json!({"name": "a"}))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = app.clone().oneshot(request("PATCH", &key, &json!({"age": 3}))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let patched: GetResponse = body_json(response).await;
+        assert_eq!(patched.id, key);
+        assert_eq!(patched.data, json!({"name": "a", "age": 3}));
+
+        let response = app.oneshot(request("PATCH", "bad%20key", &json!({}))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::cache_control::{read_cache_headers, write_cache_headers};
+use crate::handlers::key::parse_key;
+use crate::models::{PathPutQuery, PutResponse};
+use crate::routes;
+use crate::spanner::{is_valid_field_path, PathSetOutcome};
+use crate::state::AppState;
+use axum::{body::Bytes, extract::rejection::BytesRejection, extract::Path, extract::Query, extract::State, http::HeaderMap, http::StatusCode, Json};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+/// Path parameters of the path endpoints; `pointer` is absent on `/kv/:id/path`
+#[derive(Deserialize)]
+pub struct PathParams {
+    id: String,
+    pointer: Option<String>,
+}
+
+impl PathParams {
+    /// The JSON Pointer, with the leading slash the route strips; empty for the whole document
+    fn pointer(&self) -> String {
+        self.pointer.as_deref().map_or(String::new(), |pointer| format!("/{}", pointer))
+    }
+}
+
+/// The dotted field path for a JSON Pointer, if Spanner can extract it
+///
+/// Only pointers made of plain member names qualify. An all-digit token could
+/// be an array index or an object key, and escaped or empty tokens have no
+/// dotted form, so those pointers are resolved against the whole document.
+fn projection_path(pointer: &str) -> Option<String> {
+    let tokens: Vec<&str> = pointer.strip_prefix('/')?.split('/').collect();
+    tokens
+        .iter()
+        .all(|token| {
+            // A dot inside a key would be read as nesting in the dotted path
+            !token.contains('.') && is_valid_field_path(token) && !token.chars().all(|c| c.is_ascii_digit())
+        })
+        .then(|| tokens.join("."))
+}
+
+/// GET /kv/:id/path/*pointer handler - Read one value out of a document
+///
+/// The rest of the path is a JSON Pointer (RFC 6901), e.g.
+/// `/kv/:id/path/settings/theme` or `/kv/:id/path/tags/0`, with `~1` for `/`
+/// and `~0` for `~` inside a key. The value is returned as-is, so a string
+/// comes back as a JSON string. When the pointer is made of plain member names
+/// only that field is read from Spanner; otherwise the whole document is.
+/// `/kv/:id/path` with no pointer returns the whole document.
+#[utoipa::path(
+    get,
+    path = routes::KV_PATH,
+    params(
+        ("id" = String, Path, description = "Key of the document"),
+        ("pointer" = String, Path, description = "JSON Pointer into the document, without the leading slash")
+    ),
+    responses(
+        (status = 200, description = "The value at the pointer", body = serde_json::Value, content_type = "application/json"),
+        (status = 400, description = "Invalid UUID format or KEY_MODE=string key", body = ErrorResponse),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 404, description = "Key not found, or the pointer doesn't resolve in the document", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn path_handler(
+    State(state): State<AppState>,
+    Path(params): Path<PathParams>,
+) -> Result<(StatusCode, HeaderMap, Json<JsonValue>), ApiError> {
+    let id = parse_key(&state.config, &params.id)?;
+    let pointer = params.pointer();
+
+    let document = match projection_path(&pointer) {
+        Some(path) => state.spanner_client.read_projected(&id, &[path]).await?,
+        None => state.spanner_client.read_key(&id).await?,
+    };
+    let Some(mut document) = document else {
+        return Err(ApiError::KeyNotFound(id));
+    };
+
+    match document.data.pointer_mut(&pointer).map(JsonValue::take) {
+        Some(value) => Ok((StatusCode::OK, read_cache_headers(&state.config), Json(value))),
+        None => {
+            tracing::info!("Pointer {} not found in document {}", pointer, id);
+            Err(ApiError::PointerNotFound { id, pointer })
+        }
+    }
+}
+
+/// PUT /kv/:id/path/*pointer handler - Set one value inside a document
+///
+/// The body is the JSON value to store at the pointer. Object members are
+/// added or replaced, array elements are replaced by index, and `-` appends to
+/// an array. `/kv/:id/path` with no pointer replaces the whole document. The
+/// document must already exist. Missing parents are a 409 unless
+/// `create_parents=true`, which creates them as empty objects.
+///
+/// The document is changed in a single read-write transaction, so concurrent
+/// writes to different paths don't overwrite each other.
+#[utoipa::path(
+    put,
+    path = routes::KV_PATH,
+    params(
+        ("id" = String, Path, description = "Key of the document"),
+        ("pointer" = String, Path, description = "JSON Pointer into the document, without the leading slash"),
+        ("create_parents" = Option<bool>, Query, description = "Create missing parent objects along the pointer")
+    ),
+    request_body(content = serde_json::Value, content_type = "application/json"),
+    responses(
+        (status = 200, description = "Value set", body = PutResponse, headers(
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 400, description = "Invalid UUID format or KEY_MODE=string key, or invalid JSON", body = ErrorResponse),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 409, description = "The pointer doesn't fit the document: a parent is missing, isn't a container, or an array index is out of range", body = ErrorResponse),
+        (status = 413, description = "The updated document would exceed MAX_DOCUMENT_BYTES", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn put_path_handler(
+    State(state): State<AppState>,
+    Path(params): Path<PathParams>,
+    Query(query): Query<PathPutQuery>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<(StatusCode, HeaderMap, Json<PutResponse>), ApiError> {
+    let body = body?;
+    let id = parse_key(&state.config, &params.id)?;
+    let pointer = params.pointer();
+    let value: JsonValue = serde_json::from_slice(&body)?;
+    let create_parents = query.create_parents.unwrap_or(false);
+    let max = state.config.max_document_bytes;
+
+    match state.spanner_client.set_path(&id, &pointer, value, create_parents, max).await? {
+        PathSetOutcome::Set { version } => {
+            tracing::info!("Set {} in document {} at version {}", pointer, id, version);
+            Ok((StatusCode::OK, write_cache_headers(&state.config), Json(PutResponse { id, version })))
+        }
+        PathSetOutcome::NotFound => Err(ApiError::KeyNotFound(id)),
+        PathSetOutcome::Rejected(e) => Err(ApiError::Conflict(format!("cannot set {} in document {}: {}", pointer, id, e))),
+        PathSetOutcome::TooLarge(size) => Err(ApiError::PayloadTooLarge { size, max }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::handlers::put_handler;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::get, routing::put, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_projection_path() {
+        assert_eq!(projection_path("/settings/theme").as_deref(), Some("settings.theme"));
+        assert_eq!(projection_path("/name").as_deref(), Some("name"));
+        for pointer in ["/tags/0", "/a~1b", "/a~0b", "/a//b", "/", "/a.b", "/x y"] {
+            assert_eq!(projection_path(pointer), None, "{} should read the whole document", pointer);
+        }
+    }
+
+    async fn setup_test_app() -> Router {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("put-endpoint-test", "put-endpoint-test-db");
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        Router::new()
+            .route(routes::KV_ITEM, put(put_handler))
+            .route(routes::KV_PATH, get(path_handler).put(put_path_handler))
+            .route(routes::KV_PATH_ROOT, get(path_handler).put(put_path_handler))
+            .with_state(state)
+    }
+
+    async fn send(app: &Router, method: &str, uri: &str, body: &str) -> (StatusCode, axum::body::Bytes) {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        (status, axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_path_reads_single_values() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+        let data = serde_json::json!({
+            "settings": {"theme": "dark", "font": null},
+            "tags": ["a", {"b": 2}],
+            "a/b": 1,
+            "m~n": 2,
+            "0": "zero",
+            "a.b": "dotted"
+        });
+        let (status, _) = send(&app, "PUT", &format!("/kv/{}", id), &data.to_string()).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        for (pointer, expected) in [
+            ("settings/theme", serde_json::json!("dark")),
+            ("settings/font", serde_json::json!(null)),
+            ("settings", serde_json::json!({"theme": "dark", "font": null})),
+            ("tags/0", serde_json::json!("a")),
+            ("tags/1/b", serde_json::json!(2)),
+            ("a~1b", serde_json::json!(1)),
+            ("m~0n", serde_json::json!(2)),
+            ("0", serde_json::json!("zero")),
+            ("a.b", serde_json::json!("dotted")),
+        ] {
+            let (status, body) = send(&app, "GET", &format!("/kv/{}/path/{}", id, pointer), "").await;
+            assert_eq!(status, StatusCode::OK, "pointer {}", pointer);
+            let value: JsonValue = serde_json::from_slice(&body).unwrap();
+            assert_eq!(value, expected, "pointer {}", pointer);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_path_not_found() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+        let (status, _) = send(&app, "PUT", &format!("/kv/{}", id), r#"{"tags": ["a"], "n": 1}"#).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        // A missing pointer is told apart from a missing document
+        for pointer in ["missing", "n/deeper", "tags/1", "tags/01"] {
+            let (status, body) = send(&app, "GET", &format!("/kv/{}/path/{}", id, pointer), "").await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "pointer {}", pointer);
+            let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+            assert!(error.error.starts_with("Path not found"), "pointer {}: {}", pointer, error.error);
+        }
+        let (status, body) = send(&app, "GET", &format!("/kv/{}/path/n", Uuid::new_v4()), "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error.error.starts_with("Key not found"));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_path_sets_values() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+        let doc = format!("/kv/{}/path", id);
+        let (status, _) = send(&app, "PUT", &format!("/kv/{}", id), r#"{"settings": {"theme": "dark"}, "tags": ["a", "b"]}"#).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        for (pointer, value) in [("settings/theme", r#""light""#), ("tags/1", r#""B""#), ("tags/-", r#""c""#)] {
+            let (status, body) = send(&app, "PUT", &format!("{}/{}", doc, pointer), value).await;
+            assert_eq!(status, StatusCode::OK, "pointer {}", pointer);
+            let written: PutResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(written.id, id.to_string());
+        }
+        let (_, body) = send(&app, "GET", &doc, "").await;
+        let data: JsonValue = serde_json::from_slice(&body).unwrap();
+        assert_eq!(data, serde_json::json!({"settings": {"theme": "light"}, "tags": ["a", "B", "c"]}));
+
+        // The root pointer replaces the whole document
+        let (status, body) = send(&app, "PUT", &doc, r#"["replaced"]"#).await;
+        assert_eq!(status, StatusCode::OK);
+        let written: PutResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(written.version, 5);
+        let (_, body) = send(&app, "GET", &doc, "").await;
+        assert_eq!(serde_json::from_slice::<JsonValue>(&body).unwrap(), serde_json::json!(["replaced"]));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_path_conflicts() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+        let doc = format!("/kv/{}/path", id);
+        let (status, _) = send(&app, "PUT", &format!("/kv/{}", id), r#"{"a": "text", "tags": []}"#).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        for (pointer, message) in [
+            ("a/b", "/a is not an object or array"),
+            ("x/y", "/x does not exist; pass create_parents=true"),
+            ("tags/0", "/tags is an array, and '0' is not one of its indices"),
+        ] {
+            let (status, body) = send(&app, "PUT", &format!("{}/{}", doc, pointer), "1").await;
+            assert_eq!(status, StatusCode::CONFLICT, "pointer {}", pointer);
+            let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+            assert!(error.error.contains(message), "pointer {}: {}", pointer, error.error);
+        }
+
+        let (status, _) = send(&app, "PUT", &format!("{}/x/y?create_parents=true", doc), "1").await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, body) = send(&app, "GET", &format!("{}/x", doc), "").await;
+        assert_eq!(serde_json::from_slice::<JsonValue>(&body).unwrap(), serde_json::json!({"y": 1}));
+
+        let (status, _) = send(&app, "PUT", &format!("/kv/{}/path/a", Uuid::new_v4()), "1").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_path_writes() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+        let (status, _) = send(&app, "PUT", &format!("/kv/{}", id), "{}").await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        // Each write reads and rewrites the whole document, so a lost update would drop a field.
+        // The emulator aborts concurrent transactions on one row until some run out of retries
+        // and return 503, so those are re-sent; only a lost field is a failure.
+        let writes = (0..8).map(|n| {
+            let app = app.clone();
+            async move {
+                for _ in 0..50 {
+                    let (status, _) = send(&app, "PUT", &format!("/kv/{}/path/field_{}", id, n), &n.to_string()).await;
+                    if status != StatusCode::SERVICE_UNAVAILABLE {
+                        return status;
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(20 * (n + 1))).await;
+                }
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+        });
+        for status in futures_util::future::join_all(writes).await {
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        let (_, body) = send(&app, "GET", &format!("/kv/{}/path", id), "").await;
+        let data: JsonValue = serde_json::from_slice(&body).unwrap();
+        for n in 0..8 {
+            assert_eq!(data[format!("field_{}", n)], serde_json::json!(n));
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -1,54 +1,215 @@
 use crate::error::{ApiError, ErrorResponse};
-use crate::models::PutResponse;
+use crate::handlers::cache_control::write_cache_headers;
+use crate::handlers::etag::if_match_version;
+use crate::handlers::key::parse_key;
+use crate::handlers::location::with_location;
+use crate::models::{DryRunResponse, PutQuery, PutResponse};
 use crate::routes;
+use crate::spanner::{Precondition, Written};
 use crate::state::AppState;
-use axum::{extract::State, extract::Path, http::StatusCode, Json};
+use axum::{body::Bytes, extract::rejection::BytesRejection, extract::Query, extract::State, extract::Path, http::HeaderMap, http::StatusCode, response::IntoResponse, response::Response, Json};
 use serde_json::Value as JsonValue;
-use uuid::Uuid;
+use std::time::Duration;
 
 /// PUT /kv/:id handler - Store a JSON document
+///
+/// The body must be exactly one JSON value; anything but whitespace after it
+/// (e.g. `{"a":1}garbage`) is rejected with 400 rather than silently dropped.
+///
+/// With an `If-Match` header holding the `ETag` from a previous GET, the write only
+/// happens if the document is unchanged since; otherwise it fails with 412.
+/// `?expected_version=N` does the same against the document's version and fails
+/// with 409 instead. The two can't be combined.
+///
+/// With `?ttl_seconds=N` (or an `X-TTL-Seconds: N` header) the document expires N
+/// seconds after the write and is then hidden from every read and listing. A PUT
+/// without either clears any expiry.
+///
+/// A write that creates the document, because no live document held the key,
+/// returns 201 with a `Location` header; one replacing a document returns 200.
+///
+/// When `MAX_DOCUMENTS` is set, creating a new key fails with 507 once the store
+/// is at capacity; updates to existing keys are always accepted.
+///
+/// Documents larger than `MAX_DOCUMENT_BYTES`, measured as compact JSON, are
+/// rejected with 413 before anything is written. Bodies over `MAX_BODY_BYTES`
+/// are rejected with 413 while still being read.
+///
+/// With `?dry_run=true` the request goes through every check above, including
+/// the capacity check, and returns 200 with `{"dry_run": true, "valid": true}`
+/// without writing. `If-Match` and `expected_version` are checked for form but
+/// not compared against the stored document.
 #[utoipa::path(
     put,
     path = routes::KV_ITEM,
     params(
-        ("id" = String, Path, description = "UUID key for the document")
+        ("id" = String, Path, description = "Key for the document: a UUID, or any valid key in KEY_MODE=string"),
+        ("ttl_seconds" = Option<i64>, Query, description = "Expire the document this many seconds after the write"),
+        ("expected_version" = Option<i64>, Query, description = "Only write if the stored document is at this version; fails with 409 otherwise"),
+        ("dry_run" = Option<bool>, Query, description = "Validate the request and return 200 without storing anything"),
+        ("If-Match" = Option<String>, Header, description = "ETag from a previous GET; the write fails with 412 if the document has changed since"),
+        ("X-TTL-Seconds" = Option<i64>, Header, description = "Same as ttl_seconds, for clients that can't change the URL")
     ),
     request_body = serde_json::Value,
     responses(
-        (status = 200, description = "Document stored successfully", body = PutResponse),
-        (status = 400, description = "Invalid UUID format or invalid JSON", body = ErrorResponse),
-        (status = 500, description = "Database error", body = ErrorResponse)
+        (status = 200, description = "Existing document replaced, or with dry_run=true a DryRunResponse for a valid request", body = PutResponse, headers(
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 201, description = "Document created", body = PutResponse, headers(
+            ("Location" = String, description = "Path of the new document, /kv/{id}"),
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 400, description = "Invalid UUID format or KEY_MODE=string key, invalid JSON, trailing data after the JSON value, a non-positive TTL, both ttl_seconds and X-TTL-Seconds, or both If-Match and expected_version", body = ErrorResponse),
+        (status = 409, description = "Document is missing or not at expected_version", body = ErrorResponse),
+        (status = 412, description = "Document changed or was deleted since the If-Match version", body = ErrorResponse),
+        (status = 413, description = "Document is larger than MAX_DOCUMENT_BYTES, or the body larger than MAX_BODY_BYTES", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+        (status = 507, description = "New key rejected because the store is at MAX_DOCUMENTS", body = ErrorResponse)
     ),
     tag = "kv"
 )]
 pub async fn put_handler(
     State(state): State<AppState>,
     Path(id_str): Path<String>,
-    Json(data): Json<JsonValue>,
-) -> Result<(StatusCode, Json<PutResponse>), ApiError> {
-    // Parse and validate UUID
-    let id = Uuid::parse_str(&id_str).map_err(|_| ApiError::InvalidUuid(id_str.clone()))?;
+    Query(params): Query<PutQuery>,
+    headers: HeaderMap,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Response, ApiError> {
+    let body = body?;
+    let id = parse_key(&state.config, &id_str)?;
 
-    // Store the document
-    state.spanner_client.upsert(id, data).await?;
+    // from_slice fails unless the whole body is consumed, so trailing data is an error
+    let data: JsonValue = serde_json::from_slice(&body)?;
+    let if_match = if_match_version(&headers)?;
+    if if_match.is_some() && params.expected_version.is_some() {
+        return Err(ApiError::InvalidRequest(
+            "send either If-Match or expected_version, not both".to_string(),
+        ));
+    }
+    let ttl = requested_ttl(&params, &headers)?.map(parse_ttl).transpose()?;
+
+    // Measured as stored, so whitespace in the request body doesn't count
+    let size = serde_json::to_vec(&data)?.len();
+    if size > state.config.max_document_bytes {
+        tracing::info!("Rejected document {} of {} bytes", id, size);
+        return Err(ApiError::PayloadTooLarge {
+            size,
+            max: state.config.max_document_bytes,
+        });
+    }
+
+    if !state.spanner_client.has_room_for(&id).await? {
+        let max = state.spanner_client.document_limit().unwrap_or_default();
+        tracing::warn!("Rejected new document {}: store is at its limit of {}", id, max);
+        return Err(ApiError::DocumentLimitReached(max));
+    }
+
+    if params.dry_run.unwrap_or(false) {
+        tracing::info!("Validated document {} of {} bytes without storing it (dry run)", id, size);
+        return Ok((
+            write_cache_headers(&state.config),
+            Json(DryRunResponse { dry_run: true, valid: true }),
+        )
+            .into_response());
+    }
 
-    tracing::info!("Successfully stored document with id: {}", id);
+    // Store the document, conditionally if the client sent the version it last saw,
+    // in which case it already existed
+    let written = match (if_match, params.expected_version) {
+        (Some(updated_at), _) => state
+            .spanner_client
+            .upsert_if_unchanged(&id, data, Precondition::UpdatedAt(updated_at), ttl)
+            .await?
+            .ok_or_else(|| {
+                tracing::info!("Rejected stale write to document {}", id);
+                ApiError::PreconditionFailed(id.clone())
+            })
+            .map(|version| Written { version, created: false })?,
+        (None, Some(expected)) => state
+            .spanner_client
+            .upsert_if_unchanged(&id, data, Precondition::Version(expected), ttl)
+            .await?
+            .ok_or_else(|| {
+                tracing::info!("Rejected write to document {} not at version {}", id, expected);
+                ApiError::Conflict(format!("document {} is missing or not at version {}", id, expected))
+            })
+            .map(|version| Written { version, created: false })?,
+        (None, None) => match ttl {
+            Some(ttl) => state.spanner_client.upsert_with_ttl(&id, data, ttl).await?,
+            None => state.spanner_client.upsert_key(&id, data).await?,
+        },
+    };
+
+    tracing::info!(
+        "Successfully {} document with id: {} at version {}",
+        if written.created { "created" } else { "stored" },
+        id,
+        written.version
+    );
+    let (status, headers) = if written.created {
+        (StatusCode::CREATED, with_location(write_cache_headers(&state.config), &id))
+    } else {
+        (StatusCode::OK, write_cache_headers(&state.config))
+    };
     Ok((
-        StatusCode::OK,
+        status,
+        headers,
         Json(PutResponse {
-            id: id.to_string(),
+            id,
+            version: written.version,
         }),
-    ))
+    )
+        .into_response())
+}
+
+/// Header alternative to the `ttl_seconds` query parameter
+const TTL_HEADER: &str = "x-ttl-seconds";
+
+/// The TTL from `?ttl_seconds` or the `X-TTL-Seconds` header; sending both is an error
+fn requested_ttl(params: &PutQuery, headers: &HeaderMap) -> Result<Option<i64>, ApiError> {
+    let Some(value) = headers.get(TTL_HEADER) else {
+        return Ok(params.ttl_seconds);
+    };
+    if params.ttl_seconds.is_some() {
+        return Err(ApiError::InvalidRequest(
+            "send either ttl_seconds or X-TTL-Seconds, not both".to_string(),
+        ));
+    }
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .map(Some)
+        .ok_or_else(|| ApiError::InvalidRequest("X-TTL-Seconds must be a whole number of seconds".to_string()))
+}
+
+/// Longest `ttl_seconds` a PUT will accept, about 100 years
+const MAX_TTL_SECONDS: u64 = 100 * 365 * 24 * 60 * 60;
+
+/// Validate a `ttl_seconds` value, which must be between 1 and [`MAX_TTL_SECONDS`]
+fn parse_ttl(seconds: i64) -> Result<Duration, ApiError> {
+    u64::try_from(seconds)
+        .ok()
+        .filter(|seconds| (1..=MAX_TTL_SECONDS).contains(seconds))
+        .map(Duration::from_secs)
+        .ok_or_else(|| ApiError::InvalidQueryParam(format!(
+            "ttl_seconds must be between 1 and {} (got {})",
+            MAX_TTL_SECONDS, seconds
+        )))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::Config;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
     use crate::spanner::SpannerClient;
     use axum::{body::Body, http::Request, routing::put, Router};
     use std::sync::Arc;
     use tower::ServiceExt;
+    use uuid::Uuid;
 
     async fn setup_test_app() -> Router {
         // Set up config with emulator
@@ -56,14 +217,7 @@ mod tests {
             std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
         }
 
-        let config = Config {
-            spanner_emulator_host: Some("localhost:9010".to_string()),
-            spanner_project: "test-project".to_string(),
-            spanner_instance: "put-endpoint-test".to_string(),
-            spanner_database: "put-endpoint-test-db".to_string(),
-            service_port: 3000,
-            service_host: "0.0.0.0".to_string(),
-        };
+        let config = Config::for_emulator("put-endpoint-test", "put-endpoint-test-db");
 
         let spanner_client = SpannerClient::from_config(&config)
             .await
@@ -71,7 +225,9 @@ mod tests {
 
         let state = AppState {
             spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
             config: Arc::new(config),
+            metrics: Metrics::new(),
         };
 
         Router::new()
@@ -101,13 +257,46 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.status(), StatusCode::CREATED);
 
         let body = axum::body::to_bytes(response.into_body(), usize::MAX)
             .await
             .unwrap();
         let response_json: PutResponse = serde_json::from_slice(&body).unwrap();
         assert_eq!(response_json.id, test_id.to_string());
+        assert_eq!(response_json.version, 1);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_created_then_replaced() {
+        let app = setup_test_app().await;
+        let test_id = Uuid::new_v4();
+        let put = |body: &'static str| {
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/kv/{}", test_id))
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        // The first write creates the document and says where it lives
+        let response = app.clone().oneshot(put(r#"{"n": 1}"#)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()["location"], format!("/kv/{}", test_id));
+
+        // Later writes replace it
+        let response = app.clone().oneshot(put(r#"{"n": 2}"#)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response.headers().contains_key("location"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(serde_json::from_slice::<PutResponse>(&body).unwrap().version, 2);
 
         unsafe {
             std::env::remove_var("SPANNER_EMULATOR_HOST");
@@ -175,7 +364,7 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.status(), StatusCode::CREATED);
 
         unsafe {
             std::env::remove_var("SPANNER_EMULATOR_HOST");
@@ -200,8 +389,535 @@ mod tests {
             .await
             .unwrap();
 
-        // Axum's Json extractor returns 400 for invalid JSON
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains("JSON parse error"));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_endpoint_trailing_data() {
+        let app = setup_test_app().await;
+
+        let test_id = Uuid::new_v4();
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", test_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"a":1}garbage"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains("trailing characters"));
+
+        // Trailing whitespace is still fine
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", test_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from("{\"a\":1}\n  "))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl(1).unwrap(), Duration::from_secs(1));
+        assert_eq!(parse_ttl(3600).unwrap(), Duration::from_secs(3600));
+        for seconds in [0, -5, MAX_TTL_SECONDS as i64 + 1, i64::MAX] {
+            assert!(parse_ttl(seconds).is_err(), "ttl_seconds {} should be rejected", seconds);
+        }
+    }
+
+    #[test]
+    fn test_requested_ttl() {
+        let query = |ttl_seconds| PutQuery { ttl_seconds, expected_version: None, dry_run: None };
+        let mut headers = HeaderMap::new();
+        assert_eq!(requested_ttl(&query(None), &headers).unwrap(), None);
+        assert_eq!(requested_ttl(&query(Some(5)), &headers).unwrap(), Some(5));
+
+        headers.insert(TTL_HEADER, " 60 ".parse().unwrap());
+        assert_eq!(requested_ttl(&query(None), &headers).unwrap(), Some(60));
+        assert!(matches!(requested_ttl(&query(Some(5)), &headers), Err(ApiError::InvalidRequest(_))));
+
+        headers.insert(TTL_HEADER, "1h".parse().unwrap());
+        assert!(matches!(requested_ttl(&query(None), &headers), Err(ApiError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_put_with_ttl_expires() {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        // Its own database, so listing stays fast enough to beat the 1-second TTL
+        let config = Config::for_emulator("put-endpoint-test", "put-ttl-test-db");
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+        let app = Router::new()
+            .route(crate::routes::KV_ITEM, put(put_handler).get(crate::handlers::get_handler))
+            .route(crate::routes::KV_LIST, axum::routing::get(crate::handlers::list_handler))
+            .with_state(AppState {
+                spanner_client,
+                jobs: Arc::new(JobRegistry::from_config(&config)),
+                config: Arc::new(config),
+                metrics: Metrics::new(),
+            });
+
+        let send = |method: &str, uri: String| {
+            let builder = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json");
+            let body = if method == "PUT" { r#"{"session": "abc"}"# } else { "" };
+            app.clone().oneshot(builder.body(Body::from(body)).unwrap())
+        };
+        let list = |id: Uuid| async move {
+            let response = send("GET", format!("/kv?prefix={}", id)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<crate::models::ListResponse>(&body).unwrap().data
+        };
+
+        // One document expires by query parameter, the other by header
+        let test_id = Uuid::new_v4();
+        let response = send("PUT", format!("/kv/{}?ttl_seconds=1", test_id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let header_id = Uuid::new_v4();
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", header_id))
+                    .header("x-ttl-seconds", "1")
+                    .body(Body::from(r#"{"session": "def"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        for id in [test_id, header_id] {
+            let response = send("GET", format!("/kv/{}", id)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "Document should be readable before it expires");
+            let entries = list(id).await;
+            assert_eq!(entries.len(), 1, "Document should be listed before it expires");
+            assert!(entries[0].expires_at.is_some());
+        }
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        for id in [test_id, header_id] {
+            let response = send("GET", format!("/kv/{}", id)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "Document should be gone once expired");
+            assert!(list(id).await.is_empty(), "Expired document should not be listed");
+        }
+
+        for ttl in ["0", "-1"] {
+            let response = send("PUT", format!("/kv/{}?ttl_seconds={}", test_id, ttl)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "ttl_seconds={}", ttl);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_if_match_stale_writer_loses() {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("put-endpoint-test", "put-endpoint-test-db");
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+        let app = Router::new()
+            .route(crate::routes::KV_ITEM, put(put_handler).get(crate::handlers::get_handler))
+            .with_state(AppState {
+                spanner_client,
+                jobs: Arc::new(JobRegistry::from_config(&config)),
+                config: Arc::new(config),
+                metrics: Metrics::new(),
+            });
+
+        let put_request = |id: Uuid, writer: &str, if_match: Option<&str>| {
+            let mut builder = Request::builder()
+                .method("PUT")
+                .uri(format!("/kv/{}", id))
+                .header("content-type", "application/json");
+            if let Some(etag) = if_match {
+                builder = builder.header("if-match", etag);
+            }
+            builder
+                .body(Body::from(format!(r#"{{"writer": "{}"}}"#, writer)))
+                .unwrap()
+        };
+        let get_etag = |app: Router, id: Uuid| async move {
+            let response = app
+                .oneshot(Request::builder().uri(format!("/kv/{}", id)).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            response.headers()["etag"].to_str().unwrap().to_string()
+        };
+
+        let test_id = Uuid::new_v4();
+        let response = app.clone().oneshot(put_request(test_id, "initial", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        // Both writers read the same version, then race to update it
+        let etag_a = get_etag(app.clone(), test_id).await;
+        let etag_b = get_etag(app.clone(), test_id).await;
+        assert_eq!(etag_a, etag_b);
+
+        let response = app.clone().oneshot(put_request(test_id, "a", Some(&etag_a))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone().oneshot(put_request(test_id, "b", Some(&etag_b))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains("Precondition failed"));
+
+        // The winner's write stands and carries a new version
+        let etag_now = get_etag(app.clone(), test_id).await;
+        assert_ne!(etag_now, etag_a);
+
+        // A conditional write to a missing key fails too, and a malformed ETag is 400
+        let response = app.clone().oneshot(put_request(Uuid::new_v4(), "c", Some(&etag_now))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        let response = app.oneshot(put_request(test_id, "d", Some("\"v1\""))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_expected_version() {
+        let app = setup_test_app().await;
+
+        let put_request = |id: Uuid, query: &str| {
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/kv/{}{}", id, query))
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"n": 1}"#))
+                .unwrap()
+        };
+        // Only the write creating the document, at version 1, is a 201
+        let put_version = |response: axum::response::Response| async move {
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let version = serde_json::from_slice::<PutResponse>(&body).unwrap().version;
+            assert_eq!(status, if version == 1 { StatusCode::CREATED } else { StatusCode::OK });
+            version
+        };
+
+        let test_id = Uuid::new_v4();
+        let response = app.clone().oneshot(put_request(test_id, "")).await.unwrap();
+        assert_eq!(put_version(response).await, 1);
+
+        // A matching version writes and bumps it; the stale one then conflicts
+        let response = app.clone().oneshot(put_request(test_id, "?expected_version=1")).await.unwrap();
+        assert_eq!(put_version(response).await, 2);
+        let response = app.clone().oneshot(put_request(test_id, "?expected_version=1")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        // So does any expected version for a missing key
+        let response = app.clone().oneshot(put_request(Uuid::new_v4(), "?expected_version=0")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        // expected_version and If-Match are mutually exclusive
+        let mut request = put_request(test_id, "?expected_version=2");
+        request
+            .headers_mut()
+            .insert("if-match", "\"2024-01-01T00:00:00Z\"".parse().unwrap());
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_document_limit() {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        // Allow exactly one more document than the store currently holds
+        let config = Config::for_emulator("put-endpoint-test", "put-endpoint-test-db");
+        let stored = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client")
+            .count_documents()
+            .await
+            .unwrap();
+        let config = Config {
+            max_documents: Some(stored + 1),
+            ..config
+        };
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+        let app = Router::new()
+            .route(crate::routes::KV_ITEM, put(put_handler))
+            .with_state(AppState {
+                spanner_client,
+                jobs: Arc::new(JobRegistry::from_config(&config)),
+                config: Arc::new(config),
+                metrics: Metrics::new(),
+            });
+
+        let put_request = |id: Uuid, version: u32| {
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/kv/{}", id))
+                .header("content-type", "application/json")
+                .body(Body::from(format!(r#"{{"version": {}}}"#, version)))
+                .unwrap()
+        };
+
+        let admitted = Uuid::new_v4();
+        let response = app.clone().oneshot(put_request(admitted, 1)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = app.clone().oneshot(put_request(Uuid::new_v4(), 1)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains("Document limit reached"));
+
+        // Updating an existing key is still allowed at capacity
+        let response = app.oneshot(put_request(admitted, 2)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_document_size_limit() {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        const MAX: usize = 64;
+        let config = Config {
+            max_document_bytes: MAX,
+            ..Config::for_emulator("put-endpoint-test", "put-endpoint-test-db")
+        };
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+        let app = Router::new()
+            .route(crate::routes::KV_ITEM, put(put_handler))
+            .with_state(AppState {
+                spanner_client,
+                jobs: Arc::new(JobRegistry::from_config(&config)),
+                config: Arc::new(config),
+                metrics: Metrics::new(),
+            });
+
+        // {"pad":"..."} serializes to 10 bytes plus the padding
+        let put_sized = |size: usize| {
+            let data = serde_json::json!({"pad": "x".repeat(size - 10)});
+            assert_eq!(data.to_string().len(), size);
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/kv/{}", Uuid::new_v4()))
+                .header("content-type", "application/json")
+                // Whitespace in the body is not counted
+                .body(Body::from(serde_json::to_string_pretty(&data).unwrap()))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(put_sized(MAX)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = app.oneshot(put_sized(MAX + 1)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(
+            error_response.error.contains("65 bytes") && error_response.error.contains("maximum is 64"),
+            "{}",
+            error_response.error
+        );
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_dry_run_writes_nothing() {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config {
+            max_document_bytes: 64,
+            ..Config::for_emulator("put-endpoint-test", "put-endpoint-test-db")
+        };
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+        let app = Router::new()
+            .route(crate::routes::KV_ITEM, put(put_handler).get(crate::handlers::get_handler))
+            .with_state(AppState {
+                spanner_client,
+                jobs: Arc::new(JobRegistry::from_config(&config)),
+                config: Arc::new(config),
+                metrics: Metrics::new(),
+            });
+        let send = |method: &str, uri: String, body: String| {
+            app.clone().oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+        };
+
+        // A valid new document is reported valid but not stored
+        let test_id = Uuid::new_v4();
+        let response = send("PUT", format!("/kv/{}?dry_run=true", test_id), r#"{"n": 1}"#.to_string())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response.headers().contains_key("location"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let dry_run: DryRunResponse = serde_json::from_slice(&body).unwrap();
+        assert!(dry_run.dry_run && dry_run.valid);
+        let response = send("GET", format!("/kv/{}", test_id), String::new()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "Dry run should not create the document");
+
+        // Nor does it replace an existing one
+        let response = send("PUT", format!("/kv/{}", test_id), r#"{"n": 1}"#.to_string()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = send("PUT", format!("/kv/{}?dry_run=true", test_id), r#"{"n": 2}"#.to_string())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = send("GET", format!("/kv/{}", test_id), String::new()).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let stored: crate::models::GetResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(stored.data, serde_json::json!({"n": 1}));
+
+        // Invalid requests fail exactly as a real write would
+        let cases = [
+            ("/kv/not-a-uuid?dry_run=true".to_string(), "{}".to_string(), StatusCode::BAD_REQUEST),
+            (format!("/kv/{}?dry_run=true", Uuid::new_v4()), "{not json".to_string(), StatusCode::BAD_REQUEST),
+            (
+                format!("/kv/{}?dry_run=true", Uuid::new_v4()),
+                serde_json::json!({"pad": "x".repeat(64)}).to_string(),
+                StatusCode::PAYLOAD_TOO_LARGE,
+            ),
+        ];
+        for (uri, body, status) in cases {
+            let response = send("PUT", uri.clone(), body).await.unwrap();
+            assert_eq!(response.status(), status, "{}", uri);
+        }
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_body_limit_returns_json() {
+        const LIMIT: usize = 1024;
+        let app = setup_test_app()
+            .await
+            .layer(axum::extract::DefaultBodyLimit::max(LIMIT));
+
+        // `{"pad":"..."}` adds 10 bytes around the padding, so this body is exactly LIMIT bytes
+        let at_limit = serde_json::json!({"pad": "x".repeat(LIMIT - 10)}).to_string();
+        assert_eq!(at_limit.len(), LIMIT);
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", Uuid::new_v4()))
+                    .header("content-type", "application/json")
+                    .body(Body::from(at_limit))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        // One byte more is rejected
+        let data = serde_json::json!({"pad": "x".repeat(LIMIT - 9)});
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", Uuid::new_v4()))
+                    .header("content-type", "application/json")
+                    .body(Body::from(data.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains("MAX_BODY_BYTES"), "{}", error_response.error);
 
         unsafe {
             std::env::remove_var("SPANNER_EMULATOR_HOST");
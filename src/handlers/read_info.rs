@@ -0,0 +1,83 @@
+use crate::config::Config;
+use crate::spanner::ReadInfo;
+use axum::http::{HeaderMap, HeaderValue};
+use chrono::SecondsFormat;
+
+/// Request header that opts a single request into read info headers
+pub const DEBUG_READ_INFO_HEADER: &str = "x-debug-read-info";
+
+/// Response header carrying the RFC 3339 timestamp the read was served at
+pub const READ_TIMESTAMP_HEADER: &str = "x-read-timestamp";
+
+/// Response header carrying the read mode (e.g. `strong`)
+pub const READ_MODE_HEADER: &str = "x-read-mode";
+
+/// Whether read info headers should be returned for this request
+///
+/// Enabled for every request by `DEBUG_READ_INFO`, or per request with
+/// `X-Debug-Read-Info: true`.
+pub fn read_info_requested(config: &Config, headers: &HeaderMap) -> bool {
+    config.debug_read_info
+        || headers
+            .get(DEBUG_READ_INFO_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+}
+
+/// Response headers describing how a read was served
+pub fn read_info_headers(info: Option<&ReadInfo>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(info) = info {
+        let timestamp = info.timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true);
+        if let Ok(value) = HeaderValue::from_str(&timestamp) {
+            headers.insert(READ_TIMESTAMP_HEADER, value);
+        }
+        headers.insert(READ_MODE_HEADER, HeaderValue::from_static(info.mode.as_str()));
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spanner::ReadMode;
+    use chrono::{DateTime, Utc};
+
+    #[test]
+    fn test_read_info_requested() {
+        let config = Config::for_emulator("test-instance", "test-database");
+        let mut headers = HeaderMap::new();
+        assert!(!read_info_requested(&config, &headers));
+
+        headers.insert(DEBUG_READ_INFO_HEADER, HeaderValue::from_static("TRUE"));
+        assert!(read_info_requested(&config, &headers));
+
+        headers.insert(DEBUG_READ_INFO_HEADER, HeaderValue::from_static("no"));
+        assert!(!read_info_requested(&config, &headers));
+
+        let config = Config {
+            debug_read_info: true,
+            ..config
+        };
+        assert!(read_info_requested(&config, &HeaderMap::new()));
+    }
+
+    #[test]
+    fn test_read_info_headers() {
+        assert!(read_info_headers(None).is_empty());
+
+        let info = ReadInfo {
+            timestamp: DateTime::<Utc>::from_timestamp(1_700_000_000, 123_456_789).unwrap(),
+            mode: ReadMode::Strong,
+        };
+        let headers = read_info_headers(Some(&info));
+        assert_eq!(headers[READ_TIMESTAMP_HEADER], "2023-11-14T22:13:20.123456789Z");
+        assert_eq!(headers[READ_MODE_HEADER], "strong");
+
+        let info = ReadInfo {
+            mode: ReadMode::Stale,
+            ..info
+        };
+        assert_eq!(read_info_headers(Some(&info))[READ_MODE_HEADER], "stale");
+    }
+}
@@ -0,0 +1,410 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::cache_control::write_cache_headers;
+use crate::handlers::key::parse_key;
+use crate::models::{MoveQuery, MoveRequest, RenameRequest, RenameResponse};
+use crate::routes;
+use crate::spanner::RenameOutcome;
+use crate::state::AppState;
+use axum::{extract::rejection::JsonRejection, extract::Path, extract::Query, extract::State, http::HeaderMap, http::StatusCode, Json};
+
+/// POST /kv/:id/rename handler - Move a document to a new key
+///
+/// The document is read, inserted under `new_id` and deleted from `id` in one
+/// transaction, keeping its `created_at`. Fails with 409 if `new_id` is taken.
+#[utoipa::path(
+    post,
+    path = routes::KV_RENAME,
+    params(
+        ("id" = String, Path, description = "Current key of the document: a UUID, or any valid key in KEY_MODE=string")
+    ),
+    request_body = RenameRequest,
+    responses(
+        (status = 200, description = "Document renamed", body = RenameResponse, headers(
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 400, description = "Invalid UUID format or KEY_MODE=string key, or new_id equal to id", body = ErrorResponse),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 409, description = "A document already exists at new_id", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn rename_handler(
+    State(state): State<AppState>,
+    Path(id_str): Path<String>,
+    request: Result<Json<RenameRequest>, JsonRejection>,
+) -> Result<(StatusCode, HeaderMap, Json<RenameResponse>), ApiError> {
+    let Json(request) = request?;
+    move_document(&state, &id_str, &request.new_id, "new_id", false).await
+}
+
+/// POST /kv/:id/move handler - Move a document to another key
+///
+/// The same atomic move as rename, addressed like a copy: the target is
+/// `target_id`, and `overwrite=true` replaces a document already stored there.
+/// There is no moment at which both keys, or neither, hold the document.
+/// Query parameters:
+/// - overwrite: Replace a document already stored at target_id (optional, default: false)
+#[utoipa::path(
+    post,
+    path = routes::KV_MOVE,
+    params(
+        ("id" = String, Path, description = "Current key of the document: a UUID, or any valid key in KEY_MODE=string"),
+        ("overwrite" = Option<bool>, Query, description = "Replace a document already stored at target_id")
+    ),
+    request_body = MoveRequest,
+    responses(
+        (status = 200, description = "Document moved", body = RenameResponse, headers(
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 400, description = "Invalid UUID format or KEY_MODE=string key, or target_id equal to id", body = ErrorResponse),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 409, description = "A document already exists at target_id", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn move_handler(
+    State(state): State<AppState>,
+    Path(id_str): Path<String>,
+    Query(query): Query<MoveQuery>,
+    request: Result<Json<MoveRequest>, JsonRejection>,
+) -> Result<(StatusCode, HeaderMap, Json<RenameResponse>), ApiError> {
+    let Json(request) = request?;
+    let overwrite = query.overwrite.unwrap_or(false);
+    move_document(&state, &id_str, &request.target_id, "target_id", overwrite).await
+}
+
+/// Validate both keys and move the document, shared by rename and move
+///
+/// `target_field` names the request field holding the new key, for error messages.
+async fn move_document(
+    state: &AppState,
+    id_str: &str,
+    new_id_str: &str,
+    target_field: &str,
+    overwrite: bool,
+) -> Result<(StatusCode, HeaderMap, Json<RenameResponse>), ApiError> {
+    let id = parse_key(&state.config, id_str)?;
+    let new_id = parse_key(&state.config, new_id_str)?;
+    if id == new_id {
+        return Err(ApiError::InvalidRequest(format!("{} must differ from the current id", target_field)));
+    }
+
+    match state.spanner_client.rename(&id, &new_id, overwrite).await? {
+        RenameOutcome::Renamed => {
+            tracing::info!("Renamed document {} to {}", id, new_id);
+            Ok((
+                StatusCode::OK,
+                write_cache_headers(&state.config),
+                Json(RenameResponse {
+                    id: new_id,
+                    previous_id: id,
+                }),
+            ))
+        }
+        RenameOutcome::SourceNotFound => Err(ApiError::KeyNotFound(id)),
+        RenameOutcome::DestinationExists => Err(ApiError::Conflict(format!(
+            "a document already exists at {}",
+            new_id
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, KeyMode};
+    use crate::handlers::{get_handler, list_handler, put_handler};
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::models::{GetResponse, ListResponse};
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::get, routing::post, routing::put, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> Router {
+        setup_test_app_with(Config::for_emulator("put-endpoint-test", "put-endpoint-test-db")).await
+    }
+
+    async fn setup_test_app_with(config: Config) -> Router {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        Router::new()
+            .route(crate::routes::KV_LIST, get(list_handler))
+            .route(crate::routes::KV_ITEM, put(put_handler).get(get_handler))
+            .route(crate::routes::KV_RENAME, post(rename_handler))
+            .route(crate::routes::KV_MOVE, post(move_handler))
+            .with_state(state)
+    }
+
+    async fn put_document(app: &Router, id: impl std::fmt::Display, data: &serde_json::Value) {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(data.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    fn rename_request(id: Uuid, new_id: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/kv/{}/rename", id))
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({"new_id": new_id}).to_string()))
+            .unwrap()
+    }
+
+    async fn listed_entry(app: &Router, id: impl std::fmt::Display) -> Option<crate::models::KvEntryResponse> {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv?prefix={}", id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let list: ListResponse = serde_json::from_slice(&body).unwrap();
+        list.data.into_iter().next()
+    }
+
+    #[tokio::test]
+    async fn test_rename_moves_document() {
+        let app = setup_test_app().await;
+
+        let id = Uuid::new_v4();
+        let new_id = Uuid::new_v4();
+        let data = serde_json::json!({"name": "renamed"});
+        put_document(&app, id, &data).await;
+        let before = listed_entry(&app, id).await.unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(rename_request(id, &new_id.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let renamed: RenameResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(renamed.id, new_id.to_string());
+        assert_eq!(renamed.previous_id, id.to_string());
+
+        // The old key is gone and the new one has the same document
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(format!("/kv/{}", id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(format!("/kv/{}", new_id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let fetched: GetResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(fetched.data, data);
+
+        let after = listed_entry(&app, new_id).await.unwrap();
+        assert_eq!(after.created_at, before.created_at, "created_at is preserved");
+        assert_eq!(after.content_hash, before.content_hash);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rename_preconditions() {
+        let app = setup_test_app().await;
+
+        let id = Uuid::new_v4();
+        let taken = Uuid::new_v4();
+        put_document(&app, id, &serde_json::json!({"which": "source"})).await;
+        put_document(&app, taken, &serde_json::json!({"which": "taken"})).await;
+
+        // Destination exists: nothing changes
+        let response = app.clone().oneshot(rename_request(id, &taken.to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert!(listed_entry(&app, id).await.is_some());
+        assert_eq!(listed_entry(&app, taken).await.unwrap().value["which"], "taken");
+
+        // Source missing
+        let response = app
+            .clone()
+            .oneshot(rename_request(Uuid::new_v4(), &Uuid::new_v4().to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Same id, and a malformed new id
+        let response = app.clone().oneshot(rename_request(id, &id.to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app.oneshot(rename_request(id, "not-a-uuid")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    fn move_request(id: impl std::fmt::Display, target_id: impl std::fmt::Display, overwrite: bool) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/kv/{}/move?overwrite={}", id, overwrite))
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({"target_id": target_id.to_string()}).to_string()))
+            .unwrap()
+    }
+
+    async fn listed_keys(app: &Router, prefix: &str) -> Vec<String> {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(format!("/kv?prefix={}", prefix)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let list: ListResponse = serde_json::from_slice(&body).unwrap();
+        list.data.into_iter().map(|entry| entry.key).collect()
+    }
+
+    #[tokio::test]
+    async fn test_move_never_shows_both_or_neither_key() {
+        let app = setup_test_app().await;
+
+        // Source and target share a prefix, so one listing sees both keys
+        let base = Uuid::new_v4().to_string();
+        let prefix = base[..24].to_string();
+        let id = Uuid::parse_str(&format!("{}{:012x}", prefix, 1)).unwrap();
+        let target_id = Uuid::parse_str(&format!("{}{:012x}", prefix, 2)).unwrap();
+        put_document(&app, id, &serde_json::json!({"moving": true})).await;
+        let before = listed_entry(&app, id).await.unwrap();
+        assert_eq!(listed_keys(&app, &prefix).await, vec![id.to_string()]);
+
+        // List continuously while the move commits
+        let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let watcher = tokio::spawn({
+            let app = app.clone();
+            let prefix = prefix.clone();
+            let done = done.clone();
+            async move {
+                while !done.load(std::sync::atomic::Ordering::SeqCst) {
+                    let keys = listed_keys(&app, &prefix).await;
+                    assert_eq!(keys.len(), 1, "expected exactly one key, saw {:?}", keys);
+                }
+            }
+        });
+        let response = app.clone().oneshot(move_request(id, target_id, false)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        done.store(true, std::sync::atomic::Ordering::SeqCst);
+        watcher.await.unwrap();
+
+        assert_eq!(listed_keys(&app, &prefix).await, vec![target_id.to_string()]);
+        let after = listed_entry(&app, target_id).await.unwrap();
+        assert_eq!(after.created_at, before.created_at, "created_at is preserved");
+        let updated_at = |entry: &crate::models::KvEntryResponse| {
+            chrono::DateTime::parse_from_rfc3339(&entry.updated_at).unwrap()
+        };
+        assert!(updated_at(&after) > updated_at(&before), "updated_at is the move's commit");
+        assert_eq!(after.value, serde_json::json!({"moving": true}));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_move_overwrite() {
+        let app = setup_test_app().await;
+
+        let id = Uuid::new_v4();
+        let target_id = Uuid::new_v4();
+        put_document(&app, id, &serde_json::json!({"which": "source"})).await;
+        put_document(&app, target_id, &serde_json::json!({"which": "target"})).await;
+
+        // Errors mirror copy: 409 without overwrite, 404 for a missing source, 400 for the same id
+        let response = app.clone().oneshot(move_request(id, target_id, false)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let response = app.clone().oneshot(move_request(Uuid::new_v4(), target_id, true)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app.clone().oneshot(move_request(id, id, true)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app.clone().oneshot(move_request(id, target_id, true)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(listed_entry(&app, id).await.is_none());
+        assert_eq!(listed_entry(&app, target_id).await.unwrap().value["which"], "source");
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_move_string_keys() {
+        let app = setup_test_app_with(Config {
+            key_mode: KeyMode::String,
+            ..Config::for_emulator("put-endpoint-test", "put-endpoint-test-db")
+        })
+        .await;
+        let base = &Uuid::new_v4().simple().to_string()[..16];
+        let id = format!("user:{}", base);
+        let target_id = format!("member:{}", base);
+        put_document(&app, &id, &serde_json::json!({"moving": true})).await;
+
+        let response = app.clone().oneshot(move_request(&id, &target_id, false)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let moved: RenameResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!((moved.id.as_str(), moved.previous_id.as_str()), (target_id.as_str(), id.as_str()));
+        assert!(listed_entry(&app, &id).await.is_none());
+        assert_eq!(listed_entry(&app, &target_id).await.unwrap().value, serde_json::json!({"moving": true}));
+
+        let response = app.oneshot(move_request(&target_id, "bad key!", false)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
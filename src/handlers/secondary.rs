@@ -0,0 +1,256 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::models::GetResponse;
+use crate::routes;
+use crate::state::AppState;
+use axum::{extract::State, extract::Path, http::StatusCode, Json};
+
+/// GET /kv/by/:value handler - Retrieve a JSON document by its secondary key
+///
+/// Looks up the document whose field at `SECONDARY_KEY_PATH` equals the given value.
+/// Returns 409 if more than one document matches, since the key is expected to be unique.
+#[utoipa::path(
+    get,
+    path = routes::KV_BY_SECONDARY_KEY,
+    params(
+        ("value" = String, Path, description = "Value of the configured secondary key field")
+    ),
+    responses(
+        (status = 200, description = "Document found", body = GetResponse),
+        (status = 404, description = "No document has this secondary key", body = ErrorResponse),
+        (status = 409, description = "Multiple documents share this secondary key", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+        (status = 501, description = "Secondary key lookups are not configured", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn secondary_key_handler(
+    State(state): State<AppState>,
+    Path(value): Path<String>,
+) -> Result<(StatusCode, Json<GetResponse>), ApiError> {
+    let Some(path) = state.config.secondary_key_path.as_deref() else {
+        return Err(ApiError::FeatureDisabled(
+            "secondary key lookups require SECONDARY_KEY_PATH to be set".to_string(),
+        ));
+    };
+
+    let mut matches = state.spanner_client.read_by_secondary_key(&value).await?;
+
+    match matches.len() {
+        0 => {
+            tracing::info!("No document found with secondary key {} = {}", path, value);
+            Err(ApiError::SecondaryKeyNotFound(value))
+        }
+        1 => {
+            let (id, data) = matches.remove(0);
+            tracing::info!("Successfully retrieved document with id: {} via secondary key", id);
+            Ok((StatusCode::OK, Json(GetResponse {
+                id,
+                data,
+                etag: None,
+                version: None,
+                deleted_at: None,
+                created_at: None,
+                updated_at: None,
+            })))
+        }
+        _ => {
+            tracing::warn!("Secondary key {} = {} matches multiple documents", path, value);
+            Err(ApiError::Conflict(format!(
+                "multiple documents have {} = '{}'",
+                path, value
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::handlers::put::put_handler;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::get, routing::put, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app(secondary_key_path: Option<&str>) -> Router {
+        setup_test_app_with(Config {
+            secondary_key_path: secondary_key_path.map(str::to_string),
+            ..Config::for_emulator("secondary-key-test", "secondary-key-test-db")
+        })
+        .await
+    }
+
+    async fn setup_test_app_with(config: Config) -> Router {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        Router::new()
+            .route(crate::routes::KV_ITEM, put(put_handler))
+            .route(crate::routes::KV_BY_SECONDARY_KEY, get(secondary_key_handler))
+            .with_state(state)
+    }
+
+    async fn put_document(app: &Router, id: Uuid, data: &serde_json::Value) {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/kv/{}", id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(data).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    async fn get_by_secondary_key(app: &Router, value: &str) -> axum::response::Response {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri(format!("/kv/by/{}", value))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_secondary_key_lookup() {
+        let app = setup_test_app(Some("$.email")).await;
+
+        let test_id = Uuid::new_v4();
+        let email = format!("{}@example.com", test_id.simple());
+        let test_data = serde_json::json!({"email": email, "name": "test"});
+        put_document(&app, test_id, &test_data).await;
+
+        let response = get_by_secondary_key(&app, &email).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response_json: GetResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response_json.id, test_id.to_string());
+        assert_eq!(response_json.data, test_data);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secondary_key_lookup_in_second_table() {
+        // The default table already has its secondary key index in this database
+        let _ = setup_test_app(Some("$.email")).await;
+        let app = setup_test_app_with(Config {
+            secondary_key_path: Some("$.email".to_string()),
+            spanner_table: "secondary_documents".to_string(),
+            ..Config::for_emulator("secondary-key-test", "secondary-key-test-db")
+        })
+        .await;
+
+        let test_id = Uuid::new_v4();
+        let email = format!("{}@example.com", test_id.simple());
+        put_document(&app, test_id, &serde_json::json!({"email": email})).await;
+
+        let response = get_by_secondary_key(&app, &email).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response_json: GetResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response_json.id, test_id.to_string());
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_changed_secondary_key_path_fails_startup() {
+        // The column is deployed extracting $.email
+        let _ = setup_test_app(Some("$.email")).await;
+
+        let config = Config {
+            secondary_key_path: Some("$.username".to_string()),
+            ..Config::for_emulator("secondary-key-test", "secondary-key-test-db")
+        };
+        let Err(err) = SpannerClient::from_config(&config).await else {
+            panic!("Startup should fail when SECONDARY_KEY_PATH changes");
+        };
+        let message = format!("{:#}", err);
+        assert!(message.contains("'$.email'") && message.contains("'$.username'"), "{}", message);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secondary_key_not_found() {
+        let app = setup_test_app(Some("$.email")).await;
+
+        let email = format!("{}@example.com", Uuid::new_v4().simple());
+        let response = get_by_secondary_key(&app, &email).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(error_response.error.contains(&email));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secondary_key_duplicate() {
+        let app = setup_test_app(Some("$.email")).await;
+
+        let email = format!("{}@example.com", Uuid::new_v4().simple());
+        put_document(&app, Uuid::new_v4(), &serde_json::json!({"email": email})).await;
+        put_document(&app, Uuid::new_v4(), &serde_json::json!({"email": email})).await;
+
+        let response = get_by_secondary_key(&app, &email).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secondary_key_disabled() {
+        let app = setup_test_app(None).await;
+
+        let response = get_by_secondary_key(&app, "someone@example.com").await;
+        assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
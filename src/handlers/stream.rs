@@ -0,0 +1,265 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::models::{KvEntryResponse, StreamQuery};
+use crate::routes;
+use crate::spanner::{KvEntry, SortOrder, SpannerResult};
+use crate::state::AppState;
+use axum::{
+    body::{Body, Bytes},
+    extract::{rejection::QueryRejection, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use futures_util::{Stream, StreamExt};
+
+/// Content type of newline-delimited JSON
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// GET /kv/stream handler - Stream documents as newline-delimited JSON
+///
+/// Writes one `KvEntryResponse` per line, in the same shape as the entries of
+/// `GET /kv`, sent as rows arrive from Spanner instead of after the whole
+/// listing has been read. There are no pages: without `limit`, every matching
+/// document is streamed. Closing the connection stops the query.
+///
+/// If reading fails partway, the body is aborted rather than ended cleanly, so
+/// clients can tell a truncated stream from a complete one.
+#[utoipa::path(
+    get,
+    path = routes::KV_STREAM,
+    params(
+        ("prefix" = Option<String>, Query, description = "Only stream keys starting with this value"),
+        ("sort" = Option<Vec<SortOrder>>, Query, style = Form, explode = false, description = "Comma-separated sort orders applied in turn, as for GET /kv (default key_asc)"),
+        ("limit" = Option<u64>, Query, description = "Most documents to stream; all of them when unset")
+    ),
+    responses(
+        (status = 200, description = "One JSON document entry per line", body = KvEntryResponse, content_type = "application/x-ndjson"),
+        (status = 400, description = "Invalid query parameter", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn stream_handler(
+    State(state): State<AppState>,
+    query: Result<Query<StreamQuery>, QueryRejection>,
+) -> Result<Response, ApiError> {
+    let Query(query) = query?;
+    let sort = query.sort.unwrap_or_default();
+    let limit = query
+        .limit
+        .map(|limit| {
+            i64::try_from(limit)
+                .map_err(|_| ApiError::InvalidQueryParam(format!("limit must be at most {}, got {}", i64::MAX, limit)))
+        })
+        .transpose()?;
+
+    let rows = state.spanner_client.list_stream(query.prefix.clone(), sort, limit, 0);
+    let body = rows_body(rows, Vec::new(), |entry| {
+        let mut line = serde_json::to_vec(&KvEntryResponse::from(entry))?;
+        line.push(b'\n');
+        Ok(line)
+    })
+    .await?;
+
+    Ok(([(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)], body).into_response())
+}
+
+/// A response body of `preamble` followed by each row as `render` writes it
+///
+/// The first row is awaited before returning, so a failing query still
+/// produces an error status. A later failure aborts the body instead of ending
+/// it cleanly, so clients can tell a truncated stream from a complete one.
+pub async fn rows_body<S>(
+    rows: S,
+    preamble: Vec<u8>,
+    render: fn(KvEntry) -> serde_json::Result<Vec<u8>>,
+) -> Result<Body, ApiError>
+where
+    S: Stream<Item = SpannerResult<KvEntry>> + Send + 'static,
+{
+    let mut rows = Box::pin(rows);
+    let first = rows.next().await.transpose()?;
+
+    let rendered = futures_util::stream::iter(first.map(Ok)).chain(rows).map(move |row| match row {
+        Ok(entry) => render(entry)
+            .map(Bytes::from)
+            .map_err(|e| std::io::Error::other(format!("Failed to serialize entry: {}", e))),
+        Err(e) => {
+            tracing::error!("Stream aborted: {}", e);
+            Err(std::io::Error::other(e.to_string()))
+        }
+    });
+    let preamble = futures_util::stream::iter((!preamble.is_empty()).then(|| Ok(Bytes::from(preamble))));
+    Ok(Body::from_stream(preamble.chain(rendered)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::spanner::SpannerClient;
+    use axum::{http::Request, http::StatusCode, routing::get, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn setup_test_app() -> (Router, SpannerClient) {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config::for_emulator("stream-test", "stream-test-db");
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client: spanner_client.clone(),
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        let app = Router::new()
+            .route(routes::KV_STREAM, get(stream_handler))
+            .with_state(state);
+        (app, spanner_client)
+    }
+
+    /// `count` fresh UUID keys sharing a random first group, returned with that prefix
+    fn keys_under_prefix(count: usize) -> (String, Vec<Uuid>) {
+        let prefix = Uuid::new_v4().simple().to_string()[..8].to_string();
+        let keys = (0..count)
+            .map(|_| Uuid::parse_str(&format!("{}{}", prefix, &Uuid::new_v4().to_string()[8..])).unwrap())
+            .collect();
+        (prefix, keys)
+    }
+
+    #[tokio::test]
+    async fn test_stream_many_rows() {
+        let (app, client) = setup_test_app().await;
+        let (prefix, keys) = keys_under_prefix(1000);
+        let items = keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (*key, serde_json::json!({"i": i})))
+            .collect();
+        assert_eq!(client.upsert_batch(items).await.written, 1000);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv/stream?prefix={}", prefix))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], NDJSON_CONTENT_TYPE);
+        assert!(!response.headers().contains_key(header::CONTENT_LENGTH), "Body should be streamed");
+
+        // Rows arrive in several chunks rather than one buffered body
+        let chunks: Vec<Bytes> = response
+            .into_body()
+            .into_data_stream()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert!(chunks.len() > 1, "Expected a chunked body, got {} chunk", chunks.len());
+
+        let body: Vec<u8> = chunks.concat();
+        let entries: Vec<KvEntryResponse> = body
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 1000);
+        let mut expected: Vec<String> = keys.iter().map(Uuid::to_string).collect();
+        expected.sort();
+        let streamed: Vec<String> = entries.into_iter().map(|entry| entry.key).collect();
+        assert_eq!(streamed, expected);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_sort_and_limit() {
+        let (app, client) = setup_test_app().await;
+        let (prefix, keys) = keys_under_prefix(5);
+        for key in &keys {
+            client.upsert(*key, serde_json::json!({"k": key})).await.unwrap();
+        }
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv/stream?prefix={}&sort=created_desc&limit=3", prefix))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let streamed: Vec<String> = String::from_utf8(body.to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<KvEntryResponse>(line).unwrap().key)
+            .collect();
+        let newest: Vec<String> = keys.iter().rev().take(3).map(Uuid::to_string).collect();
+        assert_eq!(streamed, newest);
+
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/kv/stream?sort=newest").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // No matches is an empty body, not an error
+        let (empty_prefix, _) = keys_under_prefix(0);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/kv/stream?prefix={}", empty_prefix))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dropped_stream_stops_early() {
+        let (_, client) = setup_test_app().await;
+        let (prefix, keys) = keys_under_prefix(200);
+        let items = keys.iter().map(|key| (*key, serde_json::json!({}))).collect();
+        assert_eq!(client.upsert_batch(items).await.written, 200);
+
+        // Taking a few rows and dropping the stream must not hang or panic
+        let mut rows = Box::pin(client.list_stream(Some(prefix), crate::spanner::SortOrder::KeyAsc.into(), None, 0));
+        for _ in 0..3 {
+            rows.next().await.unwrap().unwrap();
+        }
+        drop(rows);
+
+        // The client is still usable afterwards
+        assert!(client.health_check().await.is_ok());
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use crate::error::{ApiError, ErrorResponse};
+use crate::handlers::cache_control::write_cache_headers;
+use crate::handlers::key::parse_key;
+use crate::models::UndeleteResponse;
+use crate::routes;
+use crate::spanner::UndeleteOutcome;
+use crate::state::AppState;
+use axum::{extract::State, extract::Path, http::HeaderMap, http::StatusCode, Json};
+
+/// POST /kv/:id/undelete handler - Restore a soft-deleted document
+///
+/// The document comes back exactly as it was deleted, with the same data and
+/// version. Fails with 409 if the document isn't deleted, and with 404 if it
+/// was hard-deleted, expired, or replaced by a later write.
+#[utoipa::path(
+    post,
+    path = routes::KV_UNDELETE,
+    params(
+        ("id" = String, Path, description = "Key of the soft-deleted document: a UUID, or any valid key in KEY_MODE=string")
+    ),
+    responses(
+        (status = 200, description = "Document restored", body = UndeleteResponse, headers(
+            ("Cache-Control" = String, description = "no-store when LIST_CACHE_MAX_AGE is set")
+        )),
+        (status = 400, description = "Invalid UUID format, or invalid key in KEY_MODE=string", body = ErrorResponse),
+        (status = 403, description = "Key is in the reserved internal namespace", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+        (status = 409, description = "Document is not deleted", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse)
+    ),
+    tag = "kv"
+)]
+pub async fn undelete_handler(
+    State(state): State<AppState>,
+    Path(id_str): Path<String>,
+) -> Result<(StatusCode, HeaderMap, Json<UndeleteResponse>), ApiError> {
+    let id = parse_key(&state.config, &id_str)?;
+
+    match state.spanner_client.undelete(&id).await? {
+        UndeleteOutcome::Restored => {
+            tracing::info!("Restored soft-deleted document {}", id);
+            Ok((
+                StatusCode::OK,
+                write_cache_headers(&state.config),
+                Json(UndeleteResponse {
+                    id,
+                    restored: true,
+                }),
+            ))
+        }
+        UndeleteOutcome::NotDeleted => Err(ApiError::Conflict(format!("document {} is not deleted", id))),
+        UndeleteOutcome::NotFound => Err(ApiError::KeyNotFound(id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::handlers::{delete_handler, get_handler, list_handler, put_handler};
+    use crate::jobs::JobRegistry;
+    use crate::metrics::Metrics;
+    use crate::models::{GetResponse, ListResponse};
+    use crate::spanner::SpannerClient;
+    use axum::{body::Body, http::Request, routing::get, routing::post, routing::put, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    const TOKEN: &str = "test-admin-token";
+
+    async fn setup_test_app() -> Router {
+        unsafe {
+            std::env::set_var("SPANNER_EMULATOR_HOST", "localhost:9010");
+        }
+
+        let config = Config {
+            admin_token: Some(TOKEN.to_string()),
+            ..Config::for_emulator("soft-delete-test", "soft-delete-test-db")
+        };
+        let spanner_client = SpannerClient::from_config(&config)
+            .await
+            .expect("Failed to create Spanner client");
+
+        let state = AppState {
+            spanner_client,
+            jobs: Arc::new(JobRegistry::from_config(&config)),
+            config: Arc::new(config),
+            metrics: Metrics::new(),
+        };
+
+        Router::new()
+            .route(routes::KV_LIST, get(list_handler))
+            .route(routes::KV_ITEM, put(put_handler).get(get_handler).delete(delete_handler))
+            .route(routes::KV_UNDELETE, post(undelete_handler))
+            .with_state(state)
+    }
+
+    async fn send(app: &Router, method: &str, uri: &str, admin: bool) -> (StatusCode, axum::body::Bytes) {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if admin {
+            builder = builder.header("authorization", format!("Bearer {}", TOKEN));
+        }
+        let body = if method == "PUT" {
+            builder = builder.header("content-type", "application/json");
+            Body::from(r#"{"keep": "me"}"#)
+        } else {
+            Body::empty()
+        };
+        let response = app.clone().oneshot(builder.body(body).unwrap()).await.unwrap();
+        let status = response.status();
+        (status, axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete_and_undelete() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+        let item = format!("/kv/{}", id);
+
+        send(&app, "PUT", &item, false).await;
+        send(&app, "PUT", &item, false).await;
+        assert_eq!(send(&app, "DELETE", &item, false).await.0, StatusCode::OK);
+
+        // Hidden from ordinary reads and listings
+        assert_eq!(send(&app, "GET", &item, false).await.0, StatusCode::NOT_FOUND);
+        let (_, body) = send(&app, "GET", &format!("/kv?prefix={}", id), false).await;
+        let list: ListResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(list.total_count, Some(0));
+
+        // Admins can still see it
+        assert_eq!(
+            send(&app, "GET", &format!("{}?include_deleted=true", item), false).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        let (status, body) = send(&app, "GET", &format!("{}?include_deleted=true", item), true).await;
+        assert_eq!(status, StatusCode::OK);
+        let deleted: GetResponse = serde_json::from_slice(&body).unwrap();
+        assert!(deleted.deleted_at.is_some());
+        assert_eq!(deleted.version, Some(2));
+        let (_, body) = send(&app, "GET", &format!("/kv?prefix={}&include_deleted=true", id), true).await;
+        let list: ListResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(list.total_count, Some(1));
+        assert!(list.data[0].deleted_at.is_some());
+
+        // Undelete restores it as it was; a second undelete conflicts
+        let undelete = format!("/kv/{}/undelete", id);
+        let (status, body) = send(&app, "POST", &undelete, false).await;
+        assert_eq!(status, StatusCode::OK);
+        let restored: UndeleteResponse = serde_json::from_slice(&body).unwrap();
+        assert!(restored.restored);
+        let (status, body) = send(&app, "GET", &item, false).await;
+        assert_eq!(status, StatusCode::OK);
+        let fetched: GetResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(fetched.data, serde_json::json!({"keep": "me"}));
+        assert_eq!(fetched.version, Some(2));
+        assert!(fetched.deleted_at.is_none());
+        assert_eq!(send(&app, "POST", &undelete, false).await.0, StatusCode::CONFLICT);
+
+        // A write over a soft-deleted document starts a new one
+        send(&app, "DELETE", &item, false).await;
+        send(&app, "PUT", &item, false).await;
+        let (_, body) = send(&app, "GET", &item, false).await;
+        let fetched: GetResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(fetched.version, Some(1));
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hard_delete_is_permanent() {
+        let app = setup_test_app().await;
+        let id = Uuid::new_v4();
+        let item = format!("/kv/{}", id);
+
+        // Hard-deleting a soft-deleted document removes it for good
+        send(&app, "PUT", &item, false).await;
+        send(&app, "DELETE", &item, false).await;
+        assert_eq!(send(&app, "DELETE", &format!("{}?hard=true", item), false).await.0, StatusCode::OK);
+        assert_eq!(
+            send(&app, "GET", &format!("{}?include_deleted=true", item), true).await.0,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(send(&app, "POST", &format!("/kv/{}/undelete", id), false).await.0, StatusCode::NOT_FOUND);
+        assert_eq!(send(&app, "DELETE", &format!("{}?hard=true", item), false).await.0, StatusCode::NOT_FOUND);
+
+        unsafe {
+            std::env::remove_var("SPANNER_EMULATOR_HOST");
+        }
+    }
+}
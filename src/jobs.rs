@@ -0,0 +1,374 @@
+use crate::config::Config;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// Lifecycle state of a background job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Point-in-time view of a job, as returned by the admin endpoints
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct JobInfo {
+    pub id: Uuid,
+    /// Kind of operation, e.g. `export`
+    pub kind: String,
+    pub status: JobStatus,
+    /// Parameters the job was started with
+    pub params: JsonValue,
+    /// Items processed so far
+    pub processed: u64,
+    /// Whether cancellation has been requested; the job stops at its next chunk boundary
+    pub cancel_requested: bool,
+    pub started_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Number of retained jobs in each state
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct JobCounts {
+    pub running: u64,
+    pub completed: u64,
+    pub failed: u64,
+    pub cancelled: u64,
+}
+
+struct JobEntry {
+    kind: String,
+    params: JsonValue,
+    status: JobStatus,
+    processed: Arc<AtomicU64>,
+    cancel: CancellationToken,
+    started_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+    /// When the job finished, used to expire it from the registry
+    finished: Option<Instant>,
+    error: Option<String>,
+}
+
+impl JobEntry {
+    fn info(&self, id: Uuid) -> JobInfo {
+        JobInfo {
+            id,
+            kind: self.kind.clone(),
+            status: self.status,
+            params: self.params.clone(),
+            processed: self.processed.load(Ordering::Relaxed),
+            cancel_requested: self.cancel.is_cancelled(),
+            started_at: self.started_at.to_rfc3339(),
+            finished_at: self.finished_at.map(|t| t.to_rfc3339()),
+            error: self.error.clone(),
+        }
+    }
+}
+
+/// Outcome of a cancellation request
+pub enum CancelOutcome {
+    /// Cancellation was signalled (or already had been) to a running job
+    Requested(JobInfo),
+    /// The job had already finished, so there was nothing to cancel
+    AlreadyFinished(JobInfo),
+    NotFound,
+}
+
+/// Tracks long-running operations so operators can inspect and cancel them
+///
+/// Jobs register when they start and report progress through their [`JobHandle`].
+/// Finished jobs stay visible for the retention period and are then dropped the
+/// next time the registry is read.
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<Uuid, JobEntry>>,
+    retention: Duration,
+}
+
+impl JobRegistry {
+    pub fn new(retention: Duration) -> Self {
+        Self {
+            jobs: Mutex::new(HashMap::new()),
+            retention,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(Duration::from_secs(config.job_retention_secs))
+    }
+
+    /// Register a new running job and return the handle used to drive it
+    pub fn register(self: &Arc<Self>, kind: &str, params: JsonValue) -> JobHandle {
+        let id = Uuid::new_v4();
+        let processed = Arc::new(AtomicU64::new(0));
+        let cancel = CancellationToken::new();
+
+        self.jobs.lock().unwrap().insert(
+            id,
+            JobEntry {
+                kind: kind.to_string(),
+                params,
+                status: JobStatus::Running,
+                processed: processed.clone(),
+                cancel: cancel.clone(),
+                started_at: Utc::now(),
+                finished_at: None,
+                finished: None,
+                error: None,
+            },
+        );
+        tracing::info!("Started {} job {}", kind, id);
+
+        JobHandle {
+            id,
+            registry: self.clone(),
+            processed,
+            cancel,
+            done: false,
+        }
+    }
+
+    /// All retained jobs, most recently started first
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs = self.jobs.lock().unwrap();
+        self.expire(&mut jobs);
+
+        let mut entries: Vec<_> = jobs.iter().collect();
+        entries.sort_by_key(|(_, entry)| Reverse(entry.started_at));
+        entries.into_iter().map(|(id, entry)| entry.info(*id)).collect()
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<JobInfo> {
+        let mut jobs = self.jobs.lock().unwrap();
+        self.expire(&mut jobs);
+        jobs.get(&id).map(|entry| entry.info(id))
+    }
+
+    /// Ask a running job to stop; it does so at its next chunk boundary
+    pub fn cancel(&self, id: Uuid) -> CancelOutcome {
+        let mut jobs = self.jobs.lock().unwrap();
+        self.expire(&mut jobs);
+
+        match jobs.get(&id) {
+            Some(entry) if entry.status == JobStatus::Running => {
+                entry.cancel.cancel();
+                tracing::info!("Cancellation requested for {} job {}", entry.kind, id);
+                CancelOutcome::Requested(entry.info(id))
+            }
+            Some(entry) => CancelOutcome::AlreadyFinished(entry.info(id)),
+            None => CancelOutcome::NotFound,
+        }
+    }
+
+    pub fn counts(&self) -> JobCounts {
+        let mut jobs = self.jobs.lock().unwrap();
+        self.expire(&mut jobs);
+
+        let mut counts = JobCounts::default();
+        for entry in jobs.values() {
+            match entry.status {
+                JobStatus::Running => counts.running += 1,
+                JobStatus::Completed => counts.completed += 1,
+                JobStatus::Failed => counts.failed += 1,
+                JobStatus::Cancelled => counts.cancelled += 1,
+            }
+        }
+        counts
+    }
+
+    /// Drop finished jobs older than the retention period
+    fn expire(&self, jobs: &mut HashMap<Uuid, JobEntry>) {
+        let now = Instant::now();
+        jobs.retain(|_, entry| {
+            entry
+                .finished
+                .is_none_or(|finished| now.duration_since(finished) < self.retention)
+        });
+    }
+
+    fn finish(&self, id: Uuid, status: JobStatus, error: Option<String>) {
+        if let Some(entry) = self.jobs.lock().unwrap().get_mut(&id) {
+            entry.status = status;
+            entry.error = error;
+            entry.finished_at = Some(Utc::now());
+            entry.finished = Some(Instant::now());
+            tracing::info!(
+                "{} job {} finished as {:?} after {} items",
+                entry.kind,
+                id,
+                status,
+                entry.processed.load(Ordering::Relaxed)
+            );
+        }
+    }
+}
+
+/// Handle held by a running job to report progress and observe cancellation
+///
+/// Dropping the handle without calling [`JobHandle::finish`] records the job as
+/// cancelled if that was requested and failed otherwise, so a job whose task
+/// dies never stays "running" forever.
+pub struct JobHandle {
+    id: Uuid,
+    registry: Arc<JobRegistry>,
+    processed: Arc<AtomicU64>,
+    cancel: CancellationToken,
+    done: bool,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Record `count` more processed items
+    pub fn add_progress(&self, count: u64) {
+        self.processed.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Whether the job should stop; checked by job loops between chunks
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Record the job's outcome
+    pub fn finish<T>(mut self, result: &anyhow::Result<T>) {
+        self.done = true;
+        match result {
+            Ok(_) => self.registry.finish(self.id, JobStatus::Completed, None),
+            Err(_) if self.is_cancelled() => self.registry.finish(self.id, JobStatus::Cancelled, None),
+            Err(e) => self.registry.finish(self.id, JobStatus::Failed, Some(format!("{:#}", e))),
+        }
+    }
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        if self.is_cancelled() {
+            self.registry.finish(self.id, JobStatus::Cancelled, None);
+        } else {
+            self.registry.finish(
+                self.id,
+                JobStatus::Failed,
+                Some("Job stopped before completing".to_string()),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A job that processes `chunks` chunks of 10 items, pausing between each
+    async fn slow_job(handle: JobHandle, chunks: u64) {
+        let mut result = Ok(());
+        for _ in 0..chunks {
+            if handle.is_cancelled() {
+                result = Err(anyhow::anyhow!("Cancelled"));
+                break;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            handle.add_progress(10);
+        }
+        handle.finish(&result);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_register_and_progress() {
+        let registry = Arc::new(JobRegistry::new(Duration::from_secs(60)));
+        let handle = registry.register("synthetic", json!({"chunks": 5}));
+        let id = handle.id();
+
+        let info = registry.get(id).unwrap();
+        assert_eq!(info.kind, "synthetic");
+        assert_eq!(info.status, JobStatus::Running);
+        assert_eq!(info.params, json!({"chunks": 5}));
+        assert_eq!(info.processed, 0);
+
+        let job = tokio::spawn(slow_job(handle, 5));
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        assert_eq!(registry.get(id).unwrap().processed, 20);
+        assert_eq!(registry.counts().running, 1);
+
+        job.await.unwrap();
+        let info = registry.get(id).unwrap();
+        assert_eq!(info.status, JobStatus::Completed);
+        assert_eq!(info.processed, 50);
+        assert!(info.finished_at.is_some());
+        assert_eq!(registry.counts(), JobCounts { completed: 1, ..Default::default() });
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancel_stops_job_at_chunk_boundary() {
+        let registry = Arc::new(JobRegistry::new(Duration::from_secs(60)));
+        let handle = registry.register("synthetic", json!({}));
+        let id = handle.id();
+        let job = tokio::spawn(slow_job(handle, 100));
+
+        tokio::time::sleep(Duration::from_millis(3500)).await;
+        match registry.cancel(id) {
+            CancelOutcome::Requested(info) => {
+                assert!(info.cancel_requested);
+                assert_eq!(info.status, JobStatus::Running);
+            }
+            _ => panic!("Running job should accept cancellation"),
+        }
+
+        job.await.unwrap();
+        let info = registry.get(id).unwrap();
+        assert_eq!(info.status, JobStatus::Cancelled);
+        // The chunk in flight when cancellation arrived still completes
+        assert_eq!(info.processed, 40);
+
+        assert!(matches!(registry.cancel(id), CancelOutcome::AlreadyFinished(_)));
+        assert!(matches!(registry.cancel(Uuid::new_v4()), CancelOutcome::NotFound));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_finished_jobs_expire_after_retention() {
+        let registry = Arc::new(JobRegistry::new(Duration::from_secs(60)));
+        let running = registry.register("synthetic", json!({}));
+        let finished = registry.register("synthetic", json!({}));
+        let finished_id = finished.id();
+        finished.finish(&Ok(()));
+
+        tokio::time::sleep(Duration::from_secs(59)).await;
+        assert_eq!(registry.list().len(), 2);
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let jobs = registry.list();
+        assert_eq!(jobs.len(), 1, "Only the running job should be retained");
+        assert_eq!(jobs[0].id, running.id());
+        assert!(registry.get(finished_id).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_handle_marks_job_failed() {
+        let registry = Arc::new(JobRegistry::new(Duration::from_secs(60)));
+        let handle = registry.register("synthetic", json!({}));
+        let id = handle.id();
+        drop(handle);
+
+        let info = registry.get(id).unwrap();
+        assert_eq!(info.status, JobStatus::Failed);
+        assert!(info.error.unwrap().contains("before completing"));
+    }
+}
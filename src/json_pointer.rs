@@ -0,0 +1,209 @@
+use serde_json::Value as JsonValue;
+
+/// Why a value could not be set at a JSON Pointer
+///
+/// Each variant holds the pointer to the parent that stopped the walk, with
+/// `""` meaning the document itself.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetError {
+    /// The pointer is neither empty nor starts with `/`
+    Invalid,
+    /// The parent doesn't exist and `create_parents` wasn't requested
+    MissingParent(String),
+    /// The parent is a string, number, boolean or null
+    NotAContainer(String),
+    /// The parent is an array and the token isn't an index of it or `-`
+    BadIndex { parent: String, token: String },
+}
+
+impl std::fmt::Display for SetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = |at: &str| if at.is_empty() { "the document".to_string() } else { at.to_string() };
+        match self {
+            SetError::Invalid => write!(f, "a JSON Pointer must be empty or start with '/'"),
+            SetError::MissingParent(at) => {
+                write!(f, "{} does not exist; pass create_parents=true to create it", name(at))
+            }
+            SetError::NotAContainer(at) => {
+                write!(f, "{} is not an object or array, so nothing can be set inside it", name(at))
+            }
+            SetError::BadIndex { parent, token } => write!(
+                f,
+                "{} is an array, and '{}' is not one of its indices or '-' to append",
+                name(parent),
+                token
+            ),
+        }
+    }
+}
+
+/// Decode the `~1` and `~0` escapes of one RFC 6901 reference token
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// The array index a token refers to; RFC 6901 forbids leading zeros
+fn array_index(token: &str) -> Option<usize> {
+    if !token.chars().all(|c| c.is_ascii_digit()) || (token.starts_with('0') && token != "0") {
+        return None;
+    }
+    token.parse().ok()
+}
+
+/// Set the value at an RFC 6901 JSON Pointer inside `target`
+///
+/// The empty pointer replaces the whole document. Object members are added or
+/// replaced; array elements are replaced by index, and `-` appends. Missing
+/// parents are created as empty objects when `create_parents` is set, and are
+/// an error otherwise. Nothing is changed when an error is returned.
+pub fn set(target: &mut JsonValue, pointer: &str, value: JsonValue, create_parents: bool) -> Result<(), SetError> {
+    if pointer.is_empty() {
+        *target = value;
+        return Ok(());
+    }
+    let raw: Vec<&str> = pointer.strip_prefix('/').ok_or(SetError::Invalid)?.split('/').collect();
+    let at = |depth: usize| raw[..depth].iter().map(|token| format!("/{}", token)).collect::<String>();
+
+    // Check the whole path before creating anything, so a failure leaves target as it was
+    let mut current = &*target;
+    for (depth, token) in raw.iter().enumerate().take(raw.len() - 1) {
+        let token = unescape(token);
+        let child = match current {
+            JsonValue::Object(map) => map.get(&token),
+            JsonValue::Array(_) if token == "-" => None,
+            JsonValue::Array(items) => Some(array_index(&token).and_then(|i| items.get(i)).ok_or_else(|| {
+                SetError::BadIndex { parent: at(depth), token: token.clone() }
+            })?),
+            _ => return Err(SetError::NotAContainer(at(depth))),
+        };
+        match child {
+            Some(child) => current = child,
+            None if create_parents => break,
+            None => return Err(SetError::MissingParent(at(depth + 1))),
+        }
+    }
+
+    let (last, parents) = raw.split_last().expect("split yields at least one token");
+    let mut current = target;
+    for (depth, token) in parents.iter().enumerate() {
+        let token = unescape(token);
+        current = match current {
+            JsonValue::Object(map) => map.entry(token).or_insert_with(|| JsonValue::Object(Default::default())),
+            JsonValue::Array(items) => {
+                if token == "-" {
+                    items.push(JsonValue::Object(Default::default()));
+                    items.last_mut().expect("an element was just pushed")
+                } else {
+                    array_index(&token)
+                        .and_then(|i| items.get_mut(i))
+                        .ok_or_else(|| SetError::BadIndex { parent: at(depth), token })?
+                }
+            }
+            // Existing parents were checked above, and created ones are objects
+            _ => unreachable!("parent {} is not a container", at(depth)),
+        };
+    }
+
+    let token = unescape(last);
+    match current {
+        JsonValue::Object(map) => {
+            map.insert(token, value);
+        }
+        JsonValue::Array(items) if token == "-" => items.push(value),
+        JsonValue::Array(items) => {
+            let slot = array_index(&token)
+                .and_then(|i| items.get_mut(i))
+                .ok_or_else(|| SetError::BadIndex { parent: at(parents.len()), token })?;
+            *slot = value;
+        }
+        _ => return Err(SetError::NotAContainer(at(parents.len()))),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn set_at(target: JsonValue, pointer: &str, value: JsonValue, create_parents: bool) -> Result<JsonValue, SetError> {
+        let mut target = target;
+        set(&mut target, pointer, value, create_parents)?;
+        Ok(target)
+    }
+
+    #[test]
+    fn test_root_pointer_replaces_document() {
+        assert_eq!(set_at(json!({"a": 1}), "", json!([1, 2]), false), Ok(json!([1, 2])));
+        assert_eq!(set_at(json!({"a": 1}), "a", json!(2), false), Err(SetError::Invalid));
+    }
+
+    #[test]
+    fn test_object_members() {
+        let doc = json!({"settings": {"theme": "dark"}, "a/b": 1, "m~n": 2});
+        assert_eq!(
+            set_at(doc.clone(), "/settings/theme", json!("light"), false).unwrap(),
+            json!({"settings": {"theme": "light"}, "a/b": 1, "m~n": 2})
+        );
+        assert_eq!(
+            set_at(doc.clone(), "/settings/font", json!(12), false).unwrap()["settings"],
+            json!({"theme": "dark", "font": 12})
+        );
+        let escaped = set_at(doc.clone(), "/a~1b", json!(10), false).unwrap();
+        let escaped = set_at(escaped, "/m~0n", json!(20), false).unwrap();
+        assert_eq!(escaped["a/b"], json!(10));
+        assert_eq!(escaped["m~n"], json!(20));
+    }
+
+    #[test]
+    fn test_array_elements() {
+        let doc = json!({"tags": ["a", "b"]});
+        assert_eq!(set_at(doc.clone(), "/tags/1", json!("B"), false).unwrap(), json!({"tags": ["a", "B"]}));
+        assert_eq!(set_at(doc.clone(), "/tags/-", json!("c"), false).unwrap(), json!({"tags": ["a", "b", "c"]}));
+        for token in ["2", "01", "x"] {
+            assert_eq!(
+                set_at(doc.clone(), &format!("/tags/{}", token), json!(0), false),
+                Err(SetError::BadIndex { parent: "/tags".to_string(), token: token.to_string() })
+            );
+        }
+    }
+
+    #[test]
+    fn test_missing_parents() {
+        let doc = json!({"a": {}, "list": []});
+        assert_eq!(
+            set_at(doc.clone(), "/a/b/c", json!(1), false),
+            Err(SetError::MissingParent("/a/b".to_string()))
+        );
+        assert_eq!(
+            set_at(doc.clone(), "/a/b/c", json!(1), true).unwrap(),
+            json!({"a": {"b": {"c": 1}}, "list": []})
+        );
+        assert_eq!(
+            set_at(doc, "/list/-/name", json!("x"), true).unwrap(),
+            json!({"a": {}, "list": [{"name": "x"}]})
+        );
+    }
+
+    #[test]
+    fn test_non_container_parent() {
+        let doc = json!({"a": "text", "n": null});
+        for (pointer, parent) in [("/a/b", "/a"), ("/n/b/c", "/n")] {
+            assert_eq!(
+                set_at(doc.clone(), pointer, json!(1), true),
+                Err(SetError::NotAContainer(parent.to_string()))
+            );
+        }
+        assert_eq!(set_at(json!(3), "/a", json!(1), false), Err(SetError::NotAContainer(String::new())));
+    }
+
+    #[test]
+    fn test_failed_set_changes_nothing() {
+        let mut doc = json!({"a": {"b": "text"}});
+        assert!(set(&mut doc, "/x/y/z", json!(1), false).is_err());
+        assert!(set(&mut doc, "/a/new/deeper/-", json!(1), true).is_ok());
+        let before = doc.clone();
+        assert!(set(&mut doc, "/a/b/c", json!(1), true).is_err());
+        assert_eq!(doc, before);
+    }
+}
@@ -1,22 +1,43 @@
 mod api_doc;
+mod canonical;
+mod compression;
 mod config;
+mod csv;
 mod error;
 mod handlers;
+mod jobs;
+mod json_pointer;
+mod merge_patch;
+mod metrics;
 mod models;
+mod quota;
+mod ramp;
+mod request_id;
 mod routes;
+mod singleflight;
 mod spanner;
 mod state;
+mod timeout;
+mod write_batcher;
 
-use api_doc::ApiDoc;
-use axum::{routing::get, routing::put, Router};
+use axum::{extract::DefaultBodyLimit, middleware, routing::get, routing::post, routing::put, Router};
 use config::Config;
-use handlers::{get_handler, health_handler, list_handler, put_handler};
+use handlers::{
+    batch_delete_handler, batch_get_handler, batch_put_handler, cancel_job_handler, copy_handler,
+    count_handler, create_handler, ddl_handler, delete_handler, delete_prefix_handler,
+    export_handler, get_handler, get_job_handler, head_handler, history_handler, import_handler,
+    list_handler, list_jobs_handler, liveness_handler, meta_handler, metrics_handler, move_handler,
+    patch_handler, path_handler, put_handler, put_path_handler, readiness_handler, rename_handler,
+    secondary_key_handler, stream_handler, undelete_handler,
+};
+use jobs::JobRegistry;
+// `crate::` disambiguates the module from the `metrics` crate
+use crate::metrics::{track_requests, Metrics};
 use spanner::SpannerClient;
 use state::AppState;
 use std::sync::Arc;
+use std::time::Duration;
 use tower_http::trace::TraceLayer;
-use utoipa::OpenApi;
-use utoipa_swagger_ui::SwaggerUi;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -25,26 +46,87 @@ async fn main() -> anyhow::Result<()> {
 
     tracing_subscriber::fmt::init();
 
+    // Dump the OpenAPI spec and exit without starting the server if requested
+    if let Some(path) = dump_openapi_path()? {
+        api_doc::write_openapi(&path, config::public_base_url_from_env()?.as_deref())?;
+        tracing::info!("Wrote OpenAPI document to {}", path);
+        return Ok(());
+    }
+
     tracing::info!("rust-spanner-kv starting");
 
     let config = Config::from_env()?;
+    config.validate()?;
     config.log_startup();
 
-    let spanner_client = SpannerClient::from_config(&config).await?;
+    let metrics = Metrics::new();
+    let spanner_client = SpannerClient::from_config(&config)
+        .await?
+        .with_metrics(metrics.clone());
+
+    // Prime the session pool and channels before taking traffic
+    match spanner_client.health_check().await {
+        Ok(()) => tracing::info!("Spanner warm-up query succeeded"),
+        Err(e) => tracing::warn!("Spanner warm-up query failed, starting anyway: {}", e),
+    }
 
     // Create shared application state
     let state = AppState {
         spanner_client,
         config: Arc::new(config.clone()),
+        metrics,
+        jobs: Arc::new(JobRegistry::from_config(&config)),
     };
 
-    // Build the router
-    let app = Router::new()
-        .route(routes::HEALTH, get(health_handler))
-        .route(routes::KV_LIST, get(list_handler))
-        .route(routes::KV_ITEM, put(put_handler).get(get_handler))
-        .merge(SwaggerUi::new("/swagger-ui").url("/api-doc/openapi.json", ApiDoc::openapi()))
+    // Build the router; every route times out after REQUEST_TIMEOUT_SECS except
+    // the long-poll GET, which may wait MAX_GET_WAIT_SECS on top, and the
+    // streaming export, import and stream, which run as long as the data takes
+    let request_timeout = Duration::from_secs(config.request_timeout_secs);
+    let timed = Router::new()
+        .route(routes::HEALTH, get(readiness_handler))
+        .route(routes::HEALTH_LIVE, get(liveness_handler))
+        .route(routes::HEALTH_READY, get(readiness_handler))
+        .route(routes::METRICS, get(metrics_handler))
+        .route(routes::KV_LIST, get(list_handler).delete(delete_prefix_handler))
+        .route(routes::KV_BATCH, post(batch_put_handler))
+        .route(routes::KV_BATCH_GET, post(batch_get_handler))
+        .route(routes::KV_BATCH_DELETE, post(batch_delete_handler))
+        .route(routes::KV_COUNT, get(count_handler))
+        .route(routes::KV_BY_SECONDARY_KEY, get(secondary_key_handler))
+        .route(routes::KV_RENAME, post(rename_handler))
+        .route(routes::KV_MOVE, post(move_handler))
+        .route(routes::KV_COPY, post(copy_handler))
+        .route(routes::KV_UNDELETE, post(undelete_handler))
+        .route(routes::KV_HISTORY, get(history_handler))
+        .route(routes::KV_META, get(meta_handler))
+        .route(routes::KV_PATH, get(path_handler).put(put_path_handler))
+        .route(routes::KV_PATH_ROOT, get(path_handler).put(put_path_handler))
+        .route(routes::ADMIN_DDL, get(ddl_handler))
+        .route(routes::ADMIN_JOBS, get(list_jobs_handler))
+        .route(routes::ADMIN_JOB, get(get_job_handler))
+        .route(routes::ADMIN_JOB_CANCEL, post(cancel_job_handler));
+    let long_poll = Router::new()
+        .route(routes::KV_ITEM, put(put_handler).post(create_handler).get(get_handler).head(head_handler).patch(patch_handler).delete(delete_handler));
+    let untimed = Router::new()
+        .route(routes::KV_EXPORT, get(export_handler))
+        .route(routes::KV_IMPORT, post(import_handler))
+        .route(routes::KV_STREAM, get(stream_handler));
+    let app = timeout::apply(timed, request_timeout)
+        .merge(timeout::apply(long_poll, request_timeout + Duration::from_secs(config.max_get_wait_secs)))
+        .merge(untimed)
+        .merge(api_doc::router(config.public_base_url.as_deref()));
+    let app = if config.response_compression.is_off() {
+        app
+    } else {
+        app.layer(compression::layer(config.response_compression, config.compression_min_bytes))
+    };
+    let app = app
+        .layer(compression::request_layer())
+        .layer(DefaultBodyLimit::max(config.max_body_bytes))
+        .layer(middleware::from_fn_with_state(state.clone(), track_requests))
         .layer(TraceLayer::new_for_http())
+        // Outermost, so the trace layer's own events carry the request id too
+        .layer(middleware::from_fn(request_id::propagate_request_id))
         .with_state(state.clone());
 
     // Create the server address
@@ -55,7 +137,60 @@ async fn main() -> anyhow::Result<()> {
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     tracing::info!("Server listening on {}", addr);
 
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    // Commit any writes still waiting in a batch before exiting
+    state.spanner_client.shutdown().await;
+    tracing::info!("Server shut down");
 
     Ok(())
 }
+
+/// Wait for Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => tracing::error!("Failed to listen for SIGTERM: {}", e),
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("Shutdown signal received, draining requests");
+}
+
+/// Resolve the OpenAPI dump path from `--dump-openapi <path>` or `DUMP_OPENAPI_PATH`
+///
+/// The command-line flag takes precedence over the environment variable.
+fn dump_openapi_path() -> anyhow::Result<Option<String>> {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        None => Ok(std::env::var("DUMP_OPENAPI_PATH").ok()),
+        Some("--dump-openapi") => args
+            .next()
+            .map(Some)
+            .ok_or_else(|| anyhow::anyhow!("--dump-openapi requires a path argument")),
+        Some(arg) => match arg.strip_prefix("--dump-openapi=") {
+            Some(path) => Ok(Some(path.to_string())),
+            None => anyhow::bail!("Unknown argument: {}", arg),
+        },
+    }
+}
@@ -0,0 +1,97 @@
+use serde_json::Value as JsonValue;
+
+/// Apply an RFC 7386 JSON Merge Patch to `target` in place
+///
+/// Members of an object patch are merged recursively: `null` removes the
+/// member, objects merge into existing objects, and anything else replaces the
+/// member outright. Arrays are never merged element-wise. A non-object patch
+/// replaces the whole target.
+pub fn apply(target: &mut JsonValue, patch: &JsonValue) {
+    let JsonValue::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = JsonValue::Object(Default::default());
+    }
+    let JsonValue::Object(target) = target else {
+        unreachable!("target was just made an object");
+    };
+
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            apply(target.entry(key.as_str()).or_insert(JsonValue::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn merged(target: JsonValue, patch: JsonValue) -> JsonValue {
+        let mut target = target;
+        apply(&mut target, &patch);
+        target
+    }
+
+    #[test]
+    fn test_null_removes_keys() {
+        assert_eq!(
+            merged(json!({"a": 1, "b": 2}), json!({"a": null, "c": null})),
+            json!({"b": 2})
+        );
+    }
+
+    #[test]
+    fn test_deep_nested_merge() {
+        let target = json!({
+            "title": "Hello",
+            "author": {"givenName": "John", "familyName": "Doe", "address": {"city": "Oslo", "zip": "0150"}},
+            "tags": ["example", "sample"]
+        });
+        let patch = json!({
+            "title": "Goodbye",
+            "author": {"familyName": null, "address": {"city": "Bergen"}},
+            "tags": ["example"],
+            "phone": "+01-123-456-7890"
+        });
+        assert_eq!(
+            merged(target, patch),
+            json!({
+                "title": "Goodbye",
+                "author": {"givenName": "John", "address": {"city": "Bergen", "zip": "0150"}},
+                "tags": ["example"],
+                "phone": "+01-123-456-7890"
+            })
+        );
+    }
+
+    #[test]
+    fn test_rfc7386_examples() {
+        // Test cases from RFC 7386 Appendix A
+        let cases = [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "b"}), json!({"b": "c"}), json!({"a": "b", "b": "c"})),
+            (json!({"a": "b"}), json!({"a": null}), json!({})),
+            (json!({"a": "b", "b": "c"}), json!({"a": null}), json!({"b": "c"})),
+            (json!({"a": ["b"]}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "c"}), json!({"a": ["b"]}), json!({"a": ["b"]})),
+            (json!({"a": {"b": "c"}}), json!({"a": {"b": "d", "c": null}}), json!({"a": {"b": "d"}})),
+            (json!({"a": [{"b": "c"}]}), json!({"a": [1]}), json!({"a": [1]})),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({"a": "b"}), json!(["c"]), json!(["c"])),
+            (json!({"a": "foo"}), json!(null), json!(null)),
+            (json!({"a": "foo"}), json!("bar"), json!("bar")),
+            (json!({"e": null}), json!({"a": 1}), json!({"e": null, "a": 1})),
+            (json!([1, 2]), json!({"a": "b", "c": null}), json!({"a": "b"})),
+            (json!({}), json!({"a": {"bb": {"ccc": null}}}), json!({"a": {"bb": {}}})),
+        ];
+        for (target, patch, expected) in cases {
+            assert_eq!(merged(target.clone(), patch.clone()), expected, "{} + {}", target, patch);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::jobs::JobCounts;
+use crate::state::AppState;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle, PrometheusRecorder};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Counter of handled HTTP requests, labelled by handler and outcome
+pub const REQUESTS_TOTAL: &str = "kv_requests_total";
+
+/// Counter of reads that joined an identical read already in flight
+pub const READS_COALESCED_TOTAL: &str = "kv_reads_coalesced_total";
+
+/// Gauge of retained background jobs, labelled by state
+pub const JOBS: &str = "kv_jobs";
+
+/// Histogram of Spanner call latency in seconds, labelled by operation
+pub const SPANNER_CALL_SECONDS: &str = "kv_spanner_call_duration_seconds";
+
+/// Bucket bounds for [`SPANNER_CALL_SECONDS`], from 1ms to 10s
+const SPANNER_CALL_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 10.0,
+];
+
+/// Prometheus metrics for the service
+///
+/// Each instance owns its own recorder rather than installing a global one, so
+/// tests and multiple app instances in one process don't share counts.
+#[derive(Clone)]
+pub struct Metrics {
+    recorder: Arc<PrometheusRecorder>,
+    handle: PrometheusHandle,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Full(SPANNER_CALL_SECONDS.to_string()), &SPANNER_CALL_BUCKETS)
+            .expect("Spanner call buckets are non-empty")
+            .build_recorder();
+        let handle = recorder.handle();
+
+        Self {
+            recorder: Arc::new(recorder),
+            handle,
+        }
+    }
+
+    /// Count one handled request
+    pub fn record_request(&self, handler: &str, outcome: &'static str) {
+        let handler = handler.to_string();
+        ::metrics::with_local_recorder(self.recorder.as_ref(), || {
+            ::metrics::counter!(REQUESTS_TOTAL, "handler" => handler, "outcome" => outcome).increment(1);
+        });
+    }
+
+    /// Count one read served by a read already in flight
+    pub fn record_coalesced_read(&self) {
+        ::metrics::with_local_recorder(self.recorder.as_ref(), || {
+            ::metrics::counter!(READS_COALESCED_TOTAL).increment(1);
+        });
+    }
+
+    /// Set the jobs gauge from a snapshot of the job registry
+    pub fn record_job_counts(&self, counts: &JobCounts) {
+        ::metrics::with_local_recorder(self.recorder.as_ref(), || {
+            for (state, count) in [
+                ("running", counts.running),
+                ("completed", counts.completed),
+                ("cancelled", counts.cancelled),
+                ("failed", counts.failed),
+            ] {
+                ::metrics::gauge!(JOBS, "state" => state).set(count as f64);
+            }
+        });
+    }
+
+    /// Record how long one Spanner operation took
+    pub fn record_spanner_call(&self, op: &'static str, elapsed: Duration) {
+        ::metrics::with_local_recorder(self.recorder.as_ref(), || {
+            ::metrics::histogram!(SPANNER_CALL_SECONDS, "op" => op).record(elapsed.as_secs_f64());
+        });
+    }
+
+    /// Start timing a Spanner operation; the latency is recorded when the timer drops
+    pub fn time_spanner_call(&self, op: &'static str) -> SpannerCallTimer {
+        SpannerCallTimer {
+            metrics: self.clone(),
+            op,
+            started: Instant::now(),
+        }
+    }
+
+    /// Render every metric in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        self.handle.run_upkeep();
+        self.handle.render()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Records a Spanner call's latency when dropped, so failed calls are timed too
+pub struct SpannerCallTimer {
+    metrics: Metrics,
+    op: &'static str,
+    started: Instant,
+}
+
+impl Drop for SpannerCallTimer {
+    fn drop(&mut self) {
+        self.metrics.record_spanner_call(self.op, self.started.elapsed());
+    }
+}
+
+/// Outcome label for a response status
+fn outcome(status: axum::http::StatusCode) -> &'static str {
+    if status.is_server_error() {
+        "server_error"
+    } else if status.is_client_error() {
+        "client_error"
+    } else {
+        "success"
+    }
+}
+
+/// Middleware counting every request by route and outcome
+///
+/// The handler label is the method and route template, e.g. `GET /kv/{id}`, so
+/// ids don't create a series per key. Requests matching no route are counted
+/// as `unmatched`.
+pub async fn track_requests(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let handler = match request.extensions().get::<MatchedPath>() {
+        Some(path) => format!("{} {}", request.method(), path.as_str()),
+        None => "unmatched".to_string(),
+    };
+
+    let response = next.run(request).await;
+    state.metrics.record_request(&handler, outcome(response.status()));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    #[test]
+    fn test_render_counts_requests_and_latency() {
+        let metrics = Metrics::new();
+        metrics.record_request("GET /kv/{id}", "success");
+        metrics.record_request("GET /kv/{id}", "success");
+        metrics.record_spanner_call("read", Duration::from_millis(3));
+        metrics.record_coalesced_read();
+
+        let body = metrics.render();
+        assert!(
+            body.contains(r#"kv_requests_total{handler="GET /kv/{id}",outcome="success"} 2"#),
+            "{}",
+            body
+        );
+        assert!(body.contains(r#"kv_spanner_call_duration_seconds_bucket{op="read",le="0.005"} 1"#), "{}", body);
+        assert!(body.contains(r#"kv_spanner_call_duration_seconds_count{op="read"} 1"#), "{}", body);
+        assert!(body.contains("kv_reads_coalesced_total 1"), "{}", body);
+
+        metrics.record_job_counts(&JobCounts {
+            running: 2,
+            failed: 1,
+            ..JobCounts::default()
+        });
+        let body = metrics.render();
+        assert!(body.contains(r#"kv_jobs{state="running"} 2"#), "{}", body);
+        assert!(body.contains(r#"kv_jobs{state="failed"} 1"#), "{}", body);
+        assert!(body.contains(r#"kv_jobs{state="completed"} 0"#), "{}", body);
+
+        drop(metrics.time_spanner_call("upsert"));
+        assert!(metrics.render().contains(r#"kv_spanner_call_duration_seconds_count{op="upsert"} 1"#));
+
+        // Separate instances don't share counts
+        assert!(!Metrics::new().render().contains(REQUESTS_TOTAL));
+    }
+
+    #[test]
+    fn test_outcome() {
+        assert_eq!(outcome(StatusCode::OK), "success");
+        assert_eq!(outcome(StatusCode::NOT_FOUND), "client_error");
+        assert_eq!(outcome(StatusCode::SERVICE_UNAVAILABLE), "server_error");
+    }
+}
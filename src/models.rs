@@ -1,10 +1,98 @@
 use serde::{Deserialize, Serialize};
+use crate::jobs::{JobCounts, JobInfo};
+use crate::spanner::{KvEntry, MatchMode, Sort};
 use serde_json::Value as JsonValue;
 
 /// Response type for successful PUT operations
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 pub struct PutResponse {
     pub id: String,
+    /// Version the write stored, counting up from 1 for a new document
+    pub version: i64,
+}
+
+/// Response type for a write sent with `?dry_run=true`, which validates but stores nothing
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DryRunResponse {
+    pub dry_run: bool,
+    /// Always true: an invalid request fails with the same error a real write would
+    pub valid: bool,
+}
+
+/// One document in a batch PUT request
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BatchPutEntry {
+    pub id: String,
+    pub data: JsonValue,
+}
+
+/// Outcome of one entry in a batch PUT
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchEntryStatus {
+    /// Committed
+    Written,
+    /// In the chunk whose commit failed; nothing in that chunk was written
+    Failed,
+    /// After the failed chunk, so never sent to Spanner
+    NotAttempted,
+}
+
+/// Status of one entry in a batch PUT, in request order
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BatchPutResult {
+    pub id: String,
+    pub status: BatchEntryStatus,
+}
+
+/// Response type for batch PUT operations
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BatchPutResponse {
+    /// Number of documents written
+    pub written: usize,
+    pub results: Vec<BatchPutResult>,
+    /// Why the batch stopped early, when it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Request body for a batch GET
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BatchGetRequest {
+    pub ids: Vec<String>,
+}
+
+/// Response type for batch GET operations
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BatchGetResponse {
+    /// Documents that exist, in request order
+    pub found: Vec<GetResponse>,
+    /// Requested ids with no document, in request order
+    pub missing: Vec<String>,
+}
+
+/// Request body for a batch DELETE
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BatchDeleteRequest {
+    pub ids: Vec<String>,
+}
+
+/// Response type for batch DELETE operations
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BatchDeleteResponse {
+    /// Keys that had a live document, and so were actually deleted; with
+    /// `hard=true`, soft-deleted documents count too
+    pub existed: usize,
+    /// Distinct requested ids the delete was applied to
+    pub applied: usize,
+}
+
+/// Response type for successful DELETE operations
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeleteResponse {
+    /// Key of the deleted document
+    pub id: String,
+    pub deleted: bool,
 }
 
 /// Response type for successful GET operations
@@ -12,6 +100,137 @@ pub struct PutResponse {
 pub struct GetResponse {
     pub id: String,
     pub data: JsonValue,
+    /// Same value as the `ETag` header, for clients that don't read headers;
+    /// absent where the version isn't known (PATCH and secondary-key lookups)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// Incremented on every write; absent for secondary-key lookups
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    /// When the document was soft-deleted; only returned with `include_deleted=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+    /// RFC 3339 timestamp of the document's first write; absent where it isn't
+    /// known (PATCH and secondary-key lookups)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    /// RFC 3339 commit timestamp of the document's last write; absent with `created_at`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+/// Response type for the metadata endpoint
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct KvMetaResponse {
+    pub id: String,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Size of the stored JSON in bytes
+    pub size_bytes: i64,
+}
+
+/// Query parameters for a path write
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct PathPutQuery {
+    /// Create missing parent objects along the pointer
+    pub create_parents: Option<bool>,
+}
+
+/// Request body for the rename endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct RenameRequest {
+    pub new_id: String,
+}
+
+/// Request body for the copy endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CopyRequest {
+    pub target_id: String,
+}
+
+/// Query parameters for the copy endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CopyQuery {
+    /// Replace a document already stored at `target_id`
+    pub overwrite: Option<bool>,
+}
+
+/// Response type for a successful copy
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CopyResponse {
+    /// Key the copy was written to
+    pub id: String,
+    pub source_id: String,
+    /// Size of the copied document's serialized JSON
+    pub bytes: usize,
+    /// Version the copy was written as
+    pub version: i64,
+}
+
+/// Query parameters for the delete endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct DeleteQuery {
+    /// Remove the document permanently instead of soft-deleting it
+    pub hard: Option<bool>,
+}
+
+/// Response type for a successful undelete
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct UndeleteResponse {
+    /// Key of the restored document
+    pub id: String,
+    pub restored: bool,
+}
+
+/// Request body for the move endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct MoveRequest {
+    pub target_id: String,
+}
+
+/// Query parameters for the move endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct MoveQuery {
+    /// Replace a document already stored at `target_id`
+    pub overwrite: Option<bool>,
+}
+
+/// Response type for a successful rename or move
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RenameResponse {
+    pub id: String,
+    pub previous_id: String,
+}
+
+/// Query parameters for put endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct PutQuery {
+    /// Expire the document this many seconds after the write
+    pub ttl_seconds: Option<i64>,
+    /// Only write if the stored document is at this version
+    pub expected_version: Option<i64>,
+    /// Validate the request without storing anything
+    pub dry_run: Option<bool>,
+}
+
+/// Query parameters for the batch put endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct BatchPutQuery {
+    /// Validate every entry without storing anything
+    pub dry_run: Option<bool>,
+}
+
+/// Query parameters for get endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct GetQuery {
+    /// How long to wait for a missing key to appear, e.g. `5s`
+    pub wait: Option<String>,
+    /// Accept a snapshot up to this many milliseconds old instead of a strong read
+    pub max_staleness_ms: Option<i64>,
+    /// Also return a soft-deleted document (admin only)
+    pub include_deleted: Option<bool>,
+    /// Comma-separated dotted paths to return instead of the whole document
+    pub fields: Option<String>,
 }
 
 /// Query parameters for list endpoint
@@ -20,14 +239,149 @@ pub struct ListQuery {
     pub limit: Option<u32>,
     pub offset: Option<u32>,
     pub prefix: Option<String>,
-    pub sort: Option<String>,
+    /// How `prefix` matches keys: `prefix` (default), `suffix`, `contains` or `exact`
+    #[serde(rename = "match")]
+    pub match_mode: Option<MatchMode>,
+    pub sort: Option<Sort>,
+    /// Only rows updated after this RFC 3339 timestamp (incremental sync)
+    pub updated_since: Option<String>,
+    /// Resume a sync within `updated_since` after this key
+    pub after_key: Option<String>,
+    /// Case-insensitive substring search across the document's JSON
+    pub q: Option<String>,
+    /// Also list soft-deleted documents (admin only)
+    pub include_deleted: Option<bool>,
+    /// Only rows created at or after this RFC 3339 timestamp
+    pub created_after: Option<String>,
+    /// Only rows created before this RFC 3339 timestamp
+    pub created_before: Option<String>,
+    /// Only rows last updated at or after this RFC 3339 timestamp
+    pub updated_after: Option<String>,
+    /// Only rows last updated before this RFC 3339 timestamp
+    pub updated_before: Option<String>,
+    /// Resume after the page that returned this `next_page_token`
+    pub page_token: Option<String>,
+    /// Count every matching row into `total_count` (default true)
+    pub include_count: Option<bool>,
+    /// Response format: `json` (default) or `csv`
+    pub format: Option<String>,
+}
+
+/// Query parameters for the delete-by-prefix endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct DeletePrefixQuery {
+    pub prefix: Option<String>,
+    /// Only count what would be deleted
+    pub dry_run: Option<bool>,
+}
+
+/// Response type for delete-by-prefix operations
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeletePrefixResponse {
+    pub prefix: String,
+    /// Rows deleted, or with `dry_run` the rows that would be
+    pub deleted: i64,
+    pub dry_run: bool,
+}
+
+/// Query parameters for the streaming list endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct StreamQuery {
+    pub prefix: Option<String>,
+    pub sort: Option<Sort>,
+    /// Most rows to stream; all of them when unset
+    pub limit: Option<u64>,
+}
+
+/// Query parameters for export endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ExportQuery {
+    pub format: Option<String>,
+    pub prefix: Option<String>,
+}
+
+/// Query parameters for the import endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ImportQuery {
+    /// `overwrite` (default) or `skip_existing`
+    pub mode: Option<String>,
+    /// Stop at the first malformed line instead of reporting it and continuing
+    pub strict: Option<bool>,
+}
+
+/// A line of an import that was not loaded
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ImportLineError {
+    /// 1-based line number in the request body
+    pub line: usize,
+    pub error: String,
+}
+
+/// Response type for the import endpoint
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ImportResponse {
+    /// Documents written
+    pub imported: usize,
+    /// Documents left alone because their key already held one, with `mode=skip_existing`
+    pub skipped: usize,
+    /// Malformed lines; only the first 100 are listed, `failed` counts them all
+    pub errors: Vec<ImportLineError>,
+    pub failed: usize,
+    /// Why the import stopped before the end of the body, when it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// One line of an NDJSON export
+///
+/// `id` and `data` match a `POST /kv:batch` entry, so an export can be
+/// restored by posting its lines back.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ExportRecord {
+    pub id: String,
+    pub data: JsonValue,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<KvEntry> for ExportRecord {
+    /// Timestamps are rendered as RFC 3339
+    fn from(entry: KvEntry) -> Self {
+        ExportRecord {
+            id: entry.key,
+            data: entry.value,
+            created_at: entry.created_at.to_rfc3339(),
+            updated_at: entry.updated_at.to_rfc3339(),
+        }
+    }
 }
 
 /// Response type for list endpoint
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ListResponse {
     pub data: Vec<KvEntryResponse>,
-    pub total_count: i64,
+    /// Rows matching the filters across all pages; omitted with `include_count=false`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_count: Option<i64>,
+    /// Whether more rows follow this page
+    pub has_more: bool,
+    /// Page size applied: `limit`, or `DEFAULT_LIMIT` without one
+    pub limit: i64,
+    /// Pass as `updated_since` on the next sync (only with `updated_since`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync_timestamp: Option<String>,
+    /// Pass as `after_key` on the next sync; set when more changes remain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync_after_key: Option<String>,
+    /// Pass as `page_token` for the next page; set when more rows follow (not with `updated_since`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+    /// Relative URL of the next page, e.g. `/kv?limit=10&offset=20&sort=key_asc`; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    /// Relative URL of the previous page; absent on the first page and after a `page_token`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
 }
 
 /// Individual key-value entry in list response
@@ -37,4 +391,85 @@ pub struct KvEntryResponse {
     pub value: JsonValue,
     pub created_at: String,
     pub updated_at: String,
+    /// SHA-256 of the canonical (RFC 8785) form of `value`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// When the document expires, if it was stored with a TTL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    /// Incremented on every write; 0 for rows last written before versions were tracked
+    pub version: i64,
+    /// When the document was soft-deleted; only listed with `include_deleted=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+}
+
+impl From<KvEntry> for KvEntryResponse {
+    /// Timestamps are rendered as RFC 3339
+    fn from(entry: KvEntry) -> Self {
+        KvEntryResponse {
+            key: entry.key,
+            value: entry.value,
+            created_at: entry.created_at.to_rfc3339(),
+            updated_at: entry.updated_at.to_rfc3339(),
+            content_hash: entry.content_hash,
+            expires_at: entry.expires_at.map(|expires_at| expires_at.to_rfc3339()),
+            version: entry.version,
+            deleted_at: entry.deleted_at.map(|deleted_at| deleted_at.to_rfc3339()),
+        }
+    }
+}
+
+/// Query parameters for the count endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CountQuery {
+    pub prefix: Option<String>,
+}
+
+/// Response type for the count endpoint
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CountResponse {
+    pub count: i64,
+}
+
+/// Query parameters for the history endpoint
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct HistoryQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+/// Response type for the history endpoint
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct HistoryResponse {
+    pub id: String,
+    /// Retained versions, newest first
+    pub entries: Vec<HistoryEntryResponse>,
+    /// Versions retained for the document, across all pages
+    pub total_count: i64,
+}
+
+/// One retained version of a document
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct HistoryEntryResponse {
+    pub version: i64,
+    pub data: JsonValue,
+    /// RFC 3339 commit timestamp of the write that produced this version
+    pub committed_at: String,
+}
+
+/// Response type for the admin job list endpoint
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct JobListResponse {
+    pub jobs: Vec<JobInfo>,
+    pub counts: JobCounts,
+}
+
+/// Response type for the deployed schema endpoint
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DdlResponse {
+    /// Database dialect, e.g. `GOOGLE_STANDARD_SQL` or `POSTGRESQL`
+    pub dialect: String,
+    /// DDL statements as deployed
+    pub statements: Vec<String>,
 }
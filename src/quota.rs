@@ -0,0 +1,137 @@
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How long a document count is trusted before the store is counted again
+const COUNT_CACHE_TTL: Duration = Duration::from_secs(5);
+
+struct CachedCount {
+    count: u64,
+    counted_at: Instant,
+}
+
+/// Caps the number of documents in the store
+///
+/// The total is counted at most once per [`COUNT_CACHE_TTL`], and each admitted
+/// insert is added to the cached total straight away, so a burst of new keys
+/// between counts cannot run far past the limit. The limit is still soft: writes
+/// racing across instances, or within one count, may overshoot it slightly.
+pub struct DocumentQuota {
+    max_documents: u64,
+    cached: Mutex<Option<CachedCount>>,
+}
+
+impl DocumentQuota {
+    pub fn new(max_documents: u64) -> Self {
+        Self {
+            max_documents,
+            cached: Mutex::new(None),
+        }
+    }
+
+    pub fn max_documents(&self) -> u64 {
+        self.max_documents
+    }
+
+    /// Admit one more document if the store is below the limit
+    ///
+    /// `count` is only called when the cached total is missing or stale. An
+    /// admitted document is counted immediately, whether or not its write succeeds.
+    pub async fn admit<F, Fut>(&self, count: F) -> anyhow::Result<bool>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<u64>>,
+    {
+        self.admit_many(1, count).await
+    }
+
+    /// Admit `new_documents` more documents if they all fit under the limit
+    ///
+    /// All or nothing: when they don't all fit, none are counted.
+    pub async fn admit_many<F, Fut>(&self, new_documents: u64, count: F) -> anyhow::Result<bool>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<u64>>,
+    {
+        if self.fresh_count().is_none() {
+            let counted = count().await?;
+            *self.cached.lock().unwrap() = Some(CachedCount {
+                count: counted,
+                counted_at: Instant::now(),
+            });
+        }
+
+        let mut cached = self.cached.lock().unwrap();
+        let Some(cached) = cached.as_mut() else {
+            return Ok(false);
+        };
+        if cached.count.saturating_add(new_documents) > self.max_documents {
+            return Ok(false);
+        }
+        cached.count += new_documents;
+        Ok(true)
+    }
+
+    fn fresh_count(&self) -> Option<u64> {
+        self.cached
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|cached| cached.counted_at.elapsed() < COUNT_CACHE_TTL)
+            .map(|cached| cached.count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_admits_until_limit() {
+        let quota = DocumentQuota::new(3);
+        let counts = AtomicUsize::new(0);
+        let count = || async {
+            counts.fetch_add(1, Ordering::SeqCst);
+            Ok(1)
+        };
+
+        assert!(quota.admit(count).await.unwrap());
+        assert!(quota.admit(count).await.unwrap());
+        assert!(!quota.admit(count).await.unwrap(), "Third document exceeds the limit");
+        assert_eq!(counts.load(Ordering::SeqCst), 1, "The count should be cached");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_admit_many_is_all_or_nothing() {
+        let quota = DocumentQuota::new(10);
+        let count = || async { Ok(7) };
+
+        assert!(!quota.admit_many(4, count).await.unwrap(), "Four more would exceed the limit");
+        assert!(quota.admit_many(3, count).await.unwrap());
+        assert!(!quota.admit(count).await.unwrap(), "The three admitted documents count");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_recounts_after_ttl() {
+        let quota = DocumentQuota::new(10);
+        let stored = AtomicUsize::new(10);
+        let count = || async { Ok(stored.load(Ordering::SeqCst) as u64) };
+
+        assert!(!quota.admit(count).await.unwrap());
+
+        // Documents deleted elsewhere are noticed once the cached count expires
+        stored.store(4, Ordering::SeqCst);
+        assert!(!quota.admit(count).await.unwrap());
+        tokio::time::sleep(COUNT_CACHE_TTL).await;
+        assert!(quota.admit(count).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_count_error_is_returned() {
+        let quota = DocumentQuota::new(10);
+        let result = quota.admit(|| async { Err(anyhow::anyhow!("count failed")) }).await;
+        assert!(result.unwrap_err().to_string().contains("count failed"));
+    }
+}
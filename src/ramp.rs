@@ -0,0 +1,117 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Number of times the concurrency limit doubles before it is lifted
+const RAMP_STEPS: u32 = 8;
+
+/// Limits concurrent Spanner operations for a period after startup
+///
+/// The limit starts at `initial` and doubles at evenly spaced intervals, slow-start
+/// style, so load on a cold instance grows gradually. Once the ramp duration has
+/// elapsed the semaphore is closed, which releases every waiter and turns
+/// [`ConnectionRamp::acquire`] into a no-op.
+pub struct ConnectionRamp {
+    semaphore: Arc<Semaphore>,
+}
+
+impl ConnectionRamp {
+    /// Start a ramp that lifts its limit after `duration`
+    pub fn start(duration: Duration, initial: usize) -> Self {
+        let initial = initial.max(1);
+        let semaphore = Arc::new(Semaphore::new(initial));
+        tokio::spawn(run_ramp(semaphore.clone(), duration, initial));
+        Self { semaphore }
+    }
+
+    /// Wait for a slot while the ramp is active
+    ///
+    /// Returns `None` once the limit has been lifted; otherwise the permit must be
+    /// held for the duration of the operation.
+    pub async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        // A closed semaphore means the ramp has finished
+        self.semaphore.acquire().await.ok()
+    }
+
+    /// Whether the ramp is still limiting concurrency
+    #[cfg(test)]
+    pub fn is_active(&self) -> bool {
+        !self.semaphore.is_closed()
+    }
+}
+
+/// Raise the limit step by step, then lift it entirely
+async fn run_ramp(semaphore: Arc<Semaphore>, duration: Duration, initial: usize) {
+    let step = duration / RAMP_STEPS;
+    let mut limit = initial;
+
+    for _ in 1..RAMP_STEPS {
+        tokio::time::sleep(step).await;
+        semaphore.add_permits(limit);
+        limit *= 2;
+        tracing::debug!("Spanner concurrency ramp raised limit to {}", limit);
+    }
+
+    tokio::time::sleep(step).await;
+    semaphore.close();
+    tracing::info!("Spanner concurrency ramp complete, limit lifted");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Run `tasks` operations through the ramp, returning the peak concurrency seen
+    async fn peak_concurrency(ramp: Arc<ConnectionRamp>, tasks: usize) -> usize {
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let mut handles = Vec::new();
+        for _ in 0..tasks {
+            let ramp = ramp.clone();
+            let active = active.clone();
+            let peak = peak.clone();
+            handles.push(tokio::spawn(async move {
+                let _permit = ramp.acquire().await;
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        peak.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ramp_limits_concurrency_at_startup() {
+        let ramp = Arc::new(ConnectionRamp::start(Duration::from_secs(60), 2));
+
+        assert_eq!(peak_concurrency(ramp.clone(), 20).await, 2);
+        assert!(ramp.is_active());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ramp_raises_limit_over_time() {
+        let ramp = Arc::new(ConnectionRamp::start(Duration::from_secs(80), 2));
+
+        // After two steps of 10s the limit has doubled twice
+        tokio::time::sleep(Duration::from_secs(25)).await;
+        assert_eq!(peak_concurrency(ramp, 50).await, 8);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ramp_lifts_limit_after_duration() {
+        let ramp = Arc::new(ConnectionRamp::start(Duration::from_secs(8), 1));
+        assert!(ramp.acquire().await.is_some());
+
+        tokio::time::sleep(Duration::from_secs(9)).await;
+        assert!(!ramp.is_active());
+        assert!(ramp.acquire().await.is_none(), "No permit is needed once the ramp ends");
+        assert_eq!(peak_concurrency(ramp, 50).await, 50);
+    }
+}
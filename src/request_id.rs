@@ -0,0 +1,87 @@
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Header carrying the request's correlation id, in both directions
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied id that is kept; longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The client's request id if it is usable, otherwise a fresh UUID
+fn request_id(request: &Request) -> HeaderValue {
+    request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.to_str().is_ok())
+        .cloned()
+        .unwrap_or_else(|| {
+            HeaderValue::from_str(&Uuid::new_v4().to_string()).expect("a UUID is a valid header value")
+        })
+}
+
+/// Middleware tagging each request with a correlation id
+///
+/// Everything logged while handling the request, including the handlers'
+/// own log lines, is inside a `request` span carrying `request_id`. The id is
+/// echoed back in the `X-Request-Id` response header.
+pub async fn propagate_request_id(request: Request, next: Next) -> Response {
+    let id = request_id(&request);
+    let span = tracing::info_span!(
+        "request",
+        request_id = id.to_str().unwrap_or_default(),
+    );
+
+    let mut response = next.run(request).instrument(span).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER.clone(), id);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|| async { StatusCode::OK }))
+            .layer(middleware::from_fn(propagate_request_id))
+    }
+
+    #[tokio::test]
+    async fn test_echoes_client_request_id() {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", "client-id-42")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-request-id"], "client-id-42");
+    }
+
+    #[tokio::test]
+    async fn test_generates_missing_or_unusable_request_id() {
+        for sent in [None, Some("x".repeat(MAX_REQUEST_ID_LEN + 1))] {
+            let mut builder = Request::builder().uri("/");
+            if let Some(sent) = &sent {
+                builder = builder.header("x-request-id", sent);
+            }
+            let response = app().oneshot(builder.body(Body::empty()).unwrap()).await.unwrap();
+
+            let id = response.headers()["x-request-id"].to_str().unwrap();
+            assert!(Uuid::parse_str(id).is_ok(), "expected a generated UUID, got {}", id);
+        }
+    }
+}
@@ -1,5 +1,29 @@
 // Route path constants - single source of truth for all API paths
 
 pub const HEALTH: &str = "/health";
+pub const HEALTH_LIVE: &str = "/health/live";
+pub const HEALTH_READY: &str = "/health/ready";
+pub const METRICS: &str = "/metrics";
+pub const OPENAPI: &str = "/openapi.json";
 pub const KV_LIST: &str = "/kv";
 pub const KV_ITEM: &str = "/kv/{id}";
+pub const KV_BATCH: &str = "/kv:batch";
+pub const KV_BATCH_GET: &str = "/kv:batchGet";
+pub const KV_BATCH_DELETE: &str = "/kv:batchDelete";
+pub const KV_COUNT: &str = "/kv:count";
+pub const KV_BY_SECONDARY_KEY: &str = "/kv/by/{value}";
+pub const KV_EXPORT: &str = "/kv/export";
+pub const KV_IMPORT: &str = "/kv/import";
+pub const KV_STREAM: &str = "/kv/stream";
+pub const KV_RENAME: &str = "/kv/{id}/rename";
+pub const KV_MOVE: &str = "/kv/{id}/move";
+pub const KV_COPY: &str = "/kv/{id}/copy";
+pub const KV_UNDELETE: &str = "/kv/{id}/undelete";
+pub const KV_HISTORY: &str = "/kv/{id}/history";
+pub const KV_META: &str = "/kv/{id}/meta";
+pub const KV_PATH: &str = "/kv/{id}/path/{*pointer}";
+pub const KV_PATH_ROOT: &str = "/kv/{id}/path";
+pub const ADMIN_DDL: &str = "/admin/ddl";
+pub const ADMIN_JOBS: &str = "/admin/jobs";
+pub const ADMIN_JOB: &str = "/admin/jobs/{id}";
+pub const ADMIN_JOB_CANCEL: &str = "/admin/jobs/{id}/cancel";
@@ -0,0 +1,246 @@
+use crate::metrics::Metrics;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+/// Shared outcome of a coalesced call
+///
+/// Errors are wrapped in an `Arc` so every waiter can receive the same failure.
+pub type SharedResult<V> = Result<V, Arc<anyhow::Error>>;
+
+/// Coalesces concurrent calls for the same key into a single execution
+///
+/// While a call for a key is in flight, further callers for that key wait for
+/// its result instead of starting their own. Once the call completes the key is
+/// removed, so neither values nor errors are cached beyond the in-flight window.
+/// Writers call [`SingleFlight::forget`] once they commit, so no caller that
+/// starts after a write joins a call that may have seen the data before it.
+pub struct SingleFlight<K, V> {
+    in_flight: Mutex<HashMap<K, Arc<OnceCell<SharedResult<V>>>>>,
+    coalesced: AtomicU64,
+    metrics: Option<Metrics>,
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+            coalesced: AtomicU64::new(0),
+            metrics: None,
+        }
+    }
+
+    /// Count joined calls in `metrics` as `kv_reads_coalesced_total`
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Run `f` for `key`, or join an identical call that is already in flight
+    ///
+    /// If the caller driving the call is cancelled, one of the waiters takes over
+    /// and runs `f` itself, so waiters never hang on an abandoned call.
+    pub async fn run<F, Fut>(&self, key: K, f: F) -> SharedResult<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<V>>,
+    {
+        let cell = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(&key) {
+                Some(cell) => {
+                    let coalesced = self.coalesced.fetch_add(1, Ordering::Relaxed) + 1;
+                    if let Some(metrics) = &self.metrics {
+                        metrics.record_coalesced_read();
+                    }
+                    tracing::debug!("Joined in-flight call ({} coalesced so far)", coalesced);
+                    cell.clone()
+                }
+                None => {
+                    let cell = Arc::new(OnceCell::new());
+                    in_flight.insert(key.clone(), cell.clone());
+                    cell
+                }
+            }
+        };
+
+        let result = cell
+            .get_or_init(|| async { f().await.map_err(Arc::new) })
+            .await
+            .clone();
+
+        // Remove the entry unless it has already been replaced by a newer call
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight.get(&key).is_some_and(|current| Arc::ptr_eq(current, &cell)) {
+            in_flight.remove(&key);
+        }
+
+        result
+    }
+
+    /// Stop new callers for `key` from joining the call in flight, if any
+    ///
+    /// The call itself carries on and its current waiters still get its result.
+    pub fn forget<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.in_flight.lock().unwrap().remove(key);
+    }
+
+    /// [`SingleFlight::forget`] every key matching `predicate`
+    pub fn forget_matching(&self, predicate: impl Fn(&K) -> bool) {
+        self.in_flight.lock().unwrap().retain(|key, _| !predicate(key));
+    }
+
+    /// Number of calls that joined an in-flight call instead of running their own
+    #[cfg(test)]
+    pub fn coalesced_count(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+
+    /// Number of keys with a call currently in flight
+    #[cfg(test)]
+    pub fn in_flight_count(&self) -> usize {
+        self.in_flight.lock().unwrap().len()
+    }
+}
+
+impl<K, V> Default for SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::sync::Barrier;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_execution() {
+        let group = Arc::new(SingleFlight::<String, u32>::new());
+        let calls = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(100));
+
+        let mut handles = Vec::new();
+        for _ in 0..100 {
+            let group = group.clone();
+            let calls = calls.clone();
+            let barrier = barrier.clone();
+            handles.push(tokio::spawn(async move {
+                barrier.wait().await;
+                group
+                    .run("hot-key".to_string(), || async {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                        Ok(42)
+                    })
+                    .await
+            }));
+        }
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().unwrap(), 42);
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1, "Backend should be called exactly once");
+        assert_eq!(group.coalesced_count(), 99);
+        assert_eq!(group.in_flight_count(), 0, "Entry should be removed after completion");
+    }
+
+    #[tokio::test]
+    async fn test_errors_propagate_to_all_waiters_without_caching() {
+        let group = Arc::new(SingleFlight::<String, u32>::new());
+        let barrier = Arc::new(Barrier::new(10));
+
+        let mut handles = Vec::new();
+        for _ in 0..10 {
+            let group = group.clone();
+            let barrier = barrier.clone();
+            handles.push(tokio::spawn(async move {
+                barrier.wait().await;
+                group
+                    .run("failing-key".to_string(), || async {
+                        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+                        Err(anyhow::anyhow!("backend unavailable"))
+                    })
+                    .await
+            }));
+        }
+
+        for handle in handles {
+            let err = handle.await.unwrap().unwrap_err();
+            assert!(err.to_string().contains("backend unavailable"));
+        }
+
+        // The failure is not cached - the next call runs again
+        let result = group.run("failing-key".to_string(), || async { Ok(7) }).await;
+        assert_eq!(result.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_forgotten_call_is_not_joined() {
+        let group = Arc::new(SingleFlight::<String, u32>::new());
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let stale = tokio::spawn({
+            let group = group.clone();
+            async move {
+                group
+                    .run("key".to_string(), || async {
+                        started_tx.send(()).unwrap();
+                        release_rx.await.unwrap();
+                        Ok(1)
+                    })
+                    .await
+            }
+        });
+        started_rx.await.unwrap();
+
+        group.forget("key");
+        assert_eq!(group.run("key".to_string(), || async { Ok(2) }).await.unwrap(), 2);
+        assert_eq!(group.coalesced_count(), 0);
+
+        release_tx.send(()).unwrap();
+        assert_eq!(stale.await.unwrap().unwrap(), 1);
+        assert_eq!(group.in_flight_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_different_keys_are_not_coalesced() {
+        let group = SingleFlight::<String, u32>::new();
+        let calls = AtomicUsize::new(0);
+
+        let (a, b) = tokio::join!(
+            group.run("a".to_string(), || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(1)
+            }),
+            group.run("b".to_string(), || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(2)
+            }),
+        );
+
+        assert_eq!(a.unwrap(), 1);
+        assert_eq!(b.unwrap(), 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(group.coalesced_count(), 0);
+    }
+}
@@ -1,4 +1,6 @@
 use crate::config::Config;
+use crate::jobs::JobRegistry;
+use crate::metrics::Metrics;
 use crate::spanner::SpannerClient;
 use std::sync::Arc;
 
@@ -7,4 +9,8 @@ use std::sync::Arc;
 pub struct AppState {
     pub spanner_client: SpannerClient,
     pub config: Arc<Config>,
+    /// Long-running operations, inspectable through the admin endpoints
+    pub jobs: Arc<JobRegistry>,
+    /// Prometheus recorder shared with the Spanner client, rendered at `/metrics`
+    pub metrics: Metrics,
 }
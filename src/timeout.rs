@@ -0,0 +1,74 @@
+use crate::error::ApiError;
+use axum::{
+    error_handling::HandleErrorLayer,
+    response::{IntoResponse, Response},
+    BoxError, Router,
+};
+use std::time::Duration;
+use tower::{timeout::error::Elapsed, ServiceBuilder};
+
+/// Answer requests to `router` that run longer than `duration` with a JSON 504
+///
+/// The handler's future is dropped when the time is up, so a hung Spanner
+/// call can't hold the connection open indefinitely.
+pub fn apply<S>(router: Router<S>, duration: Duration) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(move |err: BoxError| async move {
+                timeout_response(err, duration)
+            }))
+            .timeout(duration),
+    )
+}
+
+fn timeout_response(err: BoxError, duration: Duration) -> Response {
+    if err.is::<Elapsed>() {
+        ApiError::Timeout(duration).into_response()
+    } else {
+        ApiError::DatabaseError(anyhow::anyhow!("Unhandled internal error: {}", err)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorResponse;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        let router = Router::new()
+            .route("/slow", get(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                "done"
+            }))
+            .route("/fast", get(|| async { "done" }));
+        apply(router, Duration::from_millis(50))
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_times_out_with_json_504() {
+        let response = app()
+            .oneshot(Request::builder().uri("/slow").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.error, "Request timed out after 50ms");
+
+        let response = app()
+            .oneshot(Request::builder().uri("/fast").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
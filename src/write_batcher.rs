@@ -0,0 +1,267 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// A write waiting to be committed, with the channel used to report its outcome
+struct PendingWrite<T, R> {
+    item: T,
+    done: oneshot::Sender<Result<R, Arc<anyhow::Error>>>,
+}
+
+/// Coalesces independent writes into batched commits
+///
+/// Submitted items are buffered and committed together by a background flusher,
+/// either when the batching window elapses or when the batch reaches its maximum
+/// size. Each submitter waits for the commit of its batch, so a successful return
+/// still means the write is durable. If a commit fails, every write in that batch
+/// receives the error.
+///
+/// A successful commit returns one result per item, in submission order, and
+/// each submitter gets its own (e.g. the version its write produced).
+pub struct WriteBatcher<T, R = ()> {
+    sender: Mutex<Option<mpsc::Sender<PendingWrite<T, R>>>>,
+    flusher: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl<T: Send + 'static, R: Send + 'static> WriteBatcher<T, R> {
+    /// Start a batcher whose flusher commits batches with `commit`
+    pub fn spawn<F, Fut>(window: Duration, max_batch_size: usize, commit: F) -> Self
+    where
+        F: Fn(Vec<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<Vec<R>>> + Send + 'static,
+    {
+        let max_batch_size = max_batch_size.max(1);
+        let (sender, receiver) = mpsc::channel(max_batch_size * 2);
+        let flusher = tokio::spawn(run_flusher(receiver, window, max_batch_size, commit));
+
+        Self {
+            sender: Mutex::new(Some(sender)),
+            flusher: Mutex::new(Some(flusher)),
+        }
+    }
+
+    /// Queue an item and wait until the batch containing it has been committed
+    pub async fn submit(&self, item: T) -> anyhow::Result<R> {
+        let sender = self
+            .sender
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Write batcher has been shut down"))?;
+
+        let (done, result) = oneshot::channel();
+        sender
+            .send(PendingWrite { item, done })
+            .await
+            .map_err(|_| anyhow::anyhow!("Write batcher is not running"))?;
+        // Release our handle so shutdown can close the channel while we wait
+        drop(sender);
+
+        result
+            .await
+            .map_err(|_| anyhow::anyhow!("Write batcher stopped before committing"))?
+            .map_err(|err| anyhow::anyhow!("{:#}", err))
+    }
+
+    /// Stop accepting writes and wait for every queued write to be committed
+    pub async fn shutdown(&self) {
+        self.sender.lock().unwrap().take();
+
+        let flusher = self.flusher.lock().unwrap().take();
+        if let Some(flusher) = flusher
+            && let Err(e) = flusher.await
+        {
+            tracing::error!("Write batch flusher terminated abnormally: {}", e);
+        }
+    }
+}
+
+/// Collect writes into batches and commit them until the channel is closed
+async fn run_flusher<T, R, F, Fut>(
+    mut receiver: mpsc::Receiver<PendingWrite<T, R>>,
+    window: Duration,
+    max_batch_size: usize,
+    commit: F,
+) where
+    F: Fn(Vec<T>) -> Fut,
+    Fut: Future<Output = anyhow::Result<Vec<R>>>,
+{
+    while let Some(first) = receiver.recv().await {
+        let mut batch = vec![first];
+        let deadline = tokio::time::sleep(window);
+        tokio::pin!(deadline);
+
+        while batch.len() < max_batch_size {
+            tokio::select! {
+                _ = &mut deadline => break,
+                next = receiver.recv() => match next {
+                    Some(write) => batch.push(write),
+                    // Shutting down - flush what we have immediately
+                    None => break,
+                },
+            }
+        }
+
+        let (items, waiters): (Vec<T>, Vec<_>) =
+            batch.into_iter().map(|write| (write.item, write.done)).unzip();
+
+        let size = items.len();
+        let result = commit(items).await.and_then(|results| {
+            if results.len() == size {
+                Ok(results)
+            } else {
+                Err(anyhow::anyhow!(
+                    "Write batch commit returned {} results for {} items",
+                    results.len(),
+                    size
+                ))
+            }
+        });
+
+        // The submitter may have gone away (e.g. client disconnected), so sends are best effort
+        match result {
+            Ok(results) => {
+                tracing::debug!("Committed write batch of {} items", size);
+                for (waiter, result) in waiters.into_iter().zip(results) {
+                    let _ = waiter.send(Ok(result));
+                }
+            }
+            Err(e) => {
+                tracing::error!("Write batch of {} items failed: {:#}", size, e);
+                let e = Arc::new(e);
+                for waiter in waiters {
+                    let _ = waiter.send(Err(e.clone()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn counting_batcher(
+        window_ms: u64,
+        max_batch_size: usize,
+        commits: Arc<AtomicUsize>,
+        committed: Arc<Mutex<Vec<u32>>>,
+    ) -> Arc<WriteBatcher<u32>> {
+        Arc::new(WriteBatcher::spawn(
+            Duration::from_millis(window_ms),
+            max_batch_size,
+            move |items: Vec<u32>| {
+                let commits = commits.clone();
+                let committed = committed.clone();
+                async move {
+                    commits.fetch_add(1, Ordering::SeqCst);
+                    let results = vec![(); items.len()];
+                    committed.lock().unwrap().extend(items);
+                    Ok(results)
+                }
+            },
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_batches_use_fewer_commits_than_writes() {
+        let commits = Arc::new(AtomicUsize::new(0));
+        let committed = Arc::new(Mutex::new(Vec::new()));
+        let batcher = counting_batcher(20, 100, commits.clone(), committed.clone());
+
+        let mut handles = Vec::new();
+        for i in 0..1000 {
+            let batcher = batcher.clone();
+            handles.push(tokio::spawn(async move { batcher.submit(i).await }));
+        }
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+
+        let commit_count = commits.load(Ordering::SeqCst);
+        assert!(commit_count < 1000, "Expected batching, got {} commits", commit_count);
+        assert!(commit_count >= 10, "Batches must not exceed the max size");
+
+        let mut committed = committed.lock().unwrap().clone();
+        committed.sort();
+        assert_eq!(committed, (0..1000).collect::<Vec<_>>(), "Every write must be committed once");
+    }
+
+    #[tokio::test]
+    async fn test_commit_error_reaches_every_write_in_batch() {
+        let batcher = Arc::new(WriteBatcher::spawn(
+            Duration::from_millis(50),
+            100,
+            |_items: Vec<u32>| async { Err::<Vec<()>, _>(anyhow::anyhow!("commit rejected")) },
+        ));
+
+        let mut handles = Vec::new();
+        for i in 0..10 {
+            let batcher = batcher.clone();
+            handles.push(tokio::spawn(async move { batcher.submit(i).await }));
+        }
+
+        for handle in handles {
+            let err = handle.await.unwrap().unwrap_err();
+            assert!(err.to_string().contains("commit rejected"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_each_write_gets_its_own_result() {
+        let batcher = Arc::new(WriteBatcher::spawn(
+            Duration::from_millis(20),
+            100,
+            |items: Vec<u32>| async move { Ok(items.into_iter().map(|i| i * 10).collect()) },
+        ));
+
+        let mut handles = Vec::new();
+        for i in 0..50 {
+            let batcher = batcher.clone();
+            handles.push(tokio::spawn(async move { (i, batcher.submit(i).await) }));
+        }
+        for handle in handles {
+            let (i, result) = handle.await.unwrap();
+            assert_eq!(result.unwrap(), i * 10);
+        }
+
+        // A commit that loses track of results fails every write rather than misattributing them
+        let batcher = WriteBatcher::spawn(
+            Duration::from_millis(1),
+            100,
+            |_items: Vec<u32>| async { Ok(Vec::<u32>::new()) },
+        );
+        let err = batcher.submit(1).await.unwrap_err();
+        assert!(err.to_string().contains("0 results for 1 items"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_flushes_pending_writes() {
+        let commits = Arc::new(AtomicUsize::new(0));
+        let committed = Arc::new(Mutex::new(Vec::new()));
+        // A window far longer than the test, so only shutdown can trigger the flush
+        let batcher = counting_batcher(60_000, 100, commits.clone(), committed.clone());
+
+        let mut handles = Vec::new();
+        for i in 0..5 {
+            let batcher = batcher.clone();
+            handles.push(tokio::spawn(async move { batcher.submit(i).await }));
+        }
+
+        // Let the writes reach the flusher before shutting down
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        batcher.shutdown().await;
+
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+        assert_eq!(commits.load(Ordering::SeqCst), 1);
+        assert_eq!(committed.lock().unwrap().len(), 5);
+
+        let err = batcher.submit(99).await.unwrap_err();
+        assert!(err.to_string().contains("shut down"));
+    }
+}